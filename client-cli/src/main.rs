@@ -3,6 +3,7 @@ extern crate clap;
 extern crate env_logger;
 extern crate redbackup_client;
 
+use std::env;
 use std::thread;
 use std::process;
 use std::sync::mpsc;
@@ -10,7 +11,8 @@ use std::sync::mpsc::{Sender, Receiver};
 
 use redbackup_client::config::{Config, ParseError};
 use redbackup_client::{CreateBackupConfig, CreateBackupConfigError, RestoreBackupConfig,
-                       RestoreBackupConfigError, Progress};
+                       RestoreBackupConfigError, Progress, ChunkIndexSource, ListBackupsFilter,
+                       BackupSelector};
 
 use clap::{App, Arg, SubCommand};
 
@@ -43,6 +45,14 @@ fn main() {
                 .takes_value(true)
                 .default_value("/tmp/"),
         )
+        .arg(
+            Arg::with_name("key-file")
+                .help("Encrypt chunks with a key derived from FILE")
+                .long_help("Encrypt and decrypt all chunks with a key derived from the content of FILE. Alternatively, a passphrase can be given with the environment variable REDBACKUP_PASSPHRASE. Backups can only be restored with the same key.")
+                .long("key-file")
+                .takes_value(true)
+                .value_name("FILE"),
+        )
         .subcommand(
             SubCommand::with_name("create")
                 .about("Create a new backup")
@@ -63,33 +73,337 @@ fn main() {
                         .long("exclude-from")
                         .takes_value(true)
                         .value_name("FILE")
-                    ),
+                    )
+                .arg(
+                    Arg::with_name("compression")
+                        .help("Compress chunks with the given codec")
+                        .long("compression")
+                        .takes_value(true)
+                        .possible_values(&["none", "zstd", "lz4"])
+                        .default_value("none"),
+                )
+                .arg(
+                    Arg::with_name("batch-size")
+                        .help("Maximum number of chunk bytes to send in one message")
+                        .long("batch-size")
+                        .takes_value(true)
+                        .value_name("BYTES"),
+                )
+                .arg(
+                    Arg::with_name("concurrency")
+                        .help("Number of messages to send to the node at the same time")
+                        .long("concurrency")
+                        .takes_value(true)
+                        .value_name("N"),
+                )
+                .arg(
+                    Arg::with_name("follow-symlinks")
+                        .help("Back up the files and folders symlinks point to")
+                        .long_help("Back up the files and folders symlinks point to, instead of the symlinks themselves. Symlinks to folders, that contain the symlink, are skipped.")
+                        .long("follow-symlinks"),
+                )
+                .arg(
+                    Arg::with_name("xattr-include")
+                        .help("Back up only extended attributes of the given namespaces")
+                        .long_help("Back up only extended attributes of the given comma separated namespaces (user, trusted, security, system). POSIX ACLs are stored in the system namespace, SELinux labels in the security namespace. By default, all namespaces are included.")
+                        .long("xattr-include")
+                        .takes_value(true)
+                        .value_name("NAMESPACES"),
+                )
+                .arg(
+                    Arg::with_name("xattr-exclude")
+                        .help("Do not back up extended attributes of the given namespaces")
+                        .long("xattr-exclude")
+                        .takes_value(true)
+                        .value_name("NAMESPACES"),
+                )
+                .arg(
+                    Arg::with_name("label")
+                        .help("Name of the backup, as shown in the list of backups")
+                        .long("label")
+                        .takes_value(true)
+                        .value_name("LABEL"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .help("Tag the backup (can be given several times)")
+                        .long("tag")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("TAG"),
+                )
+                .arg(
+                    Arg::with_name("resume")
+                        .help("Continue the latest unfinished backup of the directory")
+                        .long_help("Continue the latest unfinished backup of the directory (e.g. after a crash or network error), instead of building a new chunk index. Only the chunks that were not acknowledged by the node are sent. Without an unfinished backup, a new backup is created.")
+                        .long("resume"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("list")
-                .about("List available backups on the node."),
+                .about("List available backups on the node.")
+                .arg(
+                    Arg::with_name("host")
+                        .help("List only backups created on HOST")
+                        .long("host")
+                        .takes_value(true)
+                        .value_name("HOST"),
+                )
+                .arg(
+                    Arg::with_name("source-path")
+                        .help("List only backups of the absolute PATH")
+                        .long("source-path")
+                        .takes_value(true)
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .help("List only backups with the given tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .value_name("TAG"),
+                )
+                .arg(
+                    Arg::with_name("label")
+                        .help("List only backups with the given label")
+                        .long("label")
+                        .takes_value(true)
+                        .value_name("LABEL"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("browse")
+                .about("List the folders and files of a backup.")
+                .arg(
+                    Arg::with_name("backup-id")
+                        .help("ID of the backup that should be browsed")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .help("Folder or file to list, relative to the backup root"),
+                )
+                .arg(
+                    Arg::with_name("recursive")
+                        .help("List the content of subfolders")
+                        .short("r")
+                        .long("recursive"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("List the changes between two backups.")
+                .arg(
+                    Arg::with_name("old")
+                        .help("ID or chunk index file of the older backup")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .help("ID or chunk index file of the newer backup")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .help("Print the changes as JSON")
+                        .long("json"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Compare a local directory with a backup, without writing any files.")
+                .arg(
+                    Arg::with_name("backup-id")
+                        .help("ID of the backup to verify, or 'latest'")
+                        .long_help("ID of the backup to verify, or 'latest' to verify the most recent backup that matches the --host, --source-path, --tag, --label and --before options.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("local-dir")
+                        .help("Directory to compare with the backup root (e.g. a restored backup)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("host")
+                        .help("Select the latest backup created on HOST")
+                        .long("host")
+                        .takes_value(true)
+                        .value_name("HOST"),
+                )
+                .arg(
+                    Arg::with_name("source-path")
+                        .help("Select the latest backup of the absolute PATH")
+                        .long("source-path")
+                        .takes_value(true)
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .help("Select the latest backup with the given tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .value_name("TAG"),
+                )
+                .arg(
+                    Arg::with_name("label")
+                        .help("Select the latest backup with the given label")
+                        .long("label")
+                        .takes_value(true)
+                        .value_name("LABEL"),
+                )
+                .arg(
+                    Arg::with_name("before")
+                        .help("Select the latest backup created before DATE (format: %Y-%m-%dT%H:%M)")
+                        .long("before")
+                        .takes_value(true)
+                        .value_name("DATE"),
+                )
+                .arg(
+                    Arg::with_name("deep")
+                        .help("Also fetch every chunk from the node and check its hash")
+                        .long("deep"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("restore")
                 .about("List available backups on the node.")
                 .arg(
                     Arg::with_name("backup-id")
-                        .help("ID of the backup that should be restored")
+                        .help("ID of the backup that should be restored, or 'latest'")
+                        .long_help("ID of the backup that should be restored, or 'latest' to restore the most recent backup that matches the --host, --source-path, --tag, --label and --before options.")
                         .required(true),
                 )
                 .arg(
                     Arg::with_name("local-restore-dir")
                         .help("Destionation, where the files should be restored to.")
                         .required(true),
+                )
+                .arg(
+                    Arg::with_name("host")
+                        .help("Select the latest backup created on HOST")
+                        .long("host")
+                        .takes_value(true)
+                        .value_name("HOST"),
+                )
+                .arg(
+                    Arg::with_name("source-path")
+                        .help("Select the latest backup of the absolute PATH")
+                        .long("source-path")
+                        .takes_value(true)
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .help("Select the latest backup with the given tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .value_name("TAG"),
+                )
+                .arg(
+                    Arg::with_name("label")
+                        .help("Select the latest backup with the given label")
+                        .long("label")
+                        .takes_value(true)
+                        .value_name("LABEL"),
+                )
+                .arg(
+                    Arg::with_name("before")
+                        .help("Select the latest backup created before DATE (format: %Y-%m-%dT%H:%M)")
+                        .long("before")
+                        .takes_value(true)
+                        .value_name("DATE"),
+                )
+                .arg(
+                    Arg::with_name("include")
+                        .help("Restore only paths matching the glob PATTERN")
+                        .long_help("Restore only paths matching the glob PATTERN. The option can be given multiple times. Patterns are relative to the backup root, e.g. 'pictures/**/*.jpg'. A matching folder is restored with its whole content. For allowed glob syntax, see https://docs.rs/glob/0/glob/struct.Pattern.html#main")
+                        .long("include")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("PATTERN"),
+                )
+                .arg(
+                    Arg::with_name("batch-size")
+                        .help("Maximum number of chunk bytes to request in one message")
+                        .long("batch-size")
+                        .takes_value(true)
+                        .value_name("BYTES"),
+                )
+                .arg(
+                    Arg::with_name("concurrency")
+                        .help("Number of messages to send to the node at the same time")
+                        .long("concurrency")
+                        .takes_value(true)
+                        .value_name("N"),
+                )
+                .arg(
+                    Arg::with_name("skip-ownership")
+                        .help("Do not restore the owner and group of files and folders")
+                        .long_help("Do not restore the owner and group of files and folders. Restoring the ownership requires root privileges, so it is skipped when restoring as a regular user.")
+                        .long("skip-ownership"),
+                )
+                .arg(
+                    Arg::with_name("on-conflict")
+                        .help("What to do with files that already exist")
+                        .long_help("What to do with files that already exist in the restore directory: abort the restore before anything is written (fail), keep the existing files (skip), replace them (overwrite), replace them unless the content is the same (overwrite-if-different) or restore the files with the suffix '.restored' (rename).")
+                        .long("on-conflict")
+                        .takes_value(true)
+                        .possible_values(
+                            &["fail", "skip", "overwrite", "overwrite-if-different", "rename"],
+                        )
+                        .default_value("fail")
+                        .value_name("POLICY"),
+                )
+                .arg(
+                    Arg::with_name("xattr-include")
+                        .help("Restore only extended attributes of the given namespaces")
+                        .long_help("Restore only extended attributes of the given comma separated namespaces (user, trusted, security, system). POSIX ACLs are stored in the system namespace, SELinux labels in the security namespace. By default, all namespaces are included.")
+                        .long("xattr-include")
+                        .takes_value(true)
+                        .value_name("NAMESPACES"),
+                )
+                .arg(
+                    Arg::with_name("xattr-exclude")
+                        .help("Do not restore extended attributes of the given namespaces")
+                        .long("xattr-exclude")
+                        .takes_value(true)
+                        .value_name("NAMESPACES"),
+                ),
+        );
+    #[cfg(feature = "mount")]
+    {
+        app = app.subcommand(
+            SubCommand::with_name("mount")
+                .about("Mount a backup as read-only file system (until unmounted).")
+                .arg(
+                    Arg::with_name("backup-id")
+                        .help("ID of the backup that should be mounted")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("mountpoint")
+                        .help("Empty folder to mount the backup at")
+                        .required(true),
                 ),
         );
+    }
     let matches = app.clone().get_matches();
 
     let node_host = matches.value_of("node-hostname").unwrap();
     let node_port = matches.value_of("node-port").unwrap();
     let chunk_index_storage = matches.value_of("chunk-index-storage").unwrap();
+    let key_file = matches.value_of("key-file");
+    let passphrase = env::var("REDBACKUP_PASSPHRASE").ok();
 
-    let config = Config::new(node_host, node_port, chunk_index_storage).unwrap_or_else(|err| {
+    let config = Config::new(
+        node_host,
+        node_port,
+        chunk_index_storage,
+        key_file,
+        passphrase.as_ref().map(|p| p.as_str()),
+    ).unwrap_or_else(|err| {
         match err {
             ParseError::InvalidHostname(err) => {
                 eprintln!("The given hostname is invalid ({})", err)
@@ -100,6 +414,9 @@ fn main() {
             ParseError::InvalidChunkIndexStorage(err) => {
                 eprintln!("The given chunk index storage could not be used ({})", err)
             }
+            ParseError::InvalidKeyFile(err) => {
+                eprintln!("The given key file could not be used ({})", err)
+            }
         };
         process::exit(1);
     });
@@ -111,11 +428,32 @@ fn main() {
             let local_backup_dir = matches_create.value_of("local-backup-dir").unwrap();
             let expiration_date = matches_create.value_of("expiration-date").unwrap();
             let exclude_from = matches_create.value_of("exclude-from");
+            let compression = matches_create.value_of("compression");
+            let batch_size = matches_create.value_of("batch-size");
+            let concurrency = matches_create.value_of("concurrency");
+            let follow_symlinks = matches_create.is_present("follow-symlinks");
+            let xattr_include = matches_create.value_of("xattr-include");
+            let xattr_exclude = matches_create.value_of("xattr-exclude");
+            let label = matches_create.value_of("label");
+            let tags: Vec<&str> = matches_create
+                .values_of("tag")
+                .map(|values| values.collect())
+                .unwrap_or_default();
+            let resume = matches_create.is_present("resume");
 
             let backup_cfg = CreateBackupConfig::new(
                 local_backup_dir,
                 expiration_date,
                 exclude_from,
+                compression,
+                batch_size,
+                concurrency,
+                follow_symlinks,
+                xattr_include,
+                xattr_exclude,
+                label,
+                &tags,
+                resume,
             ).unwrap_or_else(|err| {
                 match err {
                     CreateBackupConfigError::NonExistingDirectory(err) => {
@@ -142,6 +480,21 @@ fn main() {
                             err
                         );
                     }
+                    CreateBackupConfigError::InvalidCompression(err) => {
+                        eprintln!("The given compression '{}' is not supported", err)
+                    }
+                    CreateBackupConfigError::InvalidBatchSize(err) => {
+                        eprintln!("The given batch size '{}' is not a positive number", err)
+                    }
+                    CreateBackupConfigError::InvalidConcurrency(err) => {
+                        eprintln!("The given concurrency '{}' is not a positive number", err)
+                    }
+                    CreateBackupConfigError::InvalidXattrNamespace(err) => {
+                        eprintln!("The given extended attribute namespace '{}' is unknown", err)
+                    }
+                    CreateBackupConfigError::InvalidTag(err) => {
+                        eprintln!("The given tag '{}' is empty or contains whitespace", err)
+                    }
                 };
                 process::exit(1);
             });
@@ -151,13 +504,173 @@ fn main() {
                 .unwrap_or_else(|err| handle_error(err));
         }
 
-        ("list", _) => {
-            match redbackup_client::list_backups(config) {
+        ("list", Some(matches_list)) => {
+            let filter = ListBackupsFilter::new(
+                matches_list.value_of("host"),
+                matches_list.value_of("source-path"),
+                matches_list.value_of("tag"),
+                matches_list.value_of("label"),
+            );
+            match redbackup_client::list_backups(config, &filter) {
                 Err(err) => handle_error(err),
                 Ok(available_backups) => {
-                    println!("{:64} Expiration Date", "Backup ID"); // Backup ID length is hash dependent.
+                    println!(
+                        "{:64} {:23} {:19} {:16} {:>8} {:>12} Label / Tags / Source Path",
+                        "Backup ID", // Backup ID length is hash dependent.
+                        "Expiration Date",
+                        "Creation Date",
+                        "Host",
+                        "Files",
+                        "Size"
+                    );
                     for backup in available_backups {
-                        println!("{} {}", backup.0, backup.1);
+                        let expiration_date =
+                            backup.expiration_date.format("%Y-%m-%d %H:%M:%S UTC");
+                        match backup.manifest {
+                            Some(manifest) => {
+                                let or_dash = |value: Option<String>| value.unwrap_or("-".into());
+                                println!(
+                                    "{:64} {:23} {:19} {:16} {:>8} {:>12} {} [{}] {}",
+                                    backup.backup_id,
+                                    expiration_date.to_string(),
+                                    manifest.creation_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+                                    or_dash(manifest.hostname),
+                                    or_dash(manifest.file_count.map(|c| c.to_string())),
+                                    or_dash(manifest.total_size.map(|s| s.to_string())),
+                                    or_dash(manifest.label),
+                                    manifest.tags.join(", "),
+                                    manifest.source_path
+                                );
+                            }
+                            None => println!("{:64} {}", backup.backup_id, expiration_date),
+                        }
+                    }
+                }
+            }
+        }
+
+        ("browse", Some(matches_browse)) => {
+            let backup_id = matches_browse.value_of("backup-id").unwrap();
+            let path = matches_browse.value_of("path");
+            let recursive = matches_browse.is_present("recursive");
+            match redbackup_client::browse_backup(config, backup_id, path, recursive) {
+                Err(err) => handle_error(err),
+                Ok(entries) => {
+                    println!("{:12} {:>12} {:19} Path", "Type", "Size", "Last Change");
+                    for entry in entries {
+                        let size = entry.size.map(|s| s.to_string()).unwrap_or("-".into());
+                        let last_change_date = entry
+                            .last_change_date
+                            .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+                            .unwrap_or("-".into());
+                        println!(
+                            "{:12} {:>12} {:19} {}",
+                            entry.kind,
+                            size,
+                            last_change_date,
+                            entry.path.display()
+                        );
+                    }
+                }
+            }
+        }
+
+        ("diff", Some(matches_diff)) => {
+            let old = ChunkIndexSource::new(matches_diff.value_of("old").unwrap());
+            let new = ChunkIndexSource::new(matches_diff.value_of("new").unwrap());
+            match redbackup_client::diff_backups(config, &old, &new) {
+                Err(err) => handle_error(err),
+                Ok(changes) => {
+                    if matches_diff.is_present("json") {
+                        match redbackup_client::diff_backups::to_json(&changes) {
+                            Ok(json) => println!("{}", json),
+                            Err(err) => handle_error(err),
+                        }
+                    } else {
+                        for change in changes {
+                            let details = if change.details.is_empty() {
+                                String::new()
+                            } else {
+                                format!(" ({})", change.details.join(", "))
+                            };
+                            println!(
+                                "{:16} {:12} {}{}",
+                                change.change.name(),
+                                change.kind,
+                                change.path.display(),
+                                details
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        ("verify", Some(matches_verify)) => {
+            let backup_id = matches_verify.value_of("backup-id").unwrap();
+            let local_dir = std::path::Path::new(matches_verify.value_of("local-dir").unwrap());
+            let deep = matches_verify.is_present("deep");
+            let filter = ListBackupsFilter::new(
+                matches_verify.value_of("host"),
+                matches_verify.value_of("source-path"),
+                matches_verify.value_of("tag"),
+                matches_verify.value_of("label"),
+            );
+            let before = matches_verify.value_of("before");
+            let backup = BackupSelector::new(backup_id, filter, before).unwrap_or_else(|err| {
+                match err {
+                    RestoreBackupConfigError::InvalidBackupId(err) => {
+                        eprintln!("The given backup ID '{}' is invalid", err)
+                    }
+                    RestoreBackupConfigError::SelectorWithBackupId(err) => {
+                        eprintln!("The backup ID '{}' can not be combined with selectors", err)
+                    }
+                    RestoreBackupConfigError::InvalidDateFormat(err) => {
+                        eprintln!(
+                            "The given date '{}' can not be parsed (format: %Y-%m-%dT%H:%M)",
+                            err
+                        )
+                    }
+                    err => handle_error(err),
+                };
+                process::exit(1);
+            });
+            let progress_sender = initialize_progress_observer();
+            match redbackup_client::verify_backup(
+                config,
+                &backup,
+                local_dir,
+                deep,
+                progress_sender,
+            ) {
+                Err(err) => handle_error(err),
+                Ok(report) => {
+                    for mismatch in &report.mismatches {
+                        let detail = match mismatch.detail {
+                            Some(ref detail) => format!(" ({})", detail),
+                            None => String::new(),
+                        };
+                        println!(
+                            "{:10} {}{}",
+                            mismatch.kind.name(),
+                            mismatch.path.display(),
+                            detail
+                        );
+                    }
+                    for chunk_identifier in &report.missing_chunks {
+                        println!("{:10} chunk {}", "missing", chunk_identifier);
+                    }
+                    for chunk_identifier in &report.corrupt_chunks {
+                        println!("{:10} chunk {}", "corrupt", chunk_identifier);
+                    }
+                    println!(
+                        "Checked {} files and folders and {} chunks: {}",
+                        report.checked_entries,
+                        report.checked_chunks,
+                        if report.is_ok() { "OK" } else { "FAILED" }
+                    );
+                    if !report.is_ok() {
+                        process::exit(1);
                     }
                 }
             }
@@ -166,7 +679,37 @@ fn main() {
         ("restore", Some(matches_restore)) => {
             let local_restore_dir = matches_restore.value_of("local-restore-dir").unwrap();
             let backup_id = matches_restore.value_of("backup-id").unwrap();
-            let restore_cfg = RestoreBackupConfig::new(backup_id, local_restore_dir)
+            let include: Vec<&str> = matches_restore
+                .values_of("include")
+                .map(|values| values.collect())
+                .unwrap_or_default();
+            let batch_size = matches_restore.value_of("batch-size");
+            let concurrency = matches_restore.value_of("concurrency");
+            let skip_ownership = matches_restore.is_present("skip-ownership");
+            let xattr_include = matches_restore.value_of("xattr-include");
+            let xattr_exclude = matches_restore.value_of("xattr-exclude");
+            let filter = ListBackupsFilter::new(
+                matches_restore.value_of("host"),
+                matches_restore.value_of("source-path"),
+                matches_restore.value_of("tag"),
+                matches_restore.value_of("label"),
+            );
+            let before = matches_restore.value_of("before");
+            let conflict_policy = matches_restore.value_of("on-conflict");
+            let restore_cfg = BackupSelector::new(backup_id, filter, before)
+                .and_then(|backup| {
+                    RestoreBackupConfig::new(
+                        backup,
+                        local_restore_dir,
+                        &include,
+                        batch_size,
+                        concurrency,
+                        skip_ownership,
+                        xattr_include,
+                        xattr_exclude,
+                        conflict_policy,
+                    )
+                })
                 .unwrap_or_else(|err| {
                     match err {
                         RestoreBackupConfigError::NonExistingDirectory(err) => {
@@ -175,6 +718,33 @@ fn main() {
                         RestoreBackupConfigError::InvalidBackupId(err) => {
                             eprintln!("The given backup ID '{}' is invalid", err)
                         }
+                        RestoreBackupConfigError::SelectorWithBackupId(err) => {
+                            eprintln!(
+                                "The backup ID '{}' can not be combined with selectors",
+                                err
+                            )
+                        }
+                        RestoreBackupConfigError::InvalidDateFormat(err) => {
+                            eprintln!(
+                                "The given date '{}' can not be parsed (format: %Y-%m-%dT%H:%M)",
+                                err
+                            )
+                        }
+                        RestoreBackupConfigError::IncludePatternError(err) => {
+                            eprintln!("Invalid include glob specified ({:?})", err)
+                        }
+                        RestoreBackupConfigError::InvalidBatchSize(err) => {
+                            eprintln!("The given batch size '{}' is not a positive number", err)
+                        }
+                        RestoreBackupConfigError::InvalidConcurrency(err) => {
+                            eprintln!("The given concurrency '{}' is not a positive number", err)
+                        }
+                        RestoreBackupConfigError::InvalidXattrNamespace(err) => {
+                            eprintln!("The given extended attribute namespace '{}' is unknown", err)
+                        }
+                        RestoreBackupConfigError::InvalidConflictPolicy(err) => {
+                            eprintln!("The given conflict policy '{}' is not supported", err)
+                        }
                     };
                     process::exit(1);
                 });
@@ -183,6 +753,14 @@ fn main() {
                 .unwrap_or_else(|err| handle_error(err));
         }
 
+        #[cfg(feature = "mount")]
+        ("mount", Some(matches_mount)) => {
+            let backup_id = matches_mount.value_of("backup-id").unwrap();
+            let mountpoint = std::path::Path::new(matches_mount.value_of("mountpoint").unwrap());
+            redbackup_client::mount_backup(config, backup_id, mountpoint)
+                .unwrap_or_else(|err| handle_error(err));
+        }
+
         (&_, _) => app.print_help().expect("Could not get help options"),
     }
 }
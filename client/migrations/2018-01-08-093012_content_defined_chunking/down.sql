@@ -0,0 +1,16 @@
+ALTER TABLE chunks RENAME TO chunks_new;
+
+CREATE TABLE chunks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    chunk_identifier TEXT NOT NULL,
+    file INTEGER NOT NULL,
+    predecessor INTEGER,
+    UNIQUE(file,chunk_identifier),
+    FOREIGN KEY(file) REFERENCES files(id),
+    FOREIGN KEY(predecessor) REFERENCES chunks(id)
+);
+
+INSERT OR IGNORE INTO chunks (id, chunk_identifier, file, predecessor)
+    SELECT id, chunk_identifier, file, predecessor FROM chunks_new;
+
+DROP TABLE chunks_new;
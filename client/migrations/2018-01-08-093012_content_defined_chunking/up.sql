@@ -0,0 +1,19 @@
+-- A file is now split into multiple chunks, which may share the same identifier
+-- (e.g. zero-filled blocks), so the chunk table is recreated without the unique constraint.
+ALTER TABLE chunks RENAME TO chunks_old;
+
+CREATE TABLE chunks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    chunk_identifier TEXT NOT NULL,
+    file INTEGER NOT NULL,
+    predecessor INTEGER,
+    chunk_offset BIGINT NOT NULL DEFAULT 0,
+    chunk_size BIGINT NOT NULL DEFAULT 0,
+    FOREIGN KEY(file) REFERENCES files(id),
+    FOREIGN KEY(predecessor) REFERENCES chunks(id)
+);
+
+INSERT INTO chunks (id, chunk_identifier, file, predecessor)
+    SELECT id, chunk_identifier, file, predecessor FROM chunks_old;
+
+DROP TABLE chunks_old;
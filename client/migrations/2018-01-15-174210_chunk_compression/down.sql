@@ -0,0 +1,18 @@
+-- SQLite does not support dropping columns, so the chunk table has to be recreated.
+ALTER TABLE chunks RENAME TO chunks_new;
+
+CREATE TABLE chunks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    chunk_identifier TEXT NOT NULL,
+    file INTEGER NOT NULL,
+    predecessor INTEGER,
+    chunk_offset BIGINT NOT NULL DEFAULT 0,
+    chunk_size BIGINT NOT NULL DEFAULT 0,
+    FOREIGN KEY(file) REFERENCES files(id),
+    FOREIGN KEY(predecessor) REFERENCES chunks(id)
+);
+
+INSERT INTO chunks (id, chunk_identifier, file, predecessor, chunk_offset, chunk_size)
+    SELECT id, chunk_identifier, file, predecessor, chunk_offset, chunk_size FROM chunks_new;
+
+DROP TABLE chunks_new;
@@ -0,0 +1,17 @@
+DROP TABLE manifest;
+
+-- SQLite does not support dropping columns, so the file table has to be recreated.
+ALTER TABLE files RENAME TO files_new;
+
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    last_change_date DATETIME NOT NULL,
+    folder INTEGER NOT NULL,
+    FOREIGN KEY(folder) REFERENCES folders(id)
+);
+
+INSERT INTO files (id, name, last_change_date, folder)
+    SELECT id, name, last_change_date, folder FROM files_new;
+
+DROP TABLE files_new;
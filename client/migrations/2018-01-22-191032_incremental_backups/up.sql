@@ -0,0 +1,10 @@
+-- The size of a file is required to detect unchanged files for incremental backups.
+ALTER TABLE files ADD COLUMN size BIGINT NOT NULL DEFAULT 0;
+
+-- Describes the backup of a chunk index (there is exactly one row per chunk index).
+CREATE TABLE manifest (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    source_path TEXT NOT NULL,
+    creation_date DATETIME NOT NULL,
+    key_fingerprint TEXT
+);
@@ -0,0 +1,30 @@
+-- SQLite does not support dropping columns, so the tables have to be recreated.
+ALTER TABLE files RENAME TO files_new;
+
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    last_change_date DATETIME NOT NULL,
+    folder INTEGER NOT NULL,
+    size BIGINT NOT NULL DEFAULT 0,
+    FOREIGN KEY(folder) REFERENCES folders(id)
+);
+
+INSERT INTO files (id, name, last_change_date, folder, size)
+    SELECT id, name, last_change_date, folder, size FROM files_new;
+
+DROP TABLE files_new;
+
+ALTER TABLE folders RENAME TO folders_new;
+
+CREATE TABLE folders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    parent_folder INTEGER,
+    FOREIGN KEY(parent_folder) REFERENCES folders(id)
+);
+
+INSERT INTO folders (id, name, parent_folder)
+    SELECT id, name, parent_folder FROM folders_new;
+
+DROP TABLE folders_new;
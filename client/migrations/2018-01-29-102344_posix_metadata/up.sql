@@ -0,0 +1,12 @@
+-- POSIX metadata of files and folders. The columns are nullable, as chunk indices of older
+-- backups do not contain them.
+ALTER TABLE files ADD COLUMN mode INTEGER;
+ALTER TABLE files ADD COLUMN uid BIGINT;
+ALTER TABLE files ADD COLUMN gid BIGINT;
+ALTER TABLE files ADD COLUMN access_date DATETIME;
+
+ALTER TABLE folders ADD COLUMN mode INTEGER;
+ALTER TABLE folders ADD COLUMN uid BIGINT;
+ALTER TABLE folders ADD COLUMN gid BIGINT;
+ALTER TABLE folders ADD COLUMN last_change_date DATETIME;
+ALTER TABLE folders ADD COLUMN access_date DATETIME;
@@ -0,0 +1,21 @@
+-- SQLite does not support dropping columns, so the file table has to be recreated.
+ALTER TABLE files RENAME TO files_new;
+
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    last_change_date DATETIME NOT NULL,
+    folder INTEGER NOT NULL,
+    size BIGINT NOT NULL DEFAULT 0,
+    mode INTEGER,
+    uid BIGINT,
+    gid BIGINT,
+    access_date DATETIME,
+    FOREIGN KEY(folder) REFERENCES folders(id)
+);
+
+INSERT INTO files (id, name, last_change_date, folder, size, mode, uid, gid, access_date)
+    SELECT id, name, last_change_date, folder, size, mode, uid, gid, access_date
+    FROM files_new WHERE file_type = 'regular';
+
+DROP TABLE files_new;
@@ -0,0 +1,8 @@
+-- Besides regular files, the file table contains symlinks, hardlinks, FIFOs and device nodes.
+ALTER TABLE files ADD COLUMN file_type TEXT NOT NULL DEFAULT 'regular';
+-- Target path of a symlink.
+ALTER TABLE files ADD COLUMN link_target TEXT;
+-- The file (with the same inode), a hardlink points to.
+ALTER TABLE files ADD COLUMN hardlink INTEGER REFERENCES files(id);
+-- Device number of a device node.
+ALTER TABLE files ADD COLUMN device BIGINT;
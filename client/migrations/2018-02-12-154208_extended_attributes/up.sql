@@ -0,0 +1,10 @@
+-- Extended attributes (including POSIX ACLs) of a file or a folder.
+CREATE TABLE xattrs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    file INTEGER,
+    folder INTEGER,
+    name TEXT NOT NULL,
+    value BLOB NOT NULL,
+    FOREIGN KEY(file) REFERENCES files(id),
+    FOREIGN KEY(folder) REFERENCES folders(id)
+);
@@ -0,0 +1,47 @@
+-- SQLite does not support dropping columns, so the tables have to be recreated.
+ALTER TABLE files RENAME TO files_new;
+
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    last_change_date DATETIME NOT NULL,
+    folder INTEGER NOT NULL,
+    size BIGINT NOT NULL DEFAULT 0,
+    mode INTEGER,
+    uid BIGINT,
+    gid BIGINT,
+    access_date DATETIME,
+    file_type TEXT NOT NULL DEFAULT 'regular',
+    link_target TEXT,
+    hardlink INTEGER REFERENCES files(id),
+    device BIGINT,
+    FOREIGN KEY(folder) REFERENCES folders(id)
+);
+
+INSERT INTO files (id, name, last_change_date, folder, size, mode, uid, gid, access_date,
+                   file_type, link_target, hardlink, device)
+    SELECT id, name, last_change_date, folder, size, mode, uid, gid, access_date,
+           file_type, link_target, hardlink, device
+    FROM files_new;
+
+DROP TABLE files_new;
+
+ALTER TABLE folders RENAME TO folders_new;
+
+CREATE TABLE folders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    parent_folder INTEGER,
+    mode INTEGER,
+    uid BIGINT,
+    gid BIGINT,
+    last_change_date DATETIME,
+    access_date DATETIME,
+    FOREIGN KEY(parent_folder) REFERENCES folders(id)
+);
+
+INSERT INTO folders (id, name, parent_folder, mode, uid, gid, last_change_date, access_date)
+    SELECT id, name, parent_folder, mode, uid, gid, last_change_date, access_date
+    FROM folders_new;
+
+DROP TABLE folders_new;
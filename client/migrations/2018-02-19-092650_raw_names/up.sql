@@ -0,0 +1,5 @@
+-- Names that are not valid unicode are stored as raw bytes, the name column contains a lossy
+-- representation of it. The raw columns are NULL for valid unicode names.
+ALTER TABLE files ADD COLUMN raw_name BLOB;
+ALTER TABLE files ADD COLUMN raw_link_target BLOB;
+ALTER TABLE folders ADD COLUMN raw_name BLOB;
@@ -0,0 +1,16 @@
+DROP TABLE tags;
+
+-- SQLite does not support dropping columns, so the manifest table has to be recreated.
+ALTER TABLE manifest RENAME TO manifest_new;
+
+CREATE TABLE manifest (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    source_path TEXT NOT NULL,
+    creation_date DATETIME NOT NULL,
+    key_fingerprint TEXT
+);
+
+INSERT INTO manifest (id, source_path, creation_date, key_fingerprint)
+    SELECT id, source_path, creation_date, key_fingerprint FROM manifest_new;
+
+DROP TABLE manifest_new;
@@ -0,0 +1,11 @@
+-- Identifies the backup in the list of backups (None for chunk indices of older clients).
+ALTER TABLE manifest ADD COLUMN hostname TEXT;
+ALTER TABLE manifest ADD COLUMN label TEXT;
+ALTER TABLE manifest ADD COLUMN file_count BIGINT;
+ALTER TABLE manifest ADD COLUMN total_size BIGINT;
+
+-- User-supplied tags of the backup.
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE
+);
@@ -0,0 +1,29 @@
+-- SQLite does not support dropping columns, so the file table has to be recreated.
+ALTER TABLE files RENAME TO files_new;
+
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    last_change_date DATETIME NOT NULL,
+    folder INTEGER NOT NULL,
+    size BIGINT NOT NULL DEFAULT 0,
+    mode INTEGER,
+    uid BIGINT,
+    gid BIGINT,
+    access_date DATETIME,
+    file_type TEXT NOT NULL DEFAULT 'regular',
+    link_target TEXT,
+    hardlink INTEGER REFERENCES files(id),
+    device BIGINT,
+    raw_name BLOB,
+    raw_link_target BLOB,
+    FOREIGN KEY(folder) REFERENCES folders(id)
+);
+
+INSERT INTO files (id, name, last_change_date, folder, size, mode, uid, gid, access_date,
+                   file_type, link_target, hardlink, device, raw_name, raw_link_target)
+    SELECT id, name, last_change_date, folder, size, mode, uid, gid, access_date,
+           file_type, link_target, hardlink, device, raw_name, raw_link_target
+    FROM files_new;
+
+DROP TABLE files_new;
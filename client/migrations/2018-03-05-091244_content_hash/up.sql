@@ -0,0 +1,3 @@
+-- SHA-256 hash of the (uncompressed and unencrypted) content of regular files, to compare them
+-- with existing files on restore. NULL for other file types and chunk indices of older clients.
+ALTER TABLE files ADD COLUMN content_hash TEXT;
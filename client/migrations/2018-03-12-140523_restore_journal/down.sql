@@ -0,0 +1 @@
+DROP TABLE restored_files;
@@ -0,0 +1,8 @@
+-- Files that were completely restored, to resume an interrupted restore (only used in the local
+-- copy of a chunk index). The path is the raw path the file was restored to.
+CREATE TABLE restored_files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    file INTEGER NOT NULL,
+    path BLOB NOT NULL,
+    FOREIGN KEY(file) REFERENCES files(id)
+);
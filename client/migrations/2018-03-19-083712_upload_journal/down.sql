@@ -0,0 +1,42 @@
+-- SQLite does not support dropping columns, so the chunk and manifest tables have to be recreated.
+ALTER TABLE chunks RENAME TO chunks_new;
+
+CREATE TABLE chunks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    chunk_identifier TEXT NOT NULL,
+    file INTEGER NOT NULL,
+    predecessor INTEGER,
+    chunk_offset BIGINT NOT NULL DEFAULT 0,
+    chunk_size BIGINT NOT NULL DEFAULT 0,
+    compression TEXT,
+    FOREIGN KEY(file) REFERENCES files(id),
+    FOREIGN KEY(predecessor) REFERENCES chunks(id)
+);
+
+INSERT INTO chunks (id, chunk_identifier, file, predecessor, chunk_offset, chunk_size,
+                    compression)
+    SELECT id, chunk_identifier, file, predecessor, chunk_offset, chunk_size, compression
+    FROM chunks_new;
+
+DROP TABLE chunks_new;
+
+ALTER TABLE manifest RENAME TO manifest_new;
+
+CREATE TABLE manifest (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    source_path TEXT NOT NULL,
+    creation_date DATETIME NOT NULL,
+    key_fingerprint TEXT,
+    hostname TEXT,
+    label TEXT,
+    file_count BIGINT,
+    total_size BIGINT
+);
+
+INSERT INTO manifest (id, source_path, creation_date, key_fingerprint, hostname, label,
+                      file_count, total_size)
+    SELECT id, source_path, creation_date, key_fingerprint, hostname, label, file_count,
+           total_size
+    FROM manifest_new;
+
+DROP TABLE manifest_new;
@@ -0,0 +1,7 @@
+-- Upload state, to resume an interrupted backup (chunks of older chunk indices count as sent).
+ALTER TABLE chunks ADD COLUMN uploaded BOOLEAN NOT NULL DEFAULT 0;
+UPDATE chunks SET uploaded = 1;
+
+-- Set once the chunk index was sent to the node as root handle.
+ALTER TABLE manifest ADD COLUMN completed BOOLEAN NOT NULL DEFAULT 0;
+UPDATE manifest SET completed = 1;
@@ -0,0 +1,28 @@
+use std::io;
+use chunk_index::DatabaseError;
+use restore_backup::RestoreBackupError;
+
+
+quick_error!{
+    #[derive(Debug)]
+    pub enum BrowseBackupError {
+        IoError(err: io::Error) {
+            from()
+            cause(err)
+        }
+        DatabaseError(err: DatabaseError) {
+            from()
+            display("Database Error occured during browsing: {} ", err)
+            cause(err)
+        }
+        ChunkIndexNotAvailable(err: RestoreBackupError) {
+            from()
+            display("The chunk index could not be fetched from the node: {} ", err)
+            cause(err)
+        }
+        PathNotFound(path: String) {
+            description("The path does not exist in the backup")
+            display("The path {} does not exist in the backup", path)
+        }
+    }
+}
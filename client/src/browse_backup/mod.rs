@@ -0,0 +1,147 @@
+pub mod error;
+pub use self::error::BrowseBackupError;
+
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::prelude::*;
+
+use redbackup_protocol::Session;
+
+use super::config::Config;
+use super::chunk_index::{ChunkIndex, FileType};
+use super::chunk_index::schema::{File, Folder};
+use super::restore_backup;
+
+/// A file or folder in a backup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackupEntry {
+    /// Path relative to the backup root.
+    pub path: PathBuf,
+    /// "folder" or the name of the file type (e.g. "regular" or "symlink").
+    pub kind: String,
+    /// Size of the file content in bytes (None for folders).
+    pub size: Option<u64>,
+    /// Local modification date (None for folders of older backups).
+    pub last_change_date: Option<NaiveDateTime>,
+}
+
+/// Context to browse the file tree of a backup, without restoring it.
+pub struct BrowseBackupContext {
+    config: Config,
+    session: Session,
+}
+
+impl BrowseBackupContext {
+    pub fn new(config: Config) -> Result<Self, BrowseBackupError> {
+        let session = Session::new(config.addr)?;
+
+        Ok(Self { config, session })
+    }
+
+    /// List the content of a path in the backup (relative to the backup root).
+    pub fn run(
+        &mut self,
+        backup_id: &str,
+        path: Option<&str>,
+        recursive: bool,
+    ) -> Result<Vec<BackupEntry>, BrowseBackupError> {
+        info!(
+            "Request chunk index {} from node at {}",
+            backup_id,
+            self.config.addr
+        );
+        let chunk_index = restore_backup::fetch_chunk_index(
+            &mut self.session,
+            &self.config.encryption,
+            backup_id,
+        )?;
+        list_entries(&chunk_index, &PathBuf::from(path.unwrap_or("")), recursive)
+    }
+}
+
+/// List the content of the folder at path, or the file itself.
+///
+/// The path is relative to the backup root (the root itself if empty). Subfolders are only
+/// listed with their content if `recursive` is set.
+pub fn list_entries(
+    chunk_index: &ChunkIndex,
+    path: &Path,
+    recursive: bool,
+) -> Result<Vec<BackupEntry>, BrowseBackupError> {
+    let not_found = || BrowseBackupError::PathNotFound(path.to_string_lossy().into_owned());
+
+    // The backup root is the only folder without parent folder.
+    let mut folder = chunk_index.get_folders_by_parent(None)?.pop().ok_or_else(
+        &not_found,
+    )?;
+    let names: Vec<&OsStr> = path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    let mut local_path = PathBuf::new();
+    for (i, name) in names.iter().enumerate() {
+        local_path.push(name);
+        let subfolder = chunk_index
+            .get_folders_by_parent(Some(folder.id))?
+            .into_iter()
+            .find(|subfolder| subfolder.os_name() == *name);
+        match subfolder {
+            Some(subfolder) => folder = subfolder,
+            // Only the last name of the path may be a file.
+            None if i == names.len() - 1 => {
+                let file = chunk_index
+                    .get_files_by_folder(folder.id)?
+                    .into_iter()
+                    .find(|file| file.os_name() == *name)
+                    .ok_or_else(&not_found)?;
+                return Ok(vec![file_entry(local_path, &file)?]);
+            }
+            None => return Err(not_found()),
+        }
+    }
+
+    let mut entries = Vec::new();
+    list_folder(chunk_index, &folder, &local_path, recursive, &mut entries)?;
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Add the subfolders and files of a folder to the entries.
+fn list_folder(
+    chunk_index: &ChunkIndex,
+    folder: &Folder,
+    path: &Path,
+    recursive: bool,
+    entries: &mut Vec<BackupEntry>,
+) -> Result<(), BrowseBackupError> {
+    for subfolder in chunk_index.get_folders_by_parent(Some(folder.id))? {
+        let subfolder_path = path.join(subfolder.os_name());
+        entries.push(BackupEntry {
+            path: subfolder_path.clone(),
+            kind: String::from("folder"),
+            size: None,
+            last_change_date: subfolder.last_change_date,
+        });
+        if recursive {
+            list_folder(chunk_index, &subfolder, &subfolder_path, recursive, entries)?;
+        }
+    }
+
+    for file in chunk_index.get_files_by_folder(folder.id)? {
+        entries.push(file_entry(path.join(file.os_name()), &file)?);
+    }
+    Ok(())
+}
+
+fn file_entry(path: PathBuf, file: &File) -> Result<BackupEntry, BrowseBackupError> {
+    Ok(BackupEntry {
+        path,
+        kind: FileType::of_file(file)?.name().into(),
+        size: Some(file.size as u64),
+        last_change_date: Some(file.last_change_date),
+    })
+}
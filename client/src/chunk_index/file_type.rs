@@ -0,0 +1,73 @@
+use std::fs::Metadata;
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+
+use super::schema::File;
+
+/// Type of a file in the chunk index.
+///
+/// Only regular files have chunks. Symlinks are stored with their target, hardlinks with the
+/// file they point to and device nodes with their device number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileType {
+    Regular,
+    Symlink,
+    Hardlink,
+    Fifo,
+    CharDevice,
+    BlockDevice,
+}
+
+impl FileType {
+    /// Get a file type by its name, as used in the chunk index.
+    pub fn from_name(name: &str) -> Option<FileType> {
+        match name {
+            "regular" => Some(FileType::Regular),
+            "symlink" => Some(FileType::Symlink),
+            "hardlink" => Some(FileType::Hardlink),
+            "fifo" => Some(FileType::Fifo),
+            "char_device" => Some(FileType::CharDevice),
+            "block_device" => Some(FileType::BlockDevice),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            FileType::Regular => "regular",
+            FileType::Symlink => "symlink",
+            FileType::Hardlink => "hardlink",
+            FileType::Fifo => "fifo",
+            FileType::CharDevice => "char_device",
+            FileType::BlockDevice => "block_device",
+        }
+    }
+
+    /// Get the file type of a file from the chunk index.
+    pub fn of_file(file: &File) -> Result<FileType, io::Error> {
+        Self::from_name(&file.file_type).ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown file type {} in chunk index", file.file_type),
+        ))
+    }
+
+    /// Get the file type from the file system metadata (None for folders and sockets).
+    ///
+    /// Hardlinks can not be detected from the metadata, they are regular files.
+    pub fn of_metadata(metadata: &Metadata) -> Option<FileType> {
+        let file_type = metadata.file_type();
+        if file_type.is_file() {
+            Some(FileType::Regular)
+        } else if file_type.is_symlink() {
+            Some(FileType::Symlink)
+        } else if file_type.is_fifo() {
+            Some(FileType::Fifo)
+        } else if file_type.is_char_device() {
+            Some(FileType::CharDevice)
+        } else if file_type.is_block_device() {
+            Some(FileType::BlockDevice)
+        } else {
+            None
+        }
+    }
+}
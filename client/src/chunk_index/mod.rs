@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
 
 use r2d2;
 use diesel;
@@ -9,8 +12,10 @@ use chrono::prelude::*;
 use diesel::prelude::*;
 
 pub mod schema;
+pub mod file_type;
 
 use self::schema::*;
+pub use self::file_type::FileType;
 
 embed_migrations!("migrations");
 
@@ -77,8 +82,11 @@ impl ChunkIndex {
             .into(self::folders::table)
             .execute(&*conn)?;
 
-        // sqlite does not support RETURNING clauses, so query the new folder from database
-        let query_folder_name = dsl::folders.filter(dsl::name.eq(&new_folder.name));
+        // sqlite does not support RETURNING clauses, so query the new folder from database.
+        // Lossy names of different folders may be equal, so take the most recent one.
+        let query_folder_name = dsl::folders
+            .filter(dsl::name.eq(&new_folder.name))
+            .order(dsl::id.desc());
         let folder;
         if let Some(id) = new_folder.parent_folder {
             folder = query_folder_name
@@ -99,14 +107,26 @@ impl ChunkIndex {
             &*conn,
         )?;
 
-        // sqlite does not support RETURNING clauses, so query the new file from database
+        // sqlite does not support RETURNING clauses, so query the new file from database.
+        // Lossy names of different files may be equal, so take the most recent one.
         let file = dsl::files
             .filter(dsl::name.eq(&new_file.name))
             .filter(dsl::folder.eq(new_file.folder))
+            .order(dsl::id.desc())
             .first::<File>(&*conn)?;
         Ok(file)
     }
 
+    /// Set the content hash of a file, after its content was split into chunks.
+    pub fn set_content_hash(&self, file_id: i32, hash: &str) -> Result<(), DatabaseError> {
+        use self::files::dsl;
+        let conn = self.get_db_connection()?;
+        diesel::update(dsl::files.filter(dsl::id.eq(file_id)))
+            .set(dsl::content_hash.eq(hash))
+            .execute(&*conn)?;
+        Ok(())
+    }
+
     pub fn add_chunk(&self, new_chunk: NewChunk) -> Result<Chunk, DatabaseError> {
         use self::chunks::dsl;
         let conn = self.get_db_connection()?;
@@ -114,14 +134,204 @@ impl ChunkIndex {
             .into(self::chunks::table)
             .execute(&*conn)?;
 
-        // sqlite does not support RETURNING clauses, so query the new chunks from database
+        // sqlite does not support RETURNING clauses, so query the new chunks from database.
+        // A file may contain the same chunk multiple times, so take the most recent one.
         let chunk = dsl::chunks
             .filter(dsl::chunk_identifier.eq(&new_chunk.chunk_identifier))
             .filter(dsl::file.eq(new_chunk.file))
+            .order(dsl::id.desc())
             .first::<Chunk>(&*conn)?;
         Ok(chunk)
     }
 
+    pub fn add_xattr(&self, new_xattr: NewXattr) -> Result<(), DatabaseError> {
+        let conn = self.get_db_connection()?;
+        diesel::insert(&new_xattr)
+            .into(self::xattrs::table)
+            .execute(&*conn)?;
+        Ok(())
+    }
+
+    pub fn get_xattrs_by_file(&self, file_id: i32) -> Result<Vec<Xattr>, DatabaseError> {
+        use self::xattrs::dsl;
+        let conn = self.get_db_connection()?;
+        dsl::xattrs
+            .filter(dsl::file.eq(file_id))
+            .load::<Xattr>(&*conn)
+            .map_err(|e| DatabaseError::from(e))
+    }
+
+    pub fn get_xattrs_by_folder(&self, folder_id: i32) -> Result<Vec<Xattr>, DatabaseError> {
+        use self::xattrs::dsl;
+        let conn = self.get_db_connection()?;
+        dsl::xattrs
+            .filter(dsl::folder.eq(folder_id))
+            .load::<Xattr>(&*conn)
+            .map_err(|e| DatabaseError::from(e))
+    }
+
+    /// Record a completely restored file in the journal of a restore.
+    pub fn add_restored_file(&self, file_id: i32, path: &Path) -> Result<(), DatabaseError> {
+        let conn = self.get_db_connection()?;
+        diesel::insert(&NewRestoredFile {
+            file: file_id,
+            path: path.as_os_str().as_bytes().to_vec(),
+        }).into(self::restored_files::table)
+            .execute(&*conn)?;
+        Ok(())
+    }
+
+    /// Get the restored files (file id and path) from the journal of a restore.
+    pub fn get_restored_files(&self) -> Result<Vec<(i32, PathBuf)>, DatabaseError> {
+        let conn = self.get_db_connection()?;
+        let restored_files = self::restored_files::table.load::<RestoredFile>(&*conn)?;
+        Ok(
+            restored_files
+                .into_iter()
+                .map(|restored_file| {
+                    (
+                        restored_file.file,
+                        PathBuf::from(OsString::from_vec(restored_file.path)),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Describe the backup of this chunk index. There is only one manifest per chunk index.
+    pub fn set_manifest(&self, new_manifest: NewManifest) -> Result<Manifest, DatabaseError> {
+        let conn = self.get_db_connection()?;
+        conn.transaction::<_, DatabaseError, _>(|| {
+            diesel::delete(self::manifest::table).execute(&*conn)?;
+            diesel::insert(&new_manifest)
+                .into(self::manifest::table)
+                .execute(&*conn)?;
+            self::manifest::table.first::<Manifest>(&*conn).map_err(
+                |e| DatabaseError::from(e),
+            )
+        })
+    }
+
+    /// Get the manifest of the chunk index (None for chunk indices of older clients).
+    pub fn get_manifest(&self) -> Result<Option<Manifest>, DatabaseError> {
+        let conn = self.get_db_connection()?;
+        self::manifest::table
+            .first::<Manifest>(&*conn)
+            .optional()
+            .map_err(|e| DatabaseError::from(e))
+    }
+
+    /// Mark the backup as completed, once the chunk index was sent to the node.
+    pub fn set_completed(&self) -> Result<(), DatabaseError> {
+        use self::manifest::dsl;
+        let conn = self.get_db_connection()?;
+        diesel::update(dsl::manifest).set(dsl::completed.eq(true)).execute(
+            &*conn,
+        )?;
+        Ok(())
+    }
+
+    /// Replace the tags of the backup.
+    pub fn set_tags(&self, tags: &[String]) -> Result<(), DatabaseError> {
+        let conn = self.get_db_connection()?;
+        conn.transaction::<_, DatabaseError, _>(|| {
+            diesel::delete(self::tags::table).execute(&*conn)?;
+            for tag in tags {
+                diesel::insert(&NewTag { name: tag.clone() })
+                    .into(self::tags::table)
+                    .execute(&*conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Get the tags of the backup, sorted by name.
+    pub fn get_tags(&self) -> Result<Vec<String>, DatabaseError> {
+        use self::tags::dsl;
+        let conn = self.get_db_connection()?;
+        dsl::tags.select(dsl::name).order(dsl::name).load::<String>(&*conn).map_err(
+            |e| DatabaseError::from(e),
+        )
+    }
+
+    /// Get a file by its relative path (as returned by `Self::get_file_path`).
+    ///
+    /// Different raw names may have the same lossy name, every path component is therefore
+    /// compared by the name it has on the file system.
+    pub fn get_file_by_path(&self, path: &Path) -> Result<Option<File>, DatabaseError> {
+        use self::folders;
+        use self::files;
+        let conn = self.get_db_connection()?;
+
+        let mut names: Vec<&OsStr> = path.iter().collect();
+        let file_name = match names.pop() {
+            Some(file_name) => file_name,
+            None => return Ok(None),
+        };
+
+        let mut parent_folder: Option<i32> = None;
+        for name in names {
+            let query = folders::dsl::folders.filter(
+                folders::dsl::name.eq(name.to_string_lossy().into_owned()),
+            );
+            let candidates = match parent_folder {
+                Some(id) => {
+                    query
+                        .filter(folders::dsl::parent_folder.eq(id))
+                        .load::<Folder>(&*conn)?
+                }
+                None => {
+                    query
+                        .filter(folders::dsl::parent_folder.is_null())
+                        .load::<Folder>(&*conn)?
+                }
+            };
+            match candidates.into_iter().find(|folder| folder.os_name() == name) {
+                Some(folder) => parent_folder = Some(folder.id),
+                None => return Ok(None),
+            }
+        }
+
+        match parent_folder {
+            Some(folder) => {
+                let candidates = files::dsl::files
+                    .filter(files::dsl::name.eq(file_name.to_string_lossy().into_owned()))
+                    .filter(files::dsl::folder.eq(folder))
+                    .load::<File>(&*conn)?;
+                Ok(candidates.into_iter().find(
+                    |file| file.os_name() == file_name,
+                ))
+            }
+            // Files are always located in a folder
+            None => Ok(None),
+        }
+    }
+
+    /// Get the chunks, that were not acknowledged by the node yet.
+    pub fn get_chunks_to_upload(&self) -> Result<Vec<Chunk>, DatabaseError> {
+        use self::chunks::dsl;
+        let conn = self.get_db_connection()?;
+        dsl::chunks.filter(dsl::uploaded.eq(false)).load(&*conn).map_err(
+            |e| DatabaseError::from(e),
+        )
+    }
+
+    /// Record chunks (by their identifier) as acknowledged by the node.
+    pub fn set_chunks_uploaded(&self, chunk_identifiers: &[String]) -> Result<(), DatabaseError> {
+        use self::chunks::dsl;
+        let conn = self.get_db_connection()?;
+        conn.transaction::<_, DatabaseError, _>(|| {
+            // SQLite limits the number of variables in a statement (999 by default).
+            for chunk_identifiers in chunk_identifiers.chunks(500) {
+                diesel::update(dsl::chunks.filter(
+                    dsl::chunk_identifier.eq_any(chunk_identifiers),
+                )).set(dsl::uploaded.eq(true))
+                    .execute(&*conn)?;
+            }
+            Ok(())
+        })
+    }
+
     pub fn get_all_chunks(&self) -> Result<Vec<Chunk>, DatabaseError> {
         let conn = self.get_db_connection()?;
         self::chunks::table.load(&*conn).map_err(
@@ -129,7 +339,52 @@ impl ChunkIndex {
         )
     }
 
-    /// Get the relative path of a file by id.
+    pub fn get_file(&self, file_id: i32) -> Result<File, DatabaseError> {
+        use self::files::dsl;
+        let conn = self.get_db_connection()?;
+        dsl::files.filter(dsl::id.eq(file_id)).first::<File>(&*conn).map_err(
+            |e| DatabaseError::from(e),
+        )
+    }
+
+    pub fn get_files_by_folder(&self, folder_id: i32) -> Result<Vec<File>, DatabaseError> {
+        use self::files::dsl;
+        let conn = self.get_db_connection()?;
+        dsl::files
+            .filter(dsl::folder.eq(folder_id))
+            .load::<File>(&*conn)
+            .map_err(|e| DatabaseError::from(e))
+    }
+
+    pub fn get_all_files(&self) -> Result<Vec<File>, DatabaseError> {
+        let conn = self.get_db_connection()?;
+        self::files::table.load(&*conn).map_err(
+            |e| DatabaseError::from(e),
+        )
+    }
+
+    /// Get the chunks of a file in the order of their content (following the predecessors).
+    pub fn get_chunks_by_file(&self, file_id: i32) -> Result<Vec<Chunk>, DatabaseError> {
+        use self::chunks::dsl;
+        let conn = self.get_db_connection()?;
+        let chunks = dsl::chunks.filter(dsl::file.eq(file_id)).load::<Chunk>(
+            &*conn,
+        )?;
+
+        let mut by_predecessor: HashMap<Option<i32>, Chunk> = chunks
+            .into_iter()
+            .map(|chunk| (chunk.predecessor, chunk))
+            .collect();
+        let mut ordered = Vec::with_capacity(by_predecessor.len());
+        let mut predecessor = None;
+        while let Some(chunk) = by_predecessor.remove(&predecessor) {
+            predecessor = Some(chunk.id);
+            ordered.push(chunk);
+        }
+        Ok(ordered)
+    }
+
+    /// Get the relative path of a file by id (with the raw names, as on the file system).
     pub fn get_file_path(&self, file_id: i32) -> Result<PathBuf, DatabaseError> {
         use self::folders::dsl;
         use self::files;
@@ -141,7 +396,7 @@ impl ChunkIndex {
                 .filter(files::dsl::id.eq(&file_id))
                 .first::<File>(&*conn)?;
             let mut parent_id = file.folder;
-            let mut path_vec = vec![file.name.clone()];
+            let mut path_vec = vec![file.os_name()];
             let mut path = PathBuf::new();
 
             // query parent folders recursively
@@ -149,7 +404,7 @@ impl ChunkIndex {
                 let folder = dsl::folders.filter(dsl::id.eq(parent_id)).first::<Folder>(
                     &*conn,
                 )?;
-                path_vec.push(folder.name.clone());
+                path_vec.push(folder.os_name());
 
                 if let Some(parent) = folder.parent_folder {
                     parent_id = parent;
@@ -164,6 +419,15 @@ impl ChunkIndex {
         })
     }
 
+    pub fn get_folder(&self, folder_id: i32) -> Result<Folder, DatabaseError> {
+        use self::folders::dsl;
+        let conn = self.get_db_connection()?;
+        dsl::folders
+            .filter(dsl::id.eq(folder_id))
+            .first::<Folder>(&*conn)
+            .map_err(|e| DatabaseError::from(e))
+    }
+
     /// Get the subfolder of Some folder, or all root folders with None.
     pub fn get_folders_by_parent(
         &self,
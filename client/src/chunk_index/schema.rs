@@ -1,3 +1,6 @@
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
 use chrono::prelude::*;
 
 infer_schema!("env:DATABASE_FILE");
@@ -9,6 +12,21 @@ pub struct Folder {
     pub id: i32,
     pub name: String,
     pub parent_folder: Option<i32>,
+    /// Permission bits of the folder (None for chunk indices of older backups).
+    pub mode: Option<i32>,
+    pub uid: Option<i64>,
+    pub gid: Option<i64>,
+    pub last_change_date: Option<NaiveDateTime>,
+    pub access_date: Option<NaiveDateTime>,
+    /// Name as raw bytes, if it is not valid unicode (see `encode_name`).
+    pub raw_name: Option<Vec<u8>>,
+}
+
+impl Folder {
+    /// The name of the folder, as it is stored on the file system.
+    pub fn os_name(&self) -> OsString {
+        decode_name(&self.name, &self.raw_name)
+    }
 }
 
 #[derive(Insertable, PartialEq, Clone, Debug)]
@@ -16,6 +34,12 @@ pub struct Folder {
 pub struct NewFolder {
     pub name: String,
     pub parent_folder: Option<i32>,
+    pub mode: Option<i32>,
+    pub uid: Option<i64>,
+    pub gid: Option<i64>,
+    pub last_change_date: Option<NaiveDateTime>,
+    pub access_date: Option<NaiveDateTime>,
+    pub raw_name: Option<Vec<u8>>,
 }
 
 
@@ -28,6 +52,40 @@ pub struct File {
     pub name: String,
     pub last_change_date: NaiveDateTime,
     pub folder: i32,
+    /// Size of the file content (in bytes).
+    pub size: i64,
+    /// Permission bits of the file (None for chunk indices of older backups).
+    pub mode: Option<i32>,
+    pub uid: Option<i64>,
+    pub gid: Option<i64>,
+    pub access_date: Option<NaiveDateTime>,
+    /// Name of the file type (see `FileType`).
+    pub file_type: String,
+    /// Target path of a symlink.
+    pub link_target: Option<String>,
+    /// The file a hardlink points to.
+    pub hardlink: Option<i32>,
+    /// Device number of a device node.
+    pub device: Option<i64>,
+    /// Name as raw bytes, if it is not valid unicode (see `encode_name`).
+    pub raw_name: Option<Vec<u8>>,
+    pub raw_link_target: Option<Vec<u8>>,
+    /// SHA-256 hash of the content of a regular file (see `create_utils::file_hash`).
+    pub content_hash: Option<String>,
+}
+
+impl File {
+    /// The name of the file, as it is stored on the file system.
+    pub fn os_name(&self) -> OsString {
+        decode_name(&self.name, &self.raw_name)
+    }
+
+    /// The target of a symlink, as it is stored on the file system.
+    pub fn os_link_target(&self) -> Option<OsString> {
+        self.link_target.as_ref().map(|link_target| {
+            decode_name(link_target, &self.raw_link_target)
+        })
+    }
 }
 
 #[derive(Insertable, PartialEq, Clone, Debug)]
@@ -36,16 +94,36 @@ pub struct NewFile {
     pub name: String,
     pub last_change_date: NaiveDateTime,
     pub folder: i32,
+    pub size: i64,
+    pub mode: Option<i32>,
+    pub uid: Option<i64>,
+    pub gid: Option<i64>,
+    pub access_date: Option<NaiveDateTime>,
+    pub file_type: String,
+    pub link_target: Option<String>,
+    pub hardlink: Option<i32>,
+    pub device: Option<i64>,
+    pub raw_name: Option<Vec<u8>>,
+    pub raw_link_target: Option<Vec<u8>>,
+    pub content_hash: Option<String>,
 }
 
 #[derive(Queryable, Identifiable, Associations, PartialEq, Clone, Debug)]
-#[primary_key(chunk_identifier, file)]
+#[primary_key(id)]
 #[belongs_to(File, foreign_key = "file")]
 pub struct Chunk {
     pub id: i32,
     pub chunk_identifier: String,
     pub file: i32,
     pub predecessor: Option<i32>,
+    /// Position of the chunk content within the file (in bytes).
+    pub chunk_offset: i64,
+    /// Length of the chunk content (in bytes).
+    pub chunk_size: i64,
+    /// Compression codec of the chunk content (None if uncompressed).
+    pub compression: Option<String>,
+    /// Whether the node acknowledged the chunk (new chunks are not uploaded).
+    pub uploaded: bool,
 }
 
 #[derive(Insertable, PartialEq, Clone, Debug)]
@@ -54,4 +132,117 @@ pub struct NewChunk {
     pub chunk_identifier: String,
     pub file: i32,
     pub predecessor: Option<i32>,
+    pub chunk_offset: i64,
+    pub chunk_size: i64,
+    pub compression: Option<String>,
+}
+
+/// Describes the backup, the chunk index belongs to.
+#[derive(Queryable, Identifiable, PartialEq, Clone, Debug)]
+#[table_name = "manifest"]
+#[primary_key(id)]
+pub struct Manifest {
+    pub id: i32,
+    /// Absolute path of the backup root on the client.
+    pub source_path: String,
+    pub creation_date: NaiveDateTime,
+    /// Fingerprint of the encryption key (None if the chunks are not encrypted).
+    pub key_fingerprint: Option<String>,
+    /// Name of the client host (None for chunk indices of older clients).
+    pub hostname: Option<String>,
+    /// User-supplied name of the backup.
+    pub label: Option<String>,
+    /// Number of files (of any file type) in the backup.
+    pub file_count: Option<i64>,
+    /// Total size of the file contents (in bytes).
+    pub total_size: Option<i64>,
+    /// Whether the chunk index was sent to the node (a new manifest is not completed).
+    pub completed: bool,
+}
+
+#[derive(Insertable, PartialEq, Clone, Debug)]
+#[table_name = "manifest"]
+pub struct NewManifest {
+    pub source_path: String,
+    pub creation_date: NaiveDateTime,
+    pub key_fingerprint: Option<String>,
+    pub hostname: Option<String>,
+    pub label: Option<String>,
+    pub file_count: Option<i64>,
+    pub total_size: Option<i64>,
+}
+
+/// A user-supplied tag of the backup.
+#[derive(Queryable, Identifiable, PartialEq, Clone, Debug)]
+#[table_name = "tags"]
+#[primary_key(id)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable, PartialEq, Clone, Debug)]
+#[table_name = "tags"]
+pub struct NewTag {
+    pub name: String,
+}
+
+/// An extended attribute of either a file or a folder.
+#[derive(Queryable, Identifiable, PartialEq, Clone, Debug)]
+#[table_name = "xattrs"]
+#[primary_key(id)]
+pub struct Xattr {
+    pub id: i32,
+    pub file: Option<i32>,
+    pub folder: Option<i32>,
+    pub name: String,
+    pub value: Vec<u8>,
+}
+
+#[derive(Insertable, PartialEq, Clone, Debug)]
+#[table_name = "xattrs"]
+pub struct NewXattr {
+    pub file: Option<i32>,
+    pub folder: Option<i32>,
+    pub name: String,
+    pub value: Vec<u8>,
+}
+
+/// A file that was completely restored (journal of a restore).
+#[derive(Queryable, Identifiable, PartialEq, Clone, Debug)]
+#[table_name = "restored_files"]
+#[primary_key(id)]
+pub struct RestoredFile {
+    pub id: i32,
+    pub file: i32,
+    pub path: Vec<u8>,
+}
+
+#[derive(Insertable, PartialEq, Clone, Debug)]
+#[table_name = "restored_files"]
+pub struct NewRestoredFile {
+    pub file: i32,
+    pub path: Vec<u8>,
+}
+
+/// Split a name into the value of the name column and the raw name column.
+///
+/// Names that are not valid unicode are stored as lossy string, to be displayed and queried, and
+/// as raw bytes, to be restored byte for byte.
+pub fn encode_name(name: &OsStr) -> (String, Option<Vec<u8>>) {
+    match name.to_str() {
+        Some(name) => (name.into(), None),
+        None => (
+            name.to_string_lossy().into_owned(),
+            Some(name.as_bytes().to_vec()),
+        ),
+    }
+}
+
+/// Get the name, that was split with `encode_name`.
+pub fn decode_name(name: &str, raw_name: &Option<Vec<u8>>) -> OsString {
+    match *raw_name {
+        Some(ref raw_name) => OsString::from_vec(raw_name.clone()),
+        None => OsString::from(name),
+    }
 }
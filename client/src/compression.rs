@@ -0,0 +1,96 @@
+use std::io;
+
+use lz4;
+use zstd;
+
+use chunk_index::schema::Chunk;
+
+// As for the prototype, the compression level is a magic number that is chosen arbitrary.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Codec to compress chunk contents before they are (encrypted and) sent to the node.
+///
+/// The codec is recorded per chunk in the chunk index, so a restore can always decompress
+/// a chunk, regardless of the compression setting of later backups.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Zstd,
+    Lz4,
+}
+
+impl Compression {
+    /// Get a codec by its name, as used in the chunk index and on the command line.
+    pub fn from_name(name: &str) -> Option<Compression> {
+        match name {
+            "none" => Some(Compression::None),
+            "zstd" => Some(Compression::Zstd),
+            "lz4" => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Compression::None => "none",
+            Compression::Zstd => "zstd",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    /// Get the codec of a chunk from the chunk index.
+    pub fn of_chunk(chunk: &Chunk) -> Result<Compression, io::Error> {
+        match chunk.compression {
+            None => Ok(Compression::None),
+            Some(ref name) => {
+                Self::from_name(name).ok_or(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown compression {} in chunk index", name),
+                ))
+            }
+        }
+    }
+
+    /// The value to record in the chunk index (uncompressed chunks are recorded as NULL).
+    pub fn to_column(&self) -> Option<String> {
+        match *self {
+            Compression::None => None,
+            _ => Some(self.name().into()),
+        }
+    }
+
+    /// Get the maximum size of a compressed content of `size` bytes (incompressible content
+    /// grows slightly).
+    pub fn max_compressed_size(&self, size: u64) -> u64 {
+        match *self {
+            Compression::None => size,
+            // ZSTD_COMPRESSBOUND of the zstd library.
+            Compression::Zstd => {
+                let small_content_margin = if size < 128 * 1024 {
+                    (128 * 1024 - size) >> 11
+                } else {
+                    0
+                };
+                size + (size >> 8) + small_content_margin
+            }
+            // LZ4_COMPRESSBOUND of the lz4 library, and the prepended size.
+            Compression::Lz4 => size + size / 255 + 16 + 4,
+        }
+    }
+
+    pub fn compress(&self, content: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+        match *self {
+            Compression::None => Ok(content),
+            Compression::Zstd => zstd::encode_all(content.as_slice(), ZSTD_LEVEL),
+            Compression::Lz4 => lz4::block::compress(&content, None, true),
+        }
+    }
+
+    pub fn decompress(&self, content: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+        match *self {
+            Compression::None => Ok(content),
+            Compression::Zstd => zstd::decode_all(content.as_slice()),
+            Compression::Lz4 => lz4::block::decompress(&content, None),
+        }
+    }
+}
@@ -7,10 +7,20 @@ use std;
 
 use dns_lookup::lookup_host;
 
+use encryption::Encryption;
+
+// As for the prototype, the default batch size and concurrency are magic numbers that are chosen
+// arbitrary. They keep a few MAX_CHUNK_SIZE chunks per batch and the memory footprint reasonable.
+/// Default maximum number of chunk content bytes transferred in one message.
+pub const DEFAULT_BATCH_SIZE: u64 = 16 * 1024 * 1024;
+/// Default number of messages, that are in flight at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
 /// Shared configuration by the backup client.
 pub struct Config {
     pub addr: SocketAddr,
     pub chunk_index_storage: PathBuf,
+    pub encryption: Option<Encryption>,
 }
 
 quick_error! {
@@ -19,6 +29,7 @@ quick_error! {
         InvalidHostname(err: String) {}
         InvalidPort(err: std::num::ParseIntError) {}
         InvalidChunkIndexStorage(err: String) {}
+        InvalidKeyFile(err: String) {}
     }
 }
 
@@ -27,6 +38,8 @@ impl Config {
         hostname: &str,
         port: &str,
         chunk_index_storage: &str,
+        key_file: Option<&str>,
+        passphrase: Option<&str>,
     ) -> Result<Config, ParseError> {
         let ips = lookup_host(hostname).map_err(|e| {
             ParseError::InvalidHostname(e.description().into())
@@ -53,9 +66,26 @@ impl Config {
             ));
         }
 
+        // Chunks are only encrypted if key material is given, the key file takes precedence.
+        let encryption = if let Some(key_file) = key_file {
+            let key_file = PathBuf::from(key_file);
+            if !key_file.is_file() {
+                return Err(ParseError::InvalidKeyFile("No valid key file given".into()));
+            }
+            let encryption = Encryption::from_key_file(&key_file).map_err(|e| {
+                ParseError::InvalidKeyFile(e.description().into())
+            })?;
+            Some(encryption)
+        } else if let Some(passphrase) = passphrase {
+            Some(Encryption::from_passphrase(passphrase.as_bytes()))
+        } else {
+            None
+        };
+
         Ok(Config {
             addr,
             chunk_index_storage,
+            encryption,
         })
     }
 }
@@ -0,0 +1,134 @@
+use std::io::{self, Read};
+use std::mem;
+
+// As for the prototype, the chunk sizes are magic numbers that are chosen arbitrary.
+// They are large enough to keep the number of chunks per file (and therefore messages) low,
+// but small enough to keep the memory footprint of a chunk reasonable.
+pub const MIN_CHUNK_SIZE: usize = 256 * 1024;
+pub const AVG_CHUNK_SIZE: usize = 1024 * 1024;
+pub const MAX_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Seed of the gear table. Changing it changes all chunk boundaries (and therefore identifiers).
+const GEAR_SEED: u64 = 0x7265_6462_6163_6b75; // "redbacku"
+
+/// Splits the content of a reader into variable sized chunks (content-defined chunking).
+///
+/// The cut points are found with a rolling gear hash according to the FastCDC algorithm.
+/// As they only depend on the content around them, an insertion or modification in the middle
+/// of a file only changes the chunks around the modification.
+pub struct Chunker<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    eof: bool,
+    gear: [u64; 256],
+    mask_small: u64,
+    mask_large: u64,
+}
+
+impl<R: Read> Chunker<R> {
+    pub fn new(reader: R) -> Self {
+        let bits = (AVG_CHUNK_SIZE as f64).log2().round() as u32;
+        Chunker {
+            reader,
+            buffer: Vec::with_capacity(MAX_CHUNK_SIZE),
+            eof: false,
+            gear: gear_table(),
+            mask_small: mask(bits + 1),
+            mask_large: mask(bits - 1),
+        }
+    }
+
+    /// Read from the underlying reader until the buffer holds a maximum sized chunk or the
+    /// reader is exhausted.
+    fn fill_buffer(&mut self) -> io::Result<()> {
+        while !self.eof && self.buffer.len() < MAX_CHUNK_SIZE {
+            let len = self.buffer.len();
+            self.buffer.resize(MAX_CHUNK_SIZE, 0);
+            match self.reader.read(&mut self.buffer[len..]) {
+                Ok(0) => {
+                    self.buffer.truncate(len);
+                    self.eof = true;
+                }
+                Ok(n) => self.buffer.truncate(len + n),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => self.buffer.truncate(len),
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the next cut point in the buffer.
+    ///
+    /// Before reaching the average chunk size, a stricter mask is used, afterwards a looser one.
+    /// This normalizes the chunk sizes around the average chunk size.
+    fn cut_point(&self) -> usize {
+        let len = self.buffer.len();
+        if len <= MIN_CHUNK_SIZE {
+            return len;
+        }
+        let normal = AVG_CHUNK_SIZE.min(len);
+
+        let mut hash: u64 = 0;
+        for i in MIN_CHUNK_SIZE..normal {
+            hash = (hash << 1).wrapping_add(self.gear[self.buffer[i] as usize]);
+            if hash & self.mask_small == 0 {
+                return i + 1;
+            }
+        }
+        for i in normal..len {
+            hash = (hash << 1).wrapping_add(self.gear[self.buffer[i] as usize]);
+            if hash & self.mask_large == 0 {
+                return i + 1;
+            }
+        }
+        len
+    }
+}
+
+impl<R: Read> Iterator for Chunker<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+        if let Err(e) = self.fill_buffer() {
+            return Some(Err(e));
+        }
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let cut_point = self.cut_point();
+        let remainder = self.buffer.split_off(cut_point);
+        Some(Ok(mem::replace(&mut self.buffer, remainder)))
+    }
+}
+
+/// Create a mask with `bits` bits set, spread over the upper half of the hash.
+fn mask(bits: u32) -> u64 {
+    let mut mask = 0u64;
+    let mut set = 0;
+    let mut position = 63;
+    while set < bits {
+        mask |= 1 << position;
+        set += 1;
+        position -= 2;
+    }
+    mask
+}
+
+/// Generate the gear table deterministically (splitmix64), so that chunk boundaries are
+/// stable across runs and clients.
+fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state = GEAR_SEED;
+    for entry in table.iter_mut() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        *entry = z ^ (z >> 31);
+    }
+    table
+}
@@ -7,11 +7,29 @@ use glob::{Pattern, PatternError};
 
 use chrono::{DateTime, Utc, NaiveDateTime};
 
+use compression::Compression;
+use config::{DEFAULT_BATCH_SIZE, DEFAULT_CONCURRENCY};
+use xattr::XattrFilter;
+
 /// Parameters that are required for a backup
 pub struct CreateBackupConfig {
     pub backup_dir: PathBuf,
     pub expiration_date: DateTime<Utc>,
     pub exclude: Vec<Pattern>,
+    pub compression: Compression,
+    /// Maximum number of (uncompressed) chunk content bytes sent in one message.
+    pub batch_size: u64,
+    /// Number of messages that are in flight at the same time.
+    pub concurrency: usize,
+    /// Back up the files and folders symlinks point to, instead of the symlinks.
+    pub follow_symlinks: bool,
+    /// Extended attributes (by namespace) to back up.
+    pub xattr_filter: XattrFilter,
+    /// User-supplied name of the backup.
+    pub label: Option<String>,
+    pub tags: Vec<String>,
+    /// Continue the latest unfinished backup of the same directory (if any).
+    pub resume: bool,
 }
 
 quick_error! {
@@ -30,6 +48,11 @@ quick_error! {
             display("ExcludePatternError: {}", err)
             cause(err)
         }
+        InvalidCompression(name: String) {}
+        InvalidBatchSize(batch_size: String) {}
+        InvalidConcurrency(concurrency: String) {}
+        InvalidXattrNamespace(namespace: String) {}
+        InvalidTag(tag: String) {}
     }
 }
 
@@ -38,6 +61,15 @@ impl CreateBackupConfig {
         local_backup_dir: &str,
         expiration_date: &str,
         exclude_from: Option<&str>,
+        compression: Option<&str>,
+        batch_size: Option<&str>,
+        concurrency: Option<&str>,
+        follow_symlinks: bool,
+        xattr_include: Option<&str>,
+        xattr_exclude: Option<&str>,
+        label: Option<&str>,
+        tags: &[&str],
+        resume: bool,
     ) -> Result<CreateBackupConfig, CreateBackupConfigError> {
         let backup_dir = PathBuf::from(local_backup_dir);
         if !backup_dir.is_dir() {
@@ -73,10 +105,76 @@ impl CreateBackupConfig {
             exclude.append(&mut Self::parse_exclude_from(&exclude_from_path)?);
         }
 
+        let compression = match compression {
+            Some(name) => {
+                Compression::from_name(name).ok_or(
+                    CreateBackupConfigError::InvalidCompression(
+                        name.into(),
+                    ),
+                )?
+            }
+            None => Compression::None,
+        };
+
+        let batch_size = match batch_size {
+            Some(batch_size) => {
+                match batch_size.parse() {
+                    Ok(size) if size > 0 => size,
+                    _ => {
+                        return Err(CreateBackupConfigError::InvalidBatchSize(batch_size.into()));
+                    }
+                }
+            }
+            None => DEFAULT_BATCH_SIZE,
+        };
+
+        let concurrency = match concurrency {
+            Some(concurrency) => {
+                match concurrency.parse() {
+                    Ok(concurrency) if concurrency > 0 => concurrency,
+                    _ => {
+                        return Err(CreateBackupConfigError::InvalidConcurrency(
+                            concurrency.into(),
+                        ));
+                    }
+                }
+            }
+            None => DEFAULT_CONCURRENCY,
+        };
+
+        let xattr_filter = XattrFilter::new(xattr_include, xattr_exclude).map_err(|namespace| {
+            CreateBackupConfigError::InvalidXattrNamespace(namespace)
+        })?;
+
+        let label = match label.map(|label| label.trim()) {
+            Some(label) if !label.is_empty() => Some(label.to_string()),
+            _ => None,
+        };
+
+        // Tags are single words, to be filtered by.
+        let mut parsed_tags: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = tag.trim();
+            if tag.is_empty() || tag.contains(char::is_whitespace) {
+                return Err(CreateBackupConfigError::InvalidTag(tag.into()));
+            }
+            parsed_tags.push(tag.into());
+        }
+        parsed_tags.sort();
+        parsed_tags.dedup();
+
         Ok(CreateBackupConfig {
             backup_dir,
             expiration_date,
             exclude,
+            compression,
+            batch_size,
+            concurrency,
+            follow_symlinks,
+            xattr_filter,
+            label,
+            tags: parsed_tags,
+            resume,
         })
     }
 
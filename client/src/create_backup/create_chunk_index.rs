@@ -1,14 +1,23 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io;
-use std::fs::DirEntry;
-use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::os::unix::fs::MetadataExt;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
 use chrono::prelude::*;
 use glob::Pattern;
 
 use super::{ChunkIndex, DatabaseError};
-use super::{Folder, NewFolder, File, NewFile, NewChunk};
+use super::{Chunk, Folder, NewFolder, File, NewFile, NewChunk, NewXattr};
+use chunk_index::FileType;
+use chunk_index::schema::encode_name;
 use super::create_utils;
+use encryption::{Encryption, EncryptionError};
+use compression::Compression;
+use xattr::{self, XattrFilter};
+use super::chunker::Chunker;
 
 quick_error! {
     #[derive(Debug)]
@@ -21,9 +30,9 @@ quick_error! {
             from()
             cause(err)
         }
-        OsStringError(err: OsString) {
+        EncryptionError(err: EncryptionError) {
             from()
-            display("The path {:?} contains invalid unicode characters", err)
+            cause(err)
         }
     }
 }
@@ -35,22 +44,53 @@ pub struct CreateChunkIndex {
     root_path: PathBuf,
     parent_folder: Option<Folder>,
     exclude: Vec<Pattern>,
+    encryption: Option<Encryption>,
+    compression: Compression,
+    previous: Option<ChunkIndex>,
+    follow_symlinks: bool,
+    xattr_filter: XattrFilter,
+    /// Files with several links by (device, inode), to detect hardlinks.
+    linked_files: Rc<RefCell<HashMap<(u64, u64), i32>>>,
+    /// The folders on the current path by (device, inode), to detect loops of followed symlinks.
+    ancestors: Rc<RefCell<HashSet<(u64, u64)>>>,
 }
 
 impl CreateChunkIndex {
     /// Create a chunk index from a specified path recursively
+    ///
+    /// If the chunk index of a previous backup is given, the chunks of unchanged files (same
+    /// modification date and size) are taken from it instead of reading the files.
+    ///
+    /// Symlinks are stored as symlinks, unless `follow_symlinks` is set. Then the files and
+    /// folders they point to are added instead (except for folders that contain the symlink).
+    /// Only the extended attributes selected by the filter are added.
     pub fn new(
         chunk_index: &ChunkIndex,
         path: &PathBuf,
         exclude: &Vec<Pattern>,
+        encryption: &Option<Encryption>,
+        compression: Compression,
+        previous: &Option<ChunkIndex>,
+        follow_symlinks: bool,
+        xattr_filter: &XattrFilter,
     ) -> Result<(), BuilderError> {
         debug!("Create chunk index root folder");
+        let metadata = fs::metadata(path)?;
+        let mut ancestors = HashSet::new();
+        ancestors.insert((metadata.dev(), metadata.ino()));
         let mut create_chunk_index = Self {
             chunk_index: chunk_index.clone(),
             path: path.clone(),
             root_path: path.clone(),
             parent_folder: None,
             exclude: exclude.clone(),
+            encryption: encryption.clone(),
+            compression,
+            previous: previous.clone(),
+            follow_symlinks,
+            xattr_filter: xattr_filter.clone(),
+            linked_files: Rc::new(RefCell::new(HashMap::new())),
+            ancestors: Rc::new(RefCell::new(ancestors)),
         };
 
         let parent_folder = create_chunk_index.add_folder(path).map_err(
@@ -81,78 +121,284 @@ impl CreateChunkIndex {
                 continue;
             }
 
-            match entry.file_type() {
-                Ok(ref filetype) if filetype.is_file() => {
-                    self.add_file(entry)?;
+            let metadata = if self.follow_symlinks {
+                fs::metadata(&path)
+            } else {
+                fs::symlink_metadata(&path)
+            };
+            let metadata = match metadata {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    error!("Could not read metadata of {:?} ({})", entry.file_name(), err);
+                    continue;
                 }
+            };
 
-                Ok(ref filetype) if filetype.is_dir() => {
-                    let folder = self.add_folder(&path)?;
-                    Self {
-                        chunk_index: self.chunk_index.clone(),
-                        path: path.clone(),
-                        root_path: self.root_path.clone(),
-                        parent_folder: Some(folder),
-                        exclude: self.exclude.clone(),
-                    }.build()?;
+            if metadata.is_dir() {
+                // A followed symlink may point to a folder, that contains the symlink.
+                let folder_inode = (metadata.dev(), metadata.ino());
+                if !self.ancestors.borrow_mut().insert(folder_inode) {
+                    warn!(
+                        "Skipped {:?}, as it links to one of its parent folders",
+                        &local_path
+                    );
+                    continue;
                 }
+                let folder = self.add_folder(&path)?;
+                Self {
+                    chunk_index: self.chunk_index.clone(),
+                    path: path.clone(),
+                    root_path: self.root_path.clone(),
+                    parent_folder: Some(folder),
+                    exclude: self.exclude.clone(),
+                    encryption: self.encryption.clone(),
+                    compression: self.compression,
+                    previous: self.previous.clone(),
+                    follow_symlinks: self.follow_symlinks,
+                    xattr_filter: self.xattr_filter.clone(),
+                    linked_files: self.linked_files.clone(),
+                    ancestors: self.ancestors.clone(),
+                }.build()?;
+                self.ancestors.borrow_mut().remove(&folder_inode);
+                continue;
+            }
 
-                Ok(filetype) => {
+            match FileType::of_metadata(&metadata) {
+                Some(file_type) => {
+                    self.add_file(&path, &metadata, file_type)?;
+                }
+                None => {
                     warn!(
                         "The file type {:?} of file {:?} is not implemented",
-                        filetype,
+                        metadata.file_type(),
                         entry.file_name()
                     )
                 }
-                _ => error!("Could not read file type of {:?}", entry.file_name()),
             }
         }
         Ok(())
     }
 
     /// Read metadata of a file and add it to the chunk index.
-    fn add_file(&self, file_entry: DirEntry) -> Result<File, BuilderError> {
-        let metadata = file_entry.metadata()?;
+    fn add_file(
+        &self,
+        path: &PathBuf,
+        metadata: &Metadata,
+        file_type: FileType,
+    ) -> Result<File, BuilderError> {
         let modified = metadata.modified()?;
         let modified = DateTime::<Local>::from(modified);
+        let last_change_date = modified.naive_local();
+        let accessed = DateTime::<Local>::from(metadata.accessed()?);
+        let size = metadata.len() as i64;
+
+        let (name, raw_name) = encode_name(path.file_name().ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No file in path given",
+        ))?);
+
+        // A regular file with several links is a hardlink, if the inode was already added.
+        let inode = (metadata.dev(), metadata.ino());
+        let hardlink = if file_type == FileType::Regular && metadata.nlink() > 1 {
+            self.linked_files.borrow().get(&inode).cloned()
+        } else {
+            None
+        };
+        let file_type = match hardlink {
+            Some(_) => FileType::Hardlink,
+            None => file_type,
+        };
+
+        let (link_target, raw_link_target) = match file_type {
+            FileType::Symlink => {
+                let (link_target, raw_link_target) = encode_name(fs::read_link(path)?.as_os_str());
+                (Some(link_target), raw_link_target)
+            }
+            _ => (None, None),
+        };
+        let device = match file_type {
+            FileType::CharDevice | FileType::BlockDevice => Some(metadata.rdev() as i64),
+            _ => None,
+        };
 
         let folder_id = self.parent_folder.clone().unwrap().id;
         let file = self.chunk_index.add_file(NewFile {
-            name: file_entry.file_name().into_string()?,
-            last_change_date: modified.naive_local(),
+            name,
+            last_change_date,
             folder: folder_id,
+            size,
+            mode: Some(metadata.mode() as i32),
+            uid: Some(metadata.uid() as i64),
+            gid: Some(metadata.gid() as i64),
+            access_date: Some(accessed.naive_local()),
+            file_type: file_type.name().into(),
+            link_target,
+            hardlink,
+            device,
+            raw_name,
+            raw_link_target,
+            content_hash: None,
         })?;
 
-        let chunk_identifier = create_utils::file_hash(&file_entry.path())?;
+        // A hardlink shares the extended attributes with the file it points to.
+        if file_type != FileType::Hardlink {
+            self.add_xattrs(path, Some(file.id), None)?;
+        }
 
-        debug!("Add chunk {} to chunk index", chunk_identifier);
-        self.chunk_index.add_chunk(NewChunk {
-            chunk_identifier,
-            file: file.id,
-            predecessor: None,
-        })?;
+        // Only the content of regular files is split into chunks.
+        if file_type != FileType::Regular {
+            debug!("Added {} {:?} without content", file_type.name(), path);
+            return Ok(file);
+        }
+        if metadata.nlink() > 1 {
+            self.linked_files.borrow_mut().insert(inode, file.id);
+        }
+
+        if let Some((chunks, content_hash)) =
+            self.unchanged_file_chunks(path, &last_change_date, size)?
+        {
+            debug!("File {:?} is unchanged, reuse chunks of previous backup", path);
+            if let Some(content_hash) = content_hash {
+                self.chunk_index.set_content_hash(file.id, &content_hash)?;
+            }
+            let mut predecessor = None;
+            for chunk in chunks {
+                let chunk = self.chunk_index.add_chunk(NewChunk {
+                    chunk_identifier: chunk.chunk_identifier,
+                    file: file.id,
+                    predecessor,
+                    chunk_offset: chunk.chunk_offset,
+                    chunk_size: chunk.chunk_size,
+                    compression: chunk.compression,
+                })?;
+                predecessor = Some(chunk.id);
+            }
+            return Ok(file);
+        }
+
+        debug!("Split file {:?} into chunks", path);
+        let mut predecessor = None;
+        let mut chunk_offset = 0;
+        let mut content_hasher = create_utils::ContentHasher::new();
+        for content in Chunker::new(fs::File::open(path)?) {
+            let content = content?;
+            content_hasher.input(&content);
+            let chunk_size = content.len() as i64;
+            // The identifier is the hash of the chunk, as it is stored on the node.
+            let content = self.compression.compress(content)?;
+            let content = create_utils::encode_chunk_content(content, &self.encryption)?;
+            let chunk_identifier = create_utils::content_hash(&content);
+
+            debug!("Add chunk {} to chunk index", chunk_identifier);
+            let chunk = self.chunk_index.add_chunk(NewChunk {
+                chunk_identifier,
+                file: file.id,
+                predecessor,
+                chunk_offset,
+                chunk_size,
+                compression: self.compression.to_column(),
+            })?;
+            predecessor = Some(chunk.id);
+            chunk_offset += chunk_size;
+        }
+        self.chunk_index.set_content_hash(file.id, &content_hasher.result())?;
 
         Ok(file)
     }
 
+    /// Get the chunks and the content hash of a file from the previous chunk index, if the file
+    /// is unchanged since.
+    fn unchanged_file_chunks(
+        &self,
+        path: &Path,
+        last_change_date: &NaiveDateTime,
+        size: i64,
+    ) -> Result<Option<(Vec<Chunk>, Option<String>)>, BuilderError> {
+        let previous = match self.previous {
+            Some(ref previous) => previous,
+            None => return Ok(None),
+        };
+
+        // Paths in the chunk index start with the name of the root folder.
+        let mut relative_path = PathBuf::from(self.root_path.file_name().unwrap_or_default());
+        relative_path.push(path.strip_prefix(&self.root_path).unwrap());
+
+        match previous.get_file_by_path(&relative_path)? {
+            Some(ref file) if file.file_type == FileType::Regular.name() &&
+                                  file.last_change_date == *last_change_date &&
+                                  file.size == size => {
+                let chunks = previous.get_chunks_by_file(file.id)?;
+                // Only reuse complete chunk lists.
+                let chunks_size: i64 = chunks.iter().map(|chunk| chunk.chunk_size).sum();
+                if chunks_size == size {
+                    Ok(Some((chunks, file.content_hash.clone())))
+                } else {
+                    Ok(None)
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Read metadata of a folder and add it to the chunk index.
     fn add_folder(&self, folder_path: &PathBuf) -> Result<Folder, BuilderError> {
-        let name = OsString::from(folder_path.file_name().ok_or(io::Error::new(
+        let (name, raw_name) = encode_name(folder_path.file_name().ok_or(io::Error::new(
             io::ErrorKind::NotFound,
             "No folder in path given",
-        ))?).into_string()?;
+        ))?);
 
         let parent_folder = match self.parent_folder {
             Some(ref folder) => Some(folder.id),
             None => None,
         };
 
+        let metadata = fs::metadata(folder_path)?;
+        let modified = DateTime::<Local>::from(metadata.modified()?);
+        let accessed = DateTime::<Local>::from(metadata.accessed()?);
+
         debug!("Add folder {} to chunk index", name);
-        self.chunk_index
-            .add_folder(NewFolder {
+        let folder = self.chunk_index.add_folder(NewFolder {
+            name,
+            parent_folder,
+            mode: Some(metadata.mode() as i32),
+            uid: Some(metadata.uid() as i64),
+            gid: Some(metadata.gid() as i64),
+            last_change_date: Some(modified.naive_local()),
+            access_date: Some(accessed.naive_local()),
+            raw_name,
+        })?;
+
+        self.add_xattrs(folder_path, None, Some(folder.id))?;
+        Ok(folder)
+    }
+
+    /// Read the extended attributes of a file or folder and add the selected ones.
+    fn add_xattrs(
+        &self,
+        path: &PathBuf,
+        file: Option<i32>,
+        folder: Option<i32>,
+    ) -> Result<(), BuilderError> {
+        // Followed symlinks are replaced by the files and folders they point to.
+        let path = if self.follow_symlinks {
+            fs::canonicalize(path)?
+        } else {
+            path.clone()
+        };
+
+        for (name, value) in xattr::read_xattrs(&path)? {
+            if !self.xattr_filter.matches(&name) {
+                debug!("Skipped extended attribute {} of {:?}", name, path);
+                continue;
+            }
+            debug!("Add extended attribute {} of {:?} to chunk index", name, path);
+            self.chunk_index.add_xattr(NewXattr {
+                file,
+                folder,
                 name,
-                parent_folder,
-            })
-            .map_err(|e| BuilderError::from(e))
+                value,
+            })?;
+        }
+        Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use std::io;
 use chunk_index::DatabaseError;
+use encryption::EncryptionError;
 use super::create_chunk_index::BuilderError;
 
 
@@ -18,13 +19,21 @@ quick_error!{
             from()
             cause(err)
         }
+        EncryptionError(err: EncryptionError) {
+            from()
+            cause(err)
+        }
         DesignationNotGrantedError(node: String) {
             description("Designation was not granted")
             display("Designation was not granted by the node {}", node)
         }
-        ChunkNotAcknowledged(chunk_identifier: String) {
-            description("Chunk was not acknowledged")
-            display("The Chunk {} was not acknowledged by the node", chunk_identifier)
+        ChunksNotAcknowledged(chunk_identifiers: Vec<String>) {
+            description("Chunks were not acknowledged")
+            display("{} chunks were not acknowledged by the node: {:?}", chunk_identifiers.len(), chunk_identifiers)
+        }
+        SourceChanged(path: String) {
+            description("A file changed since the unfinished backup was started")
+            display("The file {} changed since the unfinished backup was started", path)
         }
         GetRemainingChunksFailed {
             description("Could not get remaining chunks")
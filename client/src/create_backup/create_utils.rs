@@ -1,9 +1,15 @@
 use std::path::PathBuf;
 use std::fs::File;
-use std::io::Error;
+use std::ffi::CStr;
+use std::io::{Error, ErrorKind, SeekFrom};
 
 use sha2::{Sha256, Digest};
-use std::io::Read;
+use std::io::{Read, Seek};
+
+use libc;
+
+use encryption::{Encryption, EncryptionError};
+use chunk_index::schema::Chunk;
 
 
 /// Read the content of file with the specified path to a buffer.
@@ -16,21 +22,117 @@ pub fn read_file_content(path: &PathBuf) -> Result<Vec<u8>, Error> {
     Ok(buf)
 }
 
+/// Read `size` bytes starting at `offset` of the file with the specified path to a buffer.
+pub fn read_chunk_content(path: &PathBuf, offset: u64, size: u64) -> Result<Vec<u8>, Error> {
+    debug!(
+        "Read chunk content of {:?} (offset {}, size {})",
+        path,
+        offset,
+        size
+    );
+    let mut fhandle = File::open(path)?;
+    fhandle.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::with_capacity(size as usize);
+    fhandle.take(size).read_to_end(&mut buf)?;
+    if buf.len() as u64 != size {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "The file is shorter than the chunk index states",
+        ));
+    }
+    debug!("Successfully retreived chunk content of {:?}", path);
+    Ok(buf)
+}
+
+/// Encode a chunk content the way it is sent to the node (encrypted, if enabled).
+pub fn encode_chunk_content(
+    content: Vec<u8>,
+    encryption: &Option<Encryption>,
+) -> Result<Vec<u8>, EncryptionError> {
+    match *encryption {
+        Some(ref encryption) => encryption.encrypt(&content),
+        None => Ok(content),
+    }
+}
+
+/// Split chunks into batches, whose (uncompressed) content does not exceed `batch_size` bytes.
+/// Chunks that are larger than `batch_size` are put in a batch on their own.
+pub fn batch_chunks(chunks: Vec<Chunk>, batch_size: u64) -> Vec<Vec<Chunk>> {
+    let mut batches = Vec::new();
+    let mut batch = Vec::new();
+    let mut size = 0;
+    for chunk in chunks {
+        let chunk_size = chunk.chunk_size as u64;
+        if !batch.is_empty() && size + chunk_size > batch_size {
+            batches.push(batch);
+            batch = Vec::new();
+            size = 0;
+        }
+        size += chunk_size;
+        batch.push(chunk);
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    batches
+}
+
 /// Get the hash a file by path.
 pub fn file_hash(file_path: &PathBuf) -> Result<String, Error> {
     debug!("Calculate hash of file {:?}", file_path);
     let mut file_pointer = File::open(&file_path)?;
     let hash = Sha256::digest_reader(&mut file_pointer)?;
+    let string = hex_string(&hash);
 
-    let string: String = hash.iter().map(|e| format!("{:02x}", e)).fold(
+    debug!("Hash of file {:?} is {}", file_path, string);
+    Ok(string)
+}
+
+/// Calculates the hash of a file incrementally (e.g. chunk by chunk), equal to `file_hash`.
+pub struct ContentHasher {
+    hasher: Sha256,
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        ContentHasher { hasher: Sha256::default() }
+    }
+
+    pub fn input(&mut self, content: &[u8]) {
+        self.hasher.input(content);
+    }
+
+    pub fn result(self) -> String {
+        hex_string(&self.hasher.result())
+    }
+}
+
+/// Get the hash of a content buffer.
+pub fn content_hash(content: &[u8]) -> String {
+    hex_string(&Sha256::digest(content))
+}
+
+/// Format a digest as lowercase hex string.
+fn hex_string(hash: &[u8]) -> String {
+    hash.iter().map(|e| format!("{:02x}", e)).fold(
         String::new(),
         |mut acc,
          s: String| {
             acc.push_str(&s);
             acc
         },
-    );
+    )
+}
 
-    debug!("Hash of file {:?} is {}", file_path, string);
-    Ok(string)
+/// Get the name of the host, the backup is created on.
+pub fn hostname() -> Result<String, Error> {
+    let mut buf = [0 as libc::c_char; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len()) };
+    if result != 0 {
+        return Err(Error::last_os_error());
+    }
+    // The name is not null terminated, if it was truncated.
+    buf[buf.len() - 1] = 0;
+    let hostname = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    Ok(hostname.to_string_lossy().into_owned())
 }
@@ -1,27 +1,27 @@
 pub mod create_chunk_index;
+pub mod chunker;
 pub mod config;
 pub mod create_error;
 pub mod create_utils;
 pub use self::create_error::CreateError;
 pub use self::config::CreateBackupConfig;
 
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 
-use tokio_core;
-use tokio_service::Service;
-use tokio_proto::TcpClient;
-use tokio_core::reactor::{Core, Handle};
-use futures::*;
 use chrono::prelude::*;
 
-use redbackup_protocol::RedClientProto;
+use redbackup_protocol::{Message, MessageKind, Session};
 use redbackup_protocol::message::*;
 
 use super::progress::Progress;
 use super::config::Config;
-use super::chunk_index::{ChunkIndex, DatabaseError};
-use super::chunk_index::schema::{Chunk, File, Folder, NewChunk, NewFile, NewFolder};
+use super::chunk_index::{ChunkIndex, DatabaseError, FileType};
+use super::chunk_index::schema::{Chunk, File, Folder, Manifest, NewChunk, NewFile, NewFolder,
+                                 NewManifest, NewXattr};
+use super::compression::Compression;
+use super::encryption;
 use self::create_chunk_index::CreateChunkIndex;
 
 /// The actual backup process
@@ -29,18 +29,54 @@ pub struct CreateBackupContext {
     config: Config,
     create_backup_config: CreateBackupConfig,
     chunk_index: ChunkIndex,
-    event_loop: Core,
-    handle: Handle,
+    creation_date: DateTime<Utc>,
+    /// Whether an unfinished backup is continued (its chunk index is already built).
+    resumed: bool,
+    session: Session,
     progress_sender: Sender<Progress>,
 }
 
 impl CreateBackupContext {
-    /// Create initial structures for a new backup (i.e. chunk index).
+    /// Create initial structures for a new backup (i.e. chunk index), or open the chunk index of
+    /// the unfinished backup to resume.
     pub fn new(
         config: Config,
         create_backup_config: CreateBackupConfig,
         progress_sender: Sender<Progress>,
     ) -> Result<Self, CreateError> {
+        if create_backup_config.resume {
+            let source_path = fs::canonicalize(&create_backup_config.backup_dir)?
+                .to_string_lossy()
+                .into_owned();
+            let key_fingerprint = config.encryption.as_ref().map(
+                |encryption| encryption.fingerprint(),
+            );
+            let unfinished = find_resumable_chunk_index(
+                &config.chunk_index_storage,
+                &source_path,
+                &key_fingerprint,
+            )?;
+            match unfinished {
+                Some((chunk_index, manifest)) => {
+                    info!(
+                        "Resume unfinished backup of chunk index {}",
+                        chunk_index.get_file_name().to_string_lossy()
+                    );
+                    let session = Session::new(config.addr)?;
+                    return Ok(Self {
+                        config,
+                        create_backup_config,
+                        chunk_index,
+                        creation_date: DateTime::from_utc(manifest.creation_date, Utc),
+                        resumed: true,
+                        session,
+                        progress_sender,
+                    });
+                }
+                None => info!("The last backup is finished (or missing), create a new backup"),
+            }
+        }
+
         let now = Utc::now();
         let chunk_index_file = PathBuf::from(format!(
             "{}/chunk_index-{}.db",
@@ -52,40 +88,41 @@ impl CreateBackupContext {
             chunk_index_file.to_string_lossy()
         );
 
-        let event_loop = tokio_core::reactor::Core::new()?;
-        let handle = event_loop.handle();
+        let session = Session::new(config.addr)?;
 
         debug!("Create chunk index {}", chunk_index_file.to_string_lossy());
         Ok(Self {
             config,
             create_backup_config,
             chunk_index: ChunkIndex::new(chunk_index_file, now)?,
-            event_loop,
-            handle,
+            creation_date: now,
+            resumed: false,
+            session,
             progress_sender,
         })
     }
 
     /// The backup process
+    ///
+    /// Every acknowledged chunk is recorded in the chunk index, so an interrupted backup can be
+    /// resumed without building the chunk index again.
     pub fn run(&mut self) -> Result<(), CreateError> {
-        info!(
-            "Create chunk index from {:?}",
-            self.create_backup_config.backup_dir
-        );
-        CreateChunkIndex::new(
-            &self.chunk_index,
-            &self.create_backup_config.backup_dir,
-            &self.create_backup_config.exclude,
-        )?;
-        info!("The chunk index was built successfully");
+        if !self.resumed {
+            self.build_chunk_index()?;
+        }
+
+        debug!("Collecting chunks from database");
+        let mut chunks = self.chunk_index.get_chunks_to_upload()?;
+        // Identical chunks (within or across files) only have to be sent once.
+        chunks.sort_by(|a, b| a.chunk_identifier.cmp(&b.chunk_identifier));
+        chunks.dedup_by(|a, b| a.chunk_identifier == b.chunk_identifier);
 
         info!("Request designation from node at {}", self.config.addr);
-        self.request_designation()?;
+        let estimate_size = self.estimate_size(&chunks)?;
+        self.request_designation(estimate_size)?;
         info!("Designation was granted by the node");
 
         info!("Check which chunks are already on the node");
-        debug!("Collecting chunks from database");
-        let mut chunks = self.chunk_index.get_all_chunks()?;
         let chunk_elements = chunks
             .iter()
             .map(|e| self.chunk_to_chunk_element(e))
@@ -97,33 +134,237 @@ impl CreateBackupContext {
             chunks.len() - node_chunks.len(),
             chunks.len()
         );
+        let node_chunk_identifiers: Vec<String> = node_chunks
+            .iter()
+            .map(|chunk| chunk.chunk_identifier.clone())
+            .collect();
+        self.chunk_index.set_chunks_uploaded(&node_chunk_identifiers)?;
         Self::reduce_by_remaining_chunks(&mut chunks, &node_chunks);
 
         info!("Send chunks to node");
         let mut progress = Progress::new(self.progress_sender.clone(), chunks.len());
 
-        // Send chunks one by one.
-        for chunk in chunks {
-            debug!("Collect chunk {} content", chunk.chunk_identifier);
-            let chunk_ce = self.chunk_to_chunk_content_element(&chunk)?;
-            debug!("Send chunk {} to node", chunk_ce.chunk_identifier);
-            self.send_chunk(chunk_ce)?;
-            debug!("Successfully sent chunk {} to node", chunk.chunk_identifier);
-            progress.increment();
+        // Send chunks in batches, with a sliding window of batches in flight. The content of a
+        // batch is only read when it is sent (again, if the connection drops). Not acknowledged
+        // chunks are detected below.
+        let batches = create_utils::batch_chunks(chunks, self.create_backup_config.batch_size);
+        {
+            let concurrency = self.create_backup_config.concurrency;
+            let config = &self.config;
+            let create_backup_config = &self.create_backup_config;
+            let chunk_index = &self.chunk_index;
+            let resumed = self.resumed;
+            let mut sent_batches = batches.iter();
+            self.session.call_window(
+                batches.len(),
+                |index| -> Result<Message, CreateError> {
+                    let mut batch_contents = Vec::new();
+                    for chunk in &batches[index] {
+                        debug!("Collect chunk {} content", chunk.chunk_identifier);
+                        batch_contents.push(Self::chunk_to_chunk_content_element(
+                            config,
+                            create_backup_config,
+                            chunk_index,
+                            resumed,
+                            chunk,
+                        )?);
+                    }
+                    debug!("Sending PostChunks message with {} chunks", batch_contents.len());
+                    Ok(PostChunks::new(batch_contents))
+                },
+                concurrency,
+                |response| {
+                    let expected = sent_batches
+                        .next()
+                        .unwrap()
+                        .iter()
+                        .map(|chunk| chunk.chunk_identifier.clone())
+                        .collect();
+                    let (acknowledged, _) =
+                        Self::check_acknowledgement(chunk_index, expected, response)?;
+                    for _ in acknowledged {
+                        progress.increment();
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+
+        // The root handle must only be sent, if the node has all chunks it refers to.
+        let not_uploaded: Vec<String> = self.chunk_index
+            .get_chunks_to_upload()?
+            .into_iter()
+            .map(|chunk| chunk.chunk_identifier)
+            .collect();
+        if !not_uploaded.is_empty() {
+            return Err(CreateError::ChunksNotAcknowledged(not_uploaded));
         }
         info!("Successfully sent all data chunks.");
 
         info!("Send chunk index to node as root handle");
         self.send_chunk_index()?;
+        self.chunk_index.set_completed()?;
         info!("Successfully sent chunk index");
 
         Ok(())
     }
 
+    /// Build the chunk index of the backup directory and describe the backup in its manifest.
+    fn build_chunk_index(&mut self) -> Result<(), CreateError> {
+        let source_path = fs::canonicalize(&self.create_backup_config.backup_dir)?
+            .to_string_lossy()
+            .into_owned();
+        let key_fingerprint = self.config.encryption.as_ref().map(
+            |encryption| encryption.fingerprint(),
+        );
+        let previous = self.find_previous_chunk_index(&source_path, &key_fingerprint)?;
+        if let Some(ref previous) = previous {
+            info!(
+                "Reuse chunks of unchanged files from chunk index {:?}",
+                previous.get_file_name()
+            );
+        }
+
+        info!(
+            "Create chunk index from {:?}",
+            self.create_backup_config.backup_dir
+        );
+        CreateChunkIndex::new(
+            &self.chunk_index,
+            &self.create_backup_config.backup_dir,
+            &self.create_backup_config.exclude,
+            &self.config.encryption,
+            self.create_backup_config.compression,
+            &previous,
+            self.create_backup_config.follow_symlinks,
+            &self.create_backup_config.xattr_filter,
+        )?;
+        info!("The chunk index was built successfully");
+
+        self.set_manifest(source_path, key_fingerprint)
+    }
+
+    /// Find the most recent chunk index in the chunk index storage, that was created for the same
+    /// backup root and with the same key.
+    fn find_previous_chunk_index(
+        &self,
+        source_path: &str,
+        key_fingerprint: &Option<String>,
+    ) -> Result<Option<ChunkIndex>, CreateError> {
+        let current = self.chunk_index.get_file_name();
+        let previous = Self::find_chunk_index(
+            &self.config.chunk_index_storage,
+            Some(&current),
+            |manifest| {
+                manifest.source_path == source_path && manifest.key_fingerprint == *key_fingerprint
+            },
+        )?;
+        Ok(previous.map(|(chunk_index, _)| chunk_index))
+    }
+
+    /// Find the most recent chunk index in the chunk index storage (except the current one),
+    /// whose manifest matches.
+    fn find_chunk_index<F>(
+        chunk_index_storage: &PathBuf,
+        current: Option<&Path>,
+        matches: F,
+    ) -> Result<Option<(ChunkIndex, Manifest)>, CreateError>
+    where
+        F: Fn(&Manifest) -> bool,
+    {
+        let mut candidates: Vec<PathBuf> = fs::read_dir(chunk_index_storage)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                Some(path.as_path()) != current && name.starts_with("chunk_index-") &&
+                    name.ends_with(".db")
+            })
+            .collect();
+        // The file names contain the creation date (RFC 3339), so they sort chronologically.
+        candidates.sort();
+
+        for path in candidates.into_iter().rev() {
+            let chunk_index = match ChunkIndex::new(path.clone(), Utc::now()) {
+                Ok(chunk_index) => chunk_index,
+                Err(err) => {
+                    warn!("Could not open chunk index {:?}: {}", path, err);
+                    continue;
+                }
+            };
+            match chunk_index.get_manifest()? {
+                Some(manifest) => {
+                    if matches(&manifest) {
+                        return Ok(Some((chunk_index, manifest)));
+                    }
+                    debug!("Chunk index {:?} belongs to another backup", path);
+                }
+                None => debug!("Chunk index {:?} has no manifest", path),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Describe the backup in the chunk index, to identify it in the list of backups.
+    fn set_manifest(
+        &self,
+        source_path: String,
+        key_fingerprint: Option<String>,
+    ) -> Result<(), CreateError> {
+        let hostname = match create_utils::hostname() {
+            Ok(hostname) => Some(hostname),
+            Err(err) => {
+                warn!("Could not get hostname ({})", err);
+                None
+            }
+        };
+        let files = self.chunk_index.get_all_files()?;
+        let total_size = files
+            .iter()
+            .filter(|file| file.file_type == FileType::Regular.name())
+            .map(|file| file.size)
+            .sum();
+
+        self.chunk_index.set_manifest(NewManifest {
+            source_path,
+            creation_date: self.creation_date.naive_utc(),
+            key_fingerprint,
+            hostname,
+            label: self.create_backup_config.label.clone(),
+            file_count: Some(files.len() as i64),
+            total_size: Some(total_size),
+        })?;
+        self.chunk_index.set_tags(&self.create_backup_config.tags)?;
+        Ok(())
+    }
+
+    /// Estimate the number of bytes the backup requires on the node.
+    ///
+    /// This is an upper bound, as it assumes incompressible chunks and does not consider chunks,
+    /// that are already stored on the node.
+    fn estimate_size(&self, chunks: &Vec<Chunk>) -> Result<u64, CreateError> {
+        // Encrypted contents (including the chunk index) are larger than the plaintext.
+        let overhead = match self.config.encryption {
+            Some(_) => encryption::OVERHEAD as u64,
+            None => 0,
+        };
+        let mut chunks_size = 0;
+        for chunk in chunks {
+            let compression = Compression::of_chunk(chunk)?;
+            chunks_size += compression.max_compressed_size(chunk.chunk_size as u64) + overhead;
+        }
+        let chunk_index_size = fs::metadata(self.chunk_index.get_file_name())?.len() + overhead;
+        debug!(
+            "Estimated size of backup: {} bytes of chunks and {} bytes of chunk index",
+            chunks_size,
+            chunk_index_size
+        );
+        Ok(chunks_size + chunk_index_size)
+    }
+
     /// Send a backup designation to the node
-    fn request_designation(&mut self) -> Result<(), CreateError> {
+    fn request_designation(&mut self, estimate_size: u64) -> Result<(), CreateError> {
         let expiration_date = self.create_backup_config.expiration_date.clone();
-        let req = GetDesignation::new(0, expiration_date);
+        let req = GetDesignation::new(estimate_size, expiration_date);
         let designation = self.message_node_sync(req).map(|res| match res.body {
             MessageKind::ReturnDesignation(body) => Ok(body.designation),
             _ => Err(CreateError::NodeCommunicationError),
@@ -160,81 +401,104 @@ impl CreateBackupContext {
         });
     }
 
-    /// Send the `chunk` to the node, raising an Error if the chunk was not acknowledged.
-    fn send_chunk(&mut self, chunk: ChunkContentElement) -> Result<(), CreateError> {
-        let chunk_identifier = chunk.chunk_identifier.clone();
-
-        info!("Sending PostChunks message for {}", chunk.chunk_identifier);
-        let req = PostChunks::new(vec![chunk]);
-        let acknowledged_chunks: Vec<ChunkElement> =
-            self.message_node_sync(req)
-                .map(|res| match res.body {
-                    MessageKind::AcknowledgeChunks(body) => Some(body.chunks),
-                    _ => None,
-                })?
-                .ok_or(CreateError::NodeCommunicationError)?;
-
-        let acknowledged_chunk: &ChunkElement = acknowledged_chunks.get(0).ok_or(
-            CreateError::ChunkNotAcknowledged(chunk_identifier.clone()),
-        )?;
+    /// Check which chunks of a batch are acknowledged by the node's response and record them as
+    /// uploaded in the chunk index. Returns the acknowledged and the not acknowledged chunks.
+    fn check_acknowledgement(
+        chunk_index: &ChunkIndex,
+        expected: Vec<String>,
+        response: Message,
+    ) -> Result<(Vec<String>, Vec<String>), CreateError> {
+        let acknowledged_chunks = match response.body {
+            MessageKind::AcknowledgeChunks(body) => body.chunks,
+            _ => return Err(CreateError::NodeCommunicationError),
+        };
 
-        if acknowledged_chunk.chunk_identifier == chunk_identifier {
-            debug!(
-                "Acknowledged chunk: {}",
-                acknowledged_chunk.chunk_identifier
-            );
-            Ok(())
-        } else {
-            error!(
-                "Expected chunk {} acknowledgement from node, got {}",
-                chunk_identifier,
-                acknowledged_chunk.chunk_identifier
-            );
-            Err(CreateError::ChunkNotAcknowledged(chunk_identifier))
+        let mut acknowledged = Vec::new();
+        let mut not_acknowledged = Vec::new();
+        for chunk_identifier in expected {
+            if acknowledged_chunks.iter().any(|acknowledged| {
+                acknowledged.chunk_identifier == chunk_identifier
+            })
+            {
+                debug!("Acknowledged chunk: {}", chunk_identifier);
+                acknowledged.push(chunk_identifier);
+            } else {
+                error!("Chunk {} was not acknowledged by the node", chunk_identifier);
+                not_acknowledged.push(chunk_identifier);
+            }
         }
+
+        chunk_index.set_chunks_uploaded(&acknowledged)?;
+        Ok((acknowledged, not_acknowledged))
     }
 
     /// Send the chunk index as root_handle to the node.
     fn send_chunk_index(&mut self) -> Result<(), CreateError> {
         debug!("Collect metadata and file content of chunk index");
         let file_name = self.chunk_index.get_file_name();
-        let chunk_identifier = create_utils::file_hash(&file_name)?;
-        let chunk_content = create_utils::read_file_content(&file_name)?;
+        let chunk_content = create_utils::encode_chunk_content(
+            create_utils::read_file_content(&file_name)?,
+            &self.config.encryption,
+        )?;
+        let chunk_identifier = create_utils::content_hash(&chunk_content);
         let expiration_date = self.create_backup_config.expiration_date.clone();
-        self.send_chunk(ChunkContentElement {
-            chunk_identifier,
-            chunk_content,
-            expiration_date,
-            root_handle: true,
-        })
+        let response = self.message_node_sync(PostChunks::new(vec![
+            ChunkContentElement {
+                chunk_identifier: chunk_identifier.clone(),
+                chunk_content,
+                expiration_date,
+                root_handle: true,
+            },
+        ]))?;
+        let (_, not_acknowledged) =
+            Self::check_acknowledgement(&self.chunk_index, vec![chunk_identifier], response)?;
+        if not_acknowledged.is_empty() {
+            Ok(())
+        } else {
+            Err(CreateError::ChunksNotAcknowledged(not_acknowledged))
+        }
     }
 
-    /// Send a `Message` to the node.
+    /// Send a `Message` to the node (over the connection of the session).
     fn message_node_sync(&mut self, message: Message) -> Result<Message, CreateError> {
-        let future = TcpClient::new(RedClientProto)
-            .connect(&self.config.addr, &self.handle.clone())
-            .and_then(|client| client.call(message));
-        self.event_loop.run(future).map_err(
-            |e| CreateError::from(e),
-        )
+        self.session.call(message).map_err(|e| CreateError::from(e))
     }
 
 
     /// Map a `Chunk` to a `ChunkContentElement` enriched with the file content.
+    ///
+    /// This is an associated function, so the content can be read while the session is in use.
     fn chunk_to_chunk_content_element(
-        &self,
+        config: &Config,
+        create_backup_config: &CreateBackupConfig,
+        chunk_index: &ChunkIndex,
+        resumed: bool,
         chunk: &Chunk,
     ) -> Result<ChunkContentElement, CreateError> {
         // Get full path of this chunk's file
-        let mut path = self.create_backup_config.backup_dir.clone();
+        let mut path = create_backup_config.backup_dir.clone();
         path.pop(); // The last folder here is the same as the root folder of the file
-        path.push(self.chunk_index.get_file_path(chunk.file)?);
+        path.push(chunk_index.get_file_path(chunk.file)?);
+
+        let chunk_content = create_utils::read_chunk_content(
+            &path,
+            chunk.chunk_offset as u64,
+            chunk.chunk_size as u64,
+        )?;
+        let chunk_content = Compression::of_chunk(chunk)?.compress(chunk_content)?;
+        let chunk_content =
+            create_utils::encode_chunk_content(chunk_content, &config.encryption)?;
+
+        // The files of a resumed backup may have changed since its chunk index was built.
+        if resumed && create_utils::content_hash(&chunk_content) != chunk.chunk_identifier {
+            return Err(CreateError::SourceChanged(path.to_string_lossy().into_owned()));
+        }
 
         Ok(ChunkContentElement {
             chunk_identifier: chunk.chunk_identifier.clone(),
-            expiration_date: self.create_backup_config.expiration_date.clone(),
+            expiration_date: create_backup_config.expiration_date.clone(),
             root_handle: false,
-            chunk_content: create_utils::read_file_content(&path)?,
+            chunk_content,
         })
     }
 
@@ -247,3 +511,27 @@ impl CreateBackupContext {
         }
     }
 }
+
+/// Find the chunk index of the backup to resume: the most recent backup of the source path (with
+/// the same key), if it is unfinished.
+///
+/// Older unfinished backups are superseded by a more recent backup and never resumed.
+pub fn find_resumable_chunk_index(
+    chunk_index_storage: &PathBuf,
+    source_path: &str,
+    key_fingerprint: &Option<String>,
+) -> Result<Option<(ChunkIndex, Manifest)>, CreateError> {
+    let last = CreateBackupContext::find_chunk_index(chunk_index_storage, None, |manifest| {
+        manifest.source_path == source_path && manifest.key_fingerprint == *key_fingerprint
+    })?;
+    Ok(match last {
+        Some((chunk_index, manifest)) => {
+            if manifest.completed {
+                None
+            } else {
+                Some((chunk_index, manifest))
+            }
+        }
+        None => None,
+    })
+}
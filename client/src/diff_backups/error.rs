@@ -0,0 +1,34 @@
+use std::io;
+use chunk_index::DatabaseError;
+use restore_backup::RestoreBackupError;
+use serde_json;
+
+
+quick_error!{
+    #[derive(Debug)]
+    pub enum DiffBackupsError {
+        IoError(err: io::Error) {
+            from()
+            display("I/O Error occured during diff: {} ", err)
+            cause(err)
+        }
+        DatabaseError(err: DatabaseError) {
+            from()
+            display("Database Error occured during diff: {} ", err)
+            cause(err)
+        }
+        ChunkIndexNotAvailable(err: RestoreBackupError) {
+            from()
+            display("The chunk index could not be fetched from the node: {} ", err)
+            cause(err)
+        }
+        JsonError(err: serde_json::Error) {
+            from()
+            display("The changes could not be serialized to JSON: {} ", err)
+            cause(err)
+        }
+        EmptyBackup {
+            description("The backup has no root folder")
+        }
+    }
+}
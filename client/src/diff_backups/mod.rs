@@ -0,0 +1,333 @@
+pub mod error;
+pub use self::error::DiffBackupsError;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::prelude::*;
+use serde::Serializer;
+use serde_json;
+
+use redbackup_protocol::Session;
+
+use super::config::Config;
+use super::chunk_index::{ChunkIndex, FileType};
+use super::chunk_index::schema::{File, Folder, Xattr};
+use super::restore_backup::{self, TempChunkIndex};
+
+/// Where to load a chunk index from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChunkIndexSource {
+    /// Fetch the chunk index of a backup from the node.
+    Backup(String),
+    /// Read a chunk index file (e.g. from the chunk index storage).
+    File(PathBuf),
+}
+
+impl ChunkIndexSource {
+    /// Interpret a source as path of a chunk index file if it exists, otherwise as backup id.
+    pub fn new(source: &str) -> Self {
+        if Path::new(source).is_file() {
+            ChunkIndexSource::File(PathBuf::from(source))
+        } else {
+            ChunkIndexSource::Backup(source.into())
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// The type or content of a file changed.
+    Modified,
+    /// Only the permissions, ownership, modification date or extended attributes changed.
+    MetadataChanged,
+}
+
+impl ChangeKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+            ChangeKind::MetadataChanged => "metadata_changed",
+        }
+    }
+}
+
+/// A changed file or folder between two backups.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BackupChange {
+    /// Path relative to the backup root.
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    pub change: ChangeKind,
+    /// "folder" or the name of the file type (of the newer backup, unless removed).
+    pub kind: String,
+    /// What changed, e.g. "content", "mode" or "xattrs" (empty if added or removed).
+    pub details: Vec<String>,
+}
+
+/// Context to compare two backups.
+pub struct DiffBackupsContext {
+    config: Config,
+    /// Only connected if a chunk index is fetched from the node.
+    session: Option<Session>,
+}
+
+impl DiffBackupsContext {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            session: None,
+        }
+    }
+
+    /// List the changes from the old to the new backup, sorted by path.
+    pub fn run(
+        &mut self,
+        old: &ChunkIndexSource,
+        new: &ChunkIndexSource,
+    ) -> Result<Vec<BackupChange>, DiffBackupsError> {
+        let old = self.load_chunk_index(old)?;
+        let new = self.load_chunk_index(new)?;
+        diff_chunk_indexes(&old, &new)
+    }
+
+    fn load_chunk_index(
+        &mut self,
+        source: &ChunkIndexSource,
+    ) -> Result<TempChunkIndex, DiffBackupsError> {
+        match *source {
+            ChunkIndexSource::Backup(ref backup_id) => {
+                if self.session.is_none() {
+                    self.session = Some(Session::new(self.config.addr)?);
+                }
+                let session = self.session.as_mut().unwrap();
+                Ok(restore_backup::fetch_chunk_index(
+                    session,
+                    &self.config.encryption,
+                    backup_id,
+                )?)
+            }
+            ChunkIndexSource::File(ref path) => {
+                // Opening a chunk index migrates it, the given file is therefore copied.
+                debug!("Copy chunk index {:?}", path);
+                let copy = TempChunkIndex::temp_path();
+                fs::copy(path, &copy)?;
+                Ok(TempChunkIndex::new(copy, Utc::now())?)
+            }
+        }
+    }
+}
+
+/// Serialize the changes as JSON array.
+pub fn to_json(changes: &[BackupChange]) -> Result<String, DiffBackupsError> {
+    Ok(serde_json::to_string_pretty(changes)?)
+}
+
+/// Compare the folders and files of two chunk indexes by their paths, sorted by path.
+///
+/// The content of files is compared by their chunk identifiers, so the content is never fetched.
+/// The modification date of folders is ignored, as it changes with every added or removed file.
+pub fn diff_chunk_indexes(
+    old: &ChunkIndex,
+    new: &ChunkIndex,
+) -> Result<Vec<BackupChange>, DiffBackupsError> {
+    let old = Snapshot::of_chunk_index(old)?;
+    let new = Snapshot::of_chunk_index(new)?;
+
+    let paths: BTreeSet<&PathBuf> = old.keys().chain(new.keys()).collect();
+    let mut changes = Vec::new();
+    for path in paths {
+        let change = match (old.get(path), new.get(path)) {
+            (None, Some(new)) => Some((ChangeKind::Added, new.kind.clone(), Vec::new())),
+            (Some(old), None) => Some((ChangeKind::Removed, old.kind.clone(), Vec::new())),
+            (Some(old), Some(new)) => {
+                old.compare(new).map(|(change, details)| {
+                    (change, new.kind.clone(), details)
+                })
+            }
+            (None, None) => None,
+        };
+        if let Some((change, kind, details)) = change {
+            changes.push(BackupChange {
+                path: path.clone(),
+                change,
+                kind,
+                details,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// The state of a file or folder, as it is compared between backups.
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    kind: String,
+    size: Option<i64>,
+    /// Chunk identifiers, link target, device number or path of the original file.
+    content: Vec<String>,
+    mode: Option<i32>,
+    uid: Option<i64>,
+    gid: Option<i64>,
+    last_change_date: Option<NaiveDateTime>,
+    xattrs: Vec<(String, Vec<u8>)>,
+}
+
+impl Snapshot {
+    /// Get the snapshots of all folders and files by their path relative to the backup root.
+    fn of_chunk_index(
+        chunk_index: &ChunkIndex,
+    ) -> Result<BTreeMap<PathBuf, Snapshot>, DiffBackupsError> {
+        // The backup root is the only folder without parent folder.
+        let root = chunk_index.get_folders_by_parent(None)?.pop().ok_or(
+            DiffBackupsError::EmptyBackup,
+        )?;
+        let mut snapshots = BTreeMap::new();
+        Self::add_folder(chunk_index, &root, &PathBuf::new(), &mut snapshots)?;
+        Ok(snapshots)
+    }
+
+    fn add_folder(
+        chunk_index: &ChunkIndex,
+        folder: &Folder,
+        path: &Path,
+        snapshots: &mut BTreeMap<PathBuf, Snapshot>,
+    ) -> Result<(), DiffBackupsError> {
+        for subfolder in chunk_index.get_folders_by_parent(Some(folder.id))? {
+            let subfolder_path = path.join(subfolder.os_name());
+            snapshots.insert(
+                subfolder_path.clone(),
+                Snapshot {
+                    kind: String::from("folder"),
+                    size: None,
+                    content: Vec::new(),
+                    mode: subfolder.mode,
+                    uid: subfolder.uid,
+                    gid: subfolder.gid,
+                    last_change_date: None,
+                    xattrs: sorted_xattrs(chunk_index.get_xattrs_by_folder(subfolder.id)?),
+                },
+            );
+            Self::add_folder(chunk_index, &subfolder, &subfolder_path, snapshots)?;
+        }
+
+        for file in chunk_index.get_files_by_folder(folder.id)? {
+            snapshots.insert(path.join(file.os_name()), Self::of_file(chunk_index, &file)?);
+        }
+        Ok(())
+    }
+
+    fn of_file(chunk_index: &ChunkIndex, file: &File) -> Result<Snapshot, DiffBackupsError> {
+        let file_type = FileType::of_file(file)?;
+        let content = match file_type {
+            FileType::Regular => {
+                chunk_index
+                    .get_chunks_by_file(file.id)?
+                    .into_iter()
+                    .map(|chunk| chunk.chunk_identifier)
+                    .collect()
+            }
+            FileType::Symlink => {
+                vec![
+                    file.os_link_target()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                ]
+            }
+            FileType::Hardlink => {
+                match file.hardlink {
+                    Some(original) => {
+                        // Paths in the chunk index start with the name of the root folder.
+                        let path = chunk_index.get_file_path(original)?;
+                        let path: PathBuf = path.iter().skip(1).collect();
+                        vec![path.to_string_lossy().into_owned()]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            FileType::CharDevice | FileType::BlockDevice => {
+                file.device.iter().map(|device| device.to_string()).collect()
+            }
+            FileType::Fifo => Vec::new(),
+        };
+
+        // A hardlink shares the metadata with the file it points to.
+        let xattrs = match file_type {
+            FileType::Hardlink => Vec::new(),
+            _ => sorted_xattrs(chunk_index.get_xattrs_by_file(file.id)?),
+        };
+
+        Ok(Snapshot {
+            kind: file_type.name().into(),
+            size: Some(file.size),
+            content,
+            mode: file.mode,
+            uid: file.uid,
+            gid: file.gid,
+            last_change_date: Some(file.last_change_date),
+            xattrs,
+        })
+    }
+
+    /// Get the kind of change and the names of the changed properties, if any changed.
+    fn compare(&self, new: &Snapshot) -> Option<(ChangeKind, Vec<String>)> {
+        let mut details = Vec::new();
+        if self.kind != new.kind {
+            details.push("type");
+        }
+        if self.size != new.size {
+            details.push("size");
+        }
+        if self.content != new.content {
+            details.push("content");
+        }
+        let change = if details.is_empty() {
+            ChangeKind::MetadataChanged
+        } else {
+            ChangeKind::Modified
+        };
+
+        if self.mode != new.mode {
+            details.push("mode");
+        }
+        if self.uid != new.uid || self.gid != new.gid {
+            details.push("ownership");
+        }
+        if self.last_change_date != new.last_change_date {
+            details.push("last_change_date");
+        }
+        if self.xattrs != new.xattrs {
+            details.push("xattrs");
+        }
+
+        if details.is_empty() {
+            None
+        } else {
+            Some((change, details.into_iter().map(String::from).collect()))
+        }
+    }
+}
+
+fn sorted_xattrs(xattrs: Vec<Xattr>) -> Vec<(String, Vec<u8>)> {
+    let mut xattrs: Vec<(String, Vec<u8>)> = xattrs
+        .into_iter()
+        .map(|xattr| (xattr.name, xattr.value))
+        .collect();
+    xattrs.sort();
+    xattrs
+}
+
+/// Paths are serialized as strings, names that are not valid unicode are replaced lossy.
+fn serialize_path<S>(path: &PathBuf, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&path.to_string_lossy())
+}
@@ -0,0 +1,154 @@
+use std::io;
+use std::path::PathBuf;
+
+use chacha20_poly1305_aead;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use create_backup::create_utils;
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+/// Number of bytes, that an encrypted content is larger than the plaintext (nonce and tag).
+pub const OVERHEAD: usize = NONCE_SIZE + TAG_SIZE;
+
+// The salt must be constant, as the same passphrase must always result in the same keys.
+// Otherwise, chunks of different backups could not be deduplicated.
+const KDF_SALT: &'static [u8] = b"redbackup chunk encryption";
+const KDF_ITERATIONS: u32 = 100_000;
+const KEY_FINGERPRINT_INPUT: &'static [u8] = b"redbackup key fingerprint";
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum EncryptionError {
+        IoError(err: io::Error) {
+            from()
+            display("I/O Error occured during encryption: {}", err)
+            cause(err)
+        }
+        InvalidCiphertext {
+            description("The encrypted chunk is too short")
+        }
+        DecryptionFailed {
+            description("The chunk could not be decrypted (wrong key or corrupted chunk)")
+        }
+    }
+}
+
+/// Keys to encrypt and decrypt chunk contents on the client (ChaCha20-Poly1305).
+///
+/// Chunks are encrypted deterministically (convergent encryption): The nonce is a keyed hash
+/// (HMAC-SHA256) of the plaintext. The same content therefore always results in the same
+/// ciphertext, and the chunk identifier (SHA-256 of the ciphertext) stays suitable for
+/// deduplication and the integrity checks of the node.
+#[derive(Clone)]
+pub struct Encryption {
+    encryption_key: [u8; KEY_SIZE],
+    mac_key: [u8; KEY_SIZE],
+}
+
+impl Encryption {
+    /// Derive the keys from a passphrase (PBKDF2-HMAC-SHA256).
+    pub fn from_passphrase(passphrase: &[u8]) -> Self {
+        let mut key_material = [0u8; 2 * KEY_SIZE];
+        pbkdf2(passphrase, KDF_SALT, KDF_ITERATIONS, &mut key_material);
+
+        let mut encryption_key = [0u8; KEY_SIZE];
+        let mut mac_key = [0u8; KEY_SIZE];
+        encryption_key.copy_from_slice(&key_material[..KEY_SIZE]);
+        mac_key.copy_from_slice(&key_material[KEY_SIZE..]);
+        Encryption {
+            encryption_key,
+            mac_key,
+        }
+    }
+
+    /// Derive the keys from the content of a key file.
+    pub fn from_key_file(path: &PathBuf) -> Result<Self, io::Error> {
+        let key_material = create_utils::read_file_content(path)?;
+        Ok(Self::from_passphrase(&key_material))
+    }
+
+    /// Get a fingerprint of the keys, that identifies them without revealing them.
+    pub fn fingerprint(&self) -> String {
+        let mut mac = Hmac::<Sha256>::new(&self.mac_key).expect("HMAC accepts keys of any size");
+        mac.input(KEY_FINGERPRINT_INPUT);
+        create_utils::content_hash(&mac.result().code())
+    }
+
+    /// Encrypt a chunk content. The result contains the nonce, ciphertext and tag.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let nonce = self.nonce(plaintext);
+        let mut encrypted = Vec::with_capacity(NONCE_SIZE + plaintext.len() + TAG_SIZE);
+        encrypted.extend_from_slice(&nonce);
+        let tag = chacha20_poly1305_aead::encrypt(
+            &self.encryption_key,
+            &nonce,
+            &[],
+            plaintext,
+            &mut encrypted,
+        )?;
+        encrypted.extend_from_slice(&tag);
+        Ok(encrypted)
+    }
+
+    /// Decrypt and authenticate a chunk content, that was encrypted with `Self::encrypt`.
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if encrypted.len() < NONCE_SIZE + TAG_SIZE {
+            return Err(EncryptionError::InvalidCiphertext);
+        }
+        let (nonce, rest) = encrypted.split_at(NONCE_SIZE);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_SIZE);
+
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        chacha20_poly1305_aead::decrypt(
+            &self.encryption_key,
+            nonce,
+            &[],
+            ciphertext,
+            tag,
+            &mut plaintext,
+        ).map_err(|_| EncryptionError::DecryptionFailed)?;
+        Ok(plaintext)
+    }
+
+    /// Derive the nonce of a plaintext with a keyed hash.
+    fn nonce(&self, plaintext: &[u8]) -> [u8; NONCE_SIZE] {
+        let mut mac = Hmac::<Sha256>::new(&self.mac_key).expect("HMAC accepts keys of any size");
+        mac.input(plaintext);
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce.copy_from_slice(&mac.result().code()[..NONCE_SIZE]);
+        nonce
+    }
+}
+
+/// Password based key derivation according to RFC 2898 (PBKDF2) with HMAC-SHA256.
+fn pbkdf2(password: &[u8], salt: &[u8], iterations: u32, output: &mut [u8]) {
+    let prf = Hmac::<Sha256>::new(password).expect("HMAC accepts keys of any size");
+    for (index, block) in output.chunks_mut(32).enumerate() {
+        let block_number = index as u32 + 1;
+        let mut mac = prf.clone();
+        mac.input(salt);
+        mac.input(
+            &[
+                (block_number >> 24) as u8,
+                (block_number >> 16) as u8,
+                (block_number >> 8) as u8,
+                block_number as u8,
+            ],
+        );
+        let mut u = mac.result().code();
+        let mut t = u.clone();
+        for _ in 1..iterations {
+            let mut mac = prf.clone();
+            mac.input(&u);
+            u = mac.result().code();
+            for (t, u) in t.iter_mut().zip(u.iter()) {
+                *t ^= *u;
+            }
+        }
+        let len = block.len();
+        block.copy_from_slice(&t[..len]);
+    }
+}
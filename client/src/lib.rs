@@ -19,24 +19,49 @@ extern crate tokio_proto;
 extern crate tokio_service;
 extern crate uuid;
 extern crate glob;
+extern crate hmac;
+extern crate chacha20_poly1305_aead;
+extern crate zstd;
+extern crate lz4;
+extern crate libc;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+#[cfg(feature = "mount")]
+extern crate fuse;
+#[cfg(feature = "mount")]
+extern crate time;
 
 extern crate redbackup_protocol;
 
 #[cfg(test)]
 mod tests;
 pub mod config;
+pub mod encryption;
+pub mod compression;
+pub mod xattr;
 pub mod progress;
 pub mod create_backup;
 pub mod list_backups;
+pub mod browse_backup;
+pub mod diff_backups;
+#[cfg(feature = "mount")]
+pub mod mount_backup;
 pub mod restore_backup;
+pub mod verify_backup;
 mod chunk_index;
 
 use std::sync::mpsc::Sender;
 
 pub use create_backup::config::{CreateBackupConfig, CreateBackupConfigError};
 pub use restore_backup::config::{RestoreBackupConfig, RestoreBackupConfigError};
+pub use restore_backup::BackupSelector;
+pub use browse_backup::BackupEntry;
+pub use list_backups::{BackupInfo, BackupManifest, ListBackupsFilter};
+pub use diff_backups::{BackupChange, ChangeKind, ChunkIndexSource};
+pub use verify_backup::{Mismatch, MismatchKind, VerifyReport};
 pub use progress::Progress;
-use chrono::prelude::*;
 
 pub fn create_backup(
     config: config::Config,
@@ -49,8 +74,31 @@ pub fn create_backup(
 
 pub fn list_backups(
     config: config::Config,
-) -> Result<Vec<(String, DateTime<Utc>)>, list_backups::ListBackupsError> {
-    list_backups::ListBackupsContext::new(config)?.run()
+    filter: &ListBackupsFilter,
+) -> Result<Vec<BackupInfo>, list_backups::ListBackupsError> {
+    list_backups::ListBackupsContext::new(config)?.run(filter)
+}
+
+pub fn browse_backup(
+    config: config::Config,
+    backup_id: &str,
+    path: Option<&str>,
+    recursive: bool,
+) -> Result<Vec<BackupEntry>, browse_backup::BrowseBackupError> {
+    browse_backup::BrowseBackupContext::new(config)?.run(
+        backup_id,
+        path,
+        recursive,
+    )
+}
+
+/// Compare two backups, without fetching any file content.
+pub fn diff_backups(
+    config: config::Config,
+    old: &ChunkIndexSource,
+    new: &ChunkIndexSource,
+) -> Result<Vec<BackupChange>, diff_backups::DiffBackupsError> {
+    diff_backups::DiffBackupsContext::new(config).run(old, new)
 }
 
 pub fn restore_backup(
@@ -61,3 +109,29 @@ pub fn restore_backup(
     restore_backup::RestoreBackupContext::new(config, restore_backup_config, progress_sender)?
         .run()
 }
+
+/// Compare a local directory with the selected backup, without writing any files. In deep mode,
+/// all chunks of the backup are fetched from the node and checked.
+pub fn verify_backup(
+    config: config::Config,
+    backup: &BackupSelector,
+    local_dir: &std::path::Path,
+    deep: bool,
+    progress_sender: Sender<Progress>,
+) -> Result<VerifyReport, verify_backup::VerifyBackupError> {
+    verify_backup::VerifyBackupContext::new(config, progress_sender)?.run(
+        backup,
+        local_dir,
+        deep,
+    )
+}
+
+/// Mount a backup as read-only file system. Returns when the file system is unmounted.
+#[cfg(feature = "mount")]
+pub fn mount_backup(
+    config: config::Config,
+    backup_id: &str,
+    mountpoint: &std::path::Path,
+) -> Result<(), mount_backup::MountBackupError> {
+    mount_backup::MountBackupContext::new(config)?.run(backup_id, mountpoint)
+}
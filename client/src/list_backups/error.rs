@@ -1,4 +1,6 @@
 use std::io;
+use chunk_index::DatabaseError;
+use restore_backup::RestoreBackupError;
 
 quick_error!{
     #[derive(Debug)]
@@ -10,5 +12,16 @@ quick_error!{
         NodeCommunicationError {
             description("The node did not respond with the expected message")
         }
+        DatabaseError(err: DatabaseError) {
+            from()
+            display("Database Error occured while reading a manifest: {} ", err)
+            cause(err)
+        }
+        // Boxed, as a RestoreBackupError may contain a ListBackupsError.
+        ChunkIndexNotAvailable(err: Box<RestoreBackupError>) {
+            from(err: RestoreBackupError) -> (Box::new(err))
+            display("The chunk index could not be fetched from the node: {} ", err)
+            cause(err)
+        }
     }
 }
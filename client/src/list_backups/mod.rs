@@ -1,66 +1,172 @@
 pub mod error;
 pub use self::error::ListBackupsError;
 
-use tokio_core;
-use tokio_service::Service;
-use tokio_proto::TcpClient;
-use tokio_core::reactor::{Core, Handle};
-use futures::*;
 use chrono::prelude::*;
 
-use redbackup_protocol::RedClientProto;
+use redbackup_protocol::{MessageKind, Session};
 use redbackup_protocol::message::*;
 
 use super::config::Config;
+use super::encryption::Encryption;
+use super::restore_backup;
 
 
+/// A backup on the node, with the description from its chunk index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackupInfo {
+    pub backup_id: String,
+    pub expiration_date: DateTime<Utc>,
+    /// None if the chunk index could not be read (e.g. encrypted with another key) or was
+    /// created by an older client.
+    pub manifest: Option<BackupManifest>,
+}
+
+/// Description of a backup, as stored in the manifest of its chunk index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackupManifest {
+    pub hostname: Option<String>,
+    pub source_path: String,
+    pub label: Option<String>,
+    pub tags: Vec<String>,
+    pub creation_date: DateTime<Utc>,
+    pub file_count: Option<u64>,
+    pub total_size: Option<u64>,
+}
+
+/// Selects backups by their manifest. Backups without manifest only match an empty filter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListBackupsFilter {
+    pub hostname: Option<String>,
+    /// Absolute path of the backup root on the client.
+    pub source_path: Option<String>,
+    pub tag: Option<String>,
+    pub label: Option<String>,
+}
+
+impl ListBackupsFilter {
+    pub fn new(
+        hostname: Option<&str>,
+        source_path: Option<&str>,
+        tag: Option<&str>,
+        label: Option<&str>,
+    ) -> Self {
+        Self {
+            hostname: hostname.map(String::from),
+            source_path: source_path.map(String::from),
+            tag: tag.map(String::from),
+            label: label.map(String::from),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hostname.is_none() && self.source_path.is_none() && self.tag.is_none() &&
+            self.label.is_none()
+    }
+
+    pub fn matches(&self, backup: &BackupInfo) -> bool {
+        let manifest = match backup.manifest {
+            Some(ref manifest) => manifest,
+            None => return self.is_empty(),
+        };
+        let hostname_matches = match self.hostname {
+            Some(ref hostname) => manifest.hostname.as_ref() == Some(hostname),
+            None => true,
+        };
+        let source_path_matches = match self.source_path {
+            Some(ref source_path) => manifest.source_path == *source_path,
+            None => true,
+        };
+        let tag_matches = match self.tag {
+            Some(ref tag) => manifest.tags.contains(tag),
+            None => true,
+        };
+        let label_matches = match self.label {
+            Some(ref label) => manifest.label.as_ref() == Some(label),
+            None => true,
+        };
+        hostname_matches && source_path_matches && tag_matches && label_matches
+    }
+}
+
 /// Crate context to list all backups on a node.
 pub struct ListBackupsContext {
     config: Config,
-    event_loop: Core,
-    handle: Handle,
+    session: Session,
 }
 
 impl ListBackupsContext {
     pub fn new(config: Config) -> Result<Self, ListBackupsError> {
-        let event_loop = tokio_core::reactor::Core::new()?;
-        let handle = event_loop.handle();
-
-        Ok(Self {
-            config,
-            event_loop,
-            handle,
-        })
+        let session = Session::new(config.addr)?;
+
+        Ok(Self { config, session })
     }
 
-    /// Get a List of backup (chunk) identifiers and the expiration date.
-    pub fn run(&mut self) -> Result<Vec<(String, DateTime<Utc>)>, ListBackupsError> {
+    /// Get the backups on the node, that match the filter.
+    pub fn run(&mut self, filter: &ListBackupsFilter) -> Result<Vec<BackupInfo>, ListBackupsError> {
         info!("Request root handles from node at {}", self.config.addr);
-        Ok(
-            self.get_root_handles()?
-                .iter()
-                .map(|e| (e.chunk_identifier.clone(), e.expiration_date))
-                .collect(),
-        )
+        list_backups(&mut self.session, &self.config.encryption, filter)
     }
+}
 
-    /// Request root handles from the node.
-    fn get_root_handles(&mut self) -> Result<Vec<ChunkContentElement>, ListBackupsError> {
-        self.message_node_sync(GetRootHandles::new()).map(|res| {
-            match res.body {
-                MessageKind::ReturnRootHandles(body) => Ok(body.root_handle_chunks),
-                _ => Err(ListBackupsError::NodeCommunicationError),
+/// Get the backups on the node, that match the filter.
+///
+/// The chunk index of every backup is fetched, to read its manifest.
+pub fn list_backups(
+    session: &mut Session,
+    encryption: &Option<Encryption>,
+    filter: &ListBackupsFilter,
+) -> Result<Vec<BackupInfo>, ListBackupsError> {
+    let mut backups = Vec::new();
+    for root_handle in get_root_handles(session)? {
+        let backup_id = root_handle.chunk_identifier;
+        let manifest = match read_manifest(session, encryption, &backup_id) {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                warn!("Could not read manifest of backup {} ({})", backup_id, err);
+                None
             }
-        })?
+        };
+        let backup = BackupInfo {
+            backup_id,
+            expiration_date: root_handle.expiration_date,
+            manifest,
+        };
+        if filter.matches(&backup) {
+            backups.push(backup);
+        }
     }
+    Ok(backups)
+}
+
+/// Fetch the chunk index of a backup and read its manifest.
+fn read_manifest(
+    session: &mut Session,
+    encryption: &Option<Encryption>,
+    backup_id: &str,
+) -> Result<Option<BackupManifest>, ListBackupsError> {
+    let chunk_index = restore_backup::fetch_chunk_index(session, encryption, backup_id)?;
+    let manifest = match chunk_index.get_manifest()? {
+        Some(manifest) => {
+            Some(BackupManifest {
+                hostname: manifest.hostname,
+                source_path: manifest.source_path,
+                label: manifest.label,
+                tags: chunk_index.get_tags()?,
+                creation_date: DateTime::from_utc(manifest.creation_date, Utc),
+                file_count: manifest.file_count.map(|count| count as u64),
+                total_size: manifest.total_size.map(|size| size as u64),
+            })
+        }
+        None => None,
+    };
+    Ok(manifest)
+}
 
-    /// Send a `Message` to the node.
-    fn message_node_sync(&mut self, message: Message) -> Result<Message, ListBackupsError> {
-        let future = TcpClient::new(RedClientProto)
-            .connect(&self.config.addr, &self.handle.clone())
-            .and_then(|client| client.call(message));
-        self.event_loop.run(future).map_err(
-            |e| ListBackupsError::from(e),
-        )
+/// Request root handles from the node.
+fn get_root_handles(session: &mut Session) -> Result<Vec<ChunkContentElement>, ListBackupsError> {
+    let response = session.call(GetRootHandles::new())?;
+    match response.body {
+        MessageKind::ReturnRootHandles(body) => Ok(body.root_handle_chunks),
+        _ => Err(ListBackupsError::NodeCommunicationError),
     }
 }
@@ -0,0 +1,65 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Cache for decoded chunk contents, that drops the least recently used chunks first.
+pub struct ChunkCache {
+    capacity: usize,
+    size: usize,
+    chunks: HashMap<String, Vec<u8>>,
+    /// Chunk identifiers, from the least to the most recently used.
+    usage: VecDeque<String>,
+}
+
+impl ChunkCache {
+    /// Create a cache, that holds up to `capacity` bytes of chunk content.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+            chunks: HashMap::new(),
+            usage: VecDeque::new(),
+        }
+    }
+
+    pub fn get(&mut self, chunk_identifier: &str) -> Option<&Vec<u8>> {
+        if self.chunks.contains_key(chunk_identifier) {
+            self.touch(chunk_identifier);
+        }
+        self.chunks.get(chunk_identifier)
+    }
+
+    pub fn contains(&self, chunk_identifier: &str) -> bool {
+        self.chunks.contains_key(chunk_identifier)
+    }
+
+    /// Add a chunk and drop the least recently used chunks, until the cache fits the capacity.
+    ///
+    /// The chunk added last is always kept, even if it is larger than the capacity.
+    pub fn insert(&mut self, chunk_identifier: String, content: Vec<u8>) {
+        if let Some(previous) = self.chunks.remove(&chunk_identifier) {
+            self.size -= previous.len();
+            self.usage.retain(|identifier| *identifier != chunk_identifier);
+        }
+        self.size += content.len();
+        self.chunks.insert(chunk_identifier.clone(), content);
+        self.usage.push_back(chunk_identifier);
+
+        while self.size > self.capacity && self.usage.len() > 1 {
+            let identifier = self.usage.pop_front().expect("Cache is not empty");
+            if let Some(content) = self.chunks.remove(&identifier) {
+                self.size -= content.len();
+            }
+        }
+    }
+
+    /// Total size of the cached chunk contents (in bytes).
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn touch(&mut self, chunk_identifier: &str) {
+        if let Some(position) = self.usage.iter().position(|id| id == chunk_identifier) {
+            let identifier = self.usage.remove(position).unwrap();
+            self.usage.push_back(identifier);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::io;
+use chunk_index::DatabaseError;
+use encryption::EncryptionError;
+use restore_backup::RestoreBackupError;
+
+
+quick_error!{
+    #[derive(Debug)]
+    pub enum MountBackupError {
+        IoError(err: io::Error) {
+            from()
+            display("I/O Error occured during mount: {} ", err)
+            cause(err)
+        }
+        DatabaseError(err: DatabaseError) {
+            from()
+            display("Database Error occured during mount: {} ", err)
+            cause(err)
+        }
+        EncryptionError(err: EncryptionError) {
+            from()
+            display("Chunk could not be decrypted during mount: {} ", err)
+            cause(err)
+        }
+        ChunkIndexNotAvailable(err: RestoreBackupError) {
+            from()
+            display("The chunk index could not be fetched from the node: {} ", err)
+            cause(err)
+        }
+        NodeCommunicationError {
+            description("The node did not respond with the expected message")
+        }
+        ChunkNotAvailable(err: String) {
+            description("Chunk is not available on node")
+            display("Chunk {} is not available on the node", err)
+        }
+        EmptyBackup {
+            description("The backup has no root folder")
+        }
+    }
+}
@@ -0,0 +1,457 @@
+pub mod cache;
+pub mod error;
+pub use self::error::MountBackupError;
+pub use self::cache::ChunkCache;
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::ops::Range;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use chrono::prelude::*;
+use fuse::{self, FileAttr, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, Request};
+use libc;
+use time::Timespec;
+
+use redbackup_protocol::{MessageKind, Session};
+use redbackup_protocol::message::*;
+
+use super::config::Config;
+use super::chunk_index::{ChunkIndex, FileType};
+use super::chunk_index::schema::{Chunk, File, Folder};
+use super::compression::Compression;
+use super::encryption::Encryption;
+use super::restore_backup::{self, utils};
+
+/// Inode of the backup root folder (required by FUSE).
+const ROOT_INO: u64 = 1;
+
+/// Maximum size of the decoded chunks kept in memory (in bytes).
+const CACHE_CAPACITY: usize = 64 * 1024 * 1024;
+
+/// The chunk index does not change while mounted, so the kernel may cache everything.
+const TTL: Timespec = Timespec { sec: 60, nsec: 0 };
+
+/// Context to mount a backup as read-only file system.
+pub struct MountBackupContext {
+    config: Config,
+    session: Session,
+}
+
+impl MountBackupContext {
+    pub fn new(config: Config) -> Result<Self, MountBackupError> {
+        let session = Session::new(config.addr)?;
+
+        Ok(Self { config, session })
+    }
+
+    /// Mount the backup at the mountpoint. Blocks until the file system is unmounted.
+    pub fn run(self, backup_id: &str, mountpoint: &Path) -> Result<(), MountBackupError> {
+        let MountBackupContext {
+            config,
+            mut session,
+        } = self;
+
+        info!(
+            "Request chunk index {} from node at {}",
+            backup_id,
+            config.addr
+        );
+        let chunk_index =
+            restore_backup::fetch_chunk_index(&mut session, &config.encryption, backup_id)?;
+        // The temporary chunk index is removed after the file system is unmounted.
+        let filesystem = BackupFilesystem::new(chunk_index.clone(), session, config.encryption)?;
+
+        info!("Mount backup {} at {:?}", backup_id, mountpoint);
+        let options = ["-o", "ro", "-o", "fsname=redbackup"]
+            .iter()
+            .map(|option| OsStr::new(option))
+            .collect::<Vec<&OsStr>>();
+        fuse::mount(filesystem, &mountpoint, &options)?;
+        info!("Unmounted backup {}", backup_id);
+        Ok(())
+    }
+}
+
+/// An entry of the file system, as stored in the chunk index.
+enum Node {
+    Folder(Folder),
+    File(File),
+}
+
+/// Read-only file system of the folders and files of a chunk index.
+///
+/// Folders have the inode `2 * id` (the root folder `ROOT_INO`), files `2 * id + 1`. File
+/// contents are fetched from the node, when they are read.
+pub struct BackupFilesystem {
+    chunk_index: ChunkIndex,
+    root_folder: Folder,
+    session: Session,
+    encryption: Option<Encryption>,
+    cache: ChunkCache,
+}
+
+impl BackupFilesystem {
+    pub fn new(
+        chunk_index: ChunkIndex,
+        session: Session,
+        encryption: Option<Encryption>,
+    ) -> Result<Self, MountBackupError> {
+        // The backup root is the only folder without parent folder.
+        let root_folder = chunk_index.get_folders_by_parent(None)?.pop().ok_or(
+            MountBackupError::EmptyBackup,
+        )?;
+
+        Ok(Self {
+            chunk_index,
+            root_folder,
+            session,
+            encryption,
+            cache: ChunkCache::new(CACHE_CAPACITY),
+        })
+    }
+
+    fn folder_ino(&self, folder: &Folder) -> u64 {
+        if folder.id == self.root_folder.id {
+            ROOT_INO
+        } else {
+            2 * folder.id as u64
+        }
+    }
+
+    fn file_ino(file: &File) -> u64 {
+        2 * file.id as u64 + 1
+    }
+
+    /// Get the folder or file of an inode. Hardlinks are resolved to the file they point to.
+    fn node(&self, ino: u64) -> Result<Node, MountBackupError> {
+        if ino == ROOT_INO {
+            return Ok(Node::Folder(self.root_folder.clone()));
+        }
+        if ino % 2 == 0 {
+            return Ok(Node::Folder(self.chunk_index.get_folder((ino / 2) as i32)?));
+        }
+        let file = self.chunk_index.get_file((ino / 2) as i32)?;
+        Ok(Node::File(self.resolve_hardlink(file)?))
+    }
+
+    fn resolve_hardlink(&self, file: File) -> Result<File, MountBackupError> {
+        match file.hardlink {
+            Some(original) if file.file_type == FileType::Hardlink.name() => {
+                Ok(self.chunk_index.get_file(original)?)
+            }
+            _ => Ok(file),
+        }
+    }
+
+    fn attr(&self, node: &Node) -> Result<FileAttr, MountBackupError> {
+        match *node {
+            Node::Folder(ref folder) => {
+                let date = folder.last_change_date.map(to_timespec).unwrap_or(
+                    Timespec::new(0, 0),
+                );
+                Ok(FileAttr {
+                    ino: self.folder_ino(folder),
+                    size: 0,
+                    blocks: 0,
+                    atime: folder.access_date.map(to_timespec).unwrap_or(date),
+                    mtime: date,
+                    ctime: date,
+                    crtime: date,
+                    kind: fuse::FileType::Directory,
+                    perm: folder.mode.map(|mode| (mode & 0o7777) as u16).unwrap_or(
+                        0o555,
+                    ),
+                    nlink: 2,
+                    uid: folder.uid.unwrap_or(0) as u32,
+                    gid: folder.gid.unwrap_or(0) as u32,
+                    rdev: 0,
+                    flags: 0,
+                })
+            }
+            Node::File(ref file) => {
+                let (kind, size) = match FileType::of_file(file)? {
+                    FileType::Symlink => {
+                        let link_target = file.os_link_target().unwrap_or_default();
+                        (fuse::FileType::Symlink, link_target.as_bytes().len() as u64)
+                    }
+                    FileType::Fifo => (fuse::FileType::NamedPipe, 0),
+                    FileType::CharDevice => (fuse::FileType::CharDevice, 0),
+                    FileType::BlockDevice => (fuse::FileType::BlockDevice, 0),
+                    FileType::Regular | FileType::Hardlink => {
+                        (fuse::FileType::RegularFile, file.size as u64)
+                    }
+                };
+                let date = to_timespec(file.last_change_date);
+                Ok(FileAttr {
+                    ino: Self::file_ino(file),
+                    size,
+                    blocks: (size + 511) / 512,
+                    atime: file.access_date.map(to_timespec).unwrap_or(date),
+                    mtime: date,
+                    ctime: date,
+                    crtime: date,
+                    kind,
+                    perm: file.mode.map(|mode| (mode & 0o7777) as u16).unwrap_or(0o444),
+                    nlink: 1,
+                    uid: file.uid.unwrap_or(0) as u32,
+                    gid: file.gid.unwrap_or(0) as u32,
+                    rdev: file.device.unwrap_or(0) as u32,
+                    flags: 0,
+                })
+            }
+        }
+    }
+
+    /// Find a subfolder or file of a folder by name.
+    fn lookup_node(&self, parent: u64, name: &OsStr) -> Result<Option<Node>, MountBackupError> {
+        let folder = match self.node(parent)? {
+            Node::Folder(folder) => folder,
+            Node::File(_) => return Ok(None),
+        };
+        let subfolder = self.chunk_index
+            .get_folders_by_parent(Some(folder.id))?
+            .into_iter()
+            .find(|subfolder| subfolder.os_name() == name);
+        if let Some(subfolder) = subfolder {
+            return Ok(Some(Node::Folder(subfolder)));
+        }
+        let file = self.chunk_index
+            .get_files_by_folder(folder.id)?
+            .into_iter()
+            .find(|file| file.os_name() == name);
+        match file {
+            Some(file) => Ok(Some(Node::File(self.resolve_hardlink(file)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// List a folder as (inode, file type, name), including "." and "..".
+    fn list_folder(
+        &self,
+        folder: &Folder,
+    ) -> Result<Vec<(u64, fuse::FileType, Vec<u8>)>, MountBackupError> {
+        let parent = match folder.parent_folder {
+            Some(parent) => self.folder_ino(&self.chunk_index.get_folder(parent)?),
+            None => ROOT_INO,
+        };
+        let mut entries = vec![
+            (self.folder_ino(folder), fuse::FileType::Directory, b".".to_vec()),
+            (parent, fuse::FileType::Directory, b"..".to_vec()),
+        ];
+        for subfolder in self.chunk_index.get_folders_by_parent(Some(folder.id))? {
+            entries.push((
+                self.folder_ino(&subfolder),
+                fuse::FileType::Directory,
+                subfolder.os_name().as_bytes().to_vec(),
+            ));
+        }
+        for file in self.chunk_index.get_files_by_folder(folder.id)? {
+            let name = file.os_name().as_bytes().to_vec();
+            let attr = self.attr(&Node::File(self.resolve_hardlink(file)?))?;
+            entries.push((attr.ino, attr.kind, name));
+        }
+        Ok(entries)
+    }
+
+    /// Read a part of the file content. Only the chunks, that overlap with it, are fetched.
+    fn read_content(
+        &mut self,
+        file: &File,
+        offset: u64,
+        size: u64,
+    ) -> Result<Vec<u8>, MountBackupError> {
+        let slices = chunk_slices(self.chunk_index.get_chunks_by_file(file.id)?, offset, size);
+        let chunks: Vec<Chunk> = slices.iter().map(|&(ref chunk, _)| chunk.clone()).collect();
+
+        let contents = self.fetch_chunks(&chunks)?;
+
+        let mut content = Vec::with_capacity(size as usize);
+        for (chunk, range) in slices {
+            let chunk_content = match contents.get(&chunk.chunk_identifier) {
+                Some(chunk_content) => chunk_content,
+                None => {
+                    self.cache.get(&chunk.chunk_identifier).ok_or(
+                        MountBackupError::ChunkNotAvailable(chunk.chunk_identifier.clone()),
+                    )?
+                }
+            };
+            let to = range.end.min(chunk_content.len());
+            if range.start < to {
+                content.extend_from_slice(&chunk_content[range.start..to]);
+            }
+        }
+        Ok(content)
+    }
+
+    /// Request the chunks, that are not cached, from the node and decode them.
+    fn fetch_chunks(
+        &mut self,
+        chunks: &[Chunk],
+    ) -> Result<HashMap<String, Vec<u8>>, MountBackupError> {
+        let mut missing: Vec<String> = chunks
+            .iter()
+            .filter(|chunk| !self.cache.contains(&chunk.chunk_identifier))
+            .map(|chunk| chunk.chunk_identifier.clone())
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        debug!("Request {} chunks", missing.len());
+        let response = self.session.call(GetChunks::new(missing))?;
+        let returned_chunks = match response.body {
+            MessageKind::ReturnChunks(body) => Some(body.chunks),
+            _ => None,
+        }.ok_or(MountBackupError::NodeCommunicationError)?;
+
+        // The decoded chunks are returned as well, the cache may be smaller than a single read.
+        let mut contents = HashMap::new();
+        for returned_chunk in returned_chunks {
+            let chunk = chunks
+                .iter()
+                .find(|chunk| chunk.chunk_identifier == returned_chunk.chunk_identifier)
+                .ok_or(MountBackupError::NodeCommunicationError)?;
+            let content =
+                utils::decode_chunk_content(returned_chunk.chunk_content, &self.encryption)?;
+            let content = Compression::of_chunk(chunk)?.decompress(content)?;
+            self.cache.insert(chunk.chunk_identifier.clone(), content.clone());
+            contents.insert(chunk.chunk_identifier.clone(), content);
+        }
+        Ok(contents)
+    }
+}
+
+/// Select the chunks of a file, that overlap with `size` bytes of its content at `offset`, ordered
+/// by their offset. Each chunk is returned with the part of its content, that is within the range.
+///
+/// The range may extend beyond the end of the file, only the existing content is selected.
+pub fn chunk_slices(
+    mut chunks: Vec<Chunk>,
+    offset: u64,
+    size: u64,
+) -> Vec<(Chunk, Range<usize>)> {
+    let end = offset + size;
+    chunks.sort_by_key(|chunk| chunk.chunk_offset);
+    chunks
+        .into_iter()
+        .filter_map(|chunk| {
+            let chunk_offset = chunk.chunk_offset as u64;
+            let chunk_end = chunk_offset + chunk.chunk_size as u64;
+            if chunk_offset >= end || chunk_end <= offset {
+                return None;
+            }
+            let from = offset.saturating_sub(chunk_offset) as usize;
+            let to = (end.min(chunk_end) - chunk_offset) as usize;
+            Some((chunk, from..to))
+        })
+        .collect()
+}
+
+impl Filesystem for BackupFilesystem {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let attr = self.lookup_node(parent, name).and_then(|node| match node {
+            Some(node) => self.attr(&node).map(Some),
+            None => Ok(None),
+        });
+        match attr {
+            Ok(Some(attr)) => reply.entry(&TTL, &attr, 0),
+            Ok(None) => reply.error(libc::ENOENT),
+            Err(err) => {
+                error!("Could not look up {:?} ({})", name, err);
+                reply.error(libc::EIO);
+            }
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
+        match self.node(ino).and_then(|node| self.attr(&node)) {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(err) => {
+                error!("Could not get attributes of inode {} ({})", ino, err);
+                reply.error(libc::ENOENT);
+            }
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request, ino: u64, reply: ReplyData) {
+        match self.node(ino) {
+            Ok(Node::File(ref file)) if file.link_target.is_some() => {
+                let link_target = file.os_link_target().unwrap_or_default();
+                reply.data(link_target.as_bytes());
+            }
+            Ok(_) => reply.error(libc::EINVAL),
+            Err(err) => {
+                error!("Could not read link of inode {} ({})", ino, err);
+                reply.error(libc::ENOENT);
+            }
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        reply: ReplyData,
+    ) {
+        let file = match self.node(ino) {
+            Ok(Node::File(file)) => file,
+            Ok(Node::Folder(_)) => return reply.error(libc::EISDIR),
+            Err(err) => {
+                error!("Could not read inode {} ({})", ino, err);
+                return reply.error(libc::ENOENT);
+            }
+        };
+        match self.read_content(&file, offset.max(0) as u64, size as u64) {
+            Ok(content) => reply.data(&content),
+            Err(err) => {
+                error!("Could not read content of {} ({})", file.name, err);
+                reply.error(libc::EIO);
+            }
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let entries = match self.node(ino) {
+            Ok(Node::Folder(folder)) => self.list_folder(&folder),
+            Ok(Node::File(_)) => return reply.error(libc::ENOTDIR),
+            Err(err) => Err(err),
+        };
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(err) => {
+                error!("Could not list inode {} ({})", ino, err);
+                return reply.error(libc::EIO);
+            }
+        };
+
+        // The offset of an entry is the offset of the next one.
+        for (i, (ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(ino, (i + 1) as i64, kind, OsStr::from_bytes(&name)) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Convert a local date of the chunk index to a timestamp.
+fn to_timespec(date: NaiveDateTime) -> Timespec {
+    let date = Local.from_local_datetime(&date).earliest().unwrap_or_else(
+        || Local.from_utc_datetime(&date),
+    );
+    Timespec::new(date.timestamp(), date.nanosecond() as i32)
+}
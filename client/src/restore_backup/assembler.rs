@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+use std::vec;
+
+use redbackup_protocol::message::ChunkContentElement;
+
+use chunk_index::schema::Chunk;
+use super::RestoreBackupError;
+use super::utils;
+
+/// Reassembles files from the contents of their chunks.
+///
+/// The chunks of all files are requested as one sequence, ordered by file and predecessor. Their
+/// contents arrive batch by batch in the order of this sequence, and are appended to the
+/// temporary path of their file. A file is complete, when the first chunk of the next file
+/// arrives (or all chunks are written).
+pub struct FileAssembler {
+    /// The file (index) of each chunk, in the order the chunks are written.
+    chunk_files: vec::IntoIter<usize>,
+    temp_paths: Vec<PathBuf>,
+    current_file: Option<(usize, File)>,
+}
+
+impl FileAssembler {
+    pub fn new(paths: &[PathBuf], chunk_files: Vec<usize>) -> Self {
+        FileAssembler {
+            chunk_files: chunk_files.into_iter(),
+            temp_paths: paths.iter().map(utils::temp_path).collect(),
+            current_file: None,
+        }
+    }
+
+    /// Write the chunks of the next batch with the contents the node returned for it, and
+    /// return the files that are completed.
+    ///
+    /// The same chunk may occur several times in a batch, the contents are therefore looked up
+    /// by chunk identifier. `decode` turns a content into the original chunk content.
+    pub fn write_batch<F>(
+        &mut self,
+        batch: &[Chunk],
+        contents: Vec<ChunkContentElement>,
+        decode: F,
+    ) -> Result<Vec<usize>, RestoreBackupError>
+    where
+        F: Fn(&Chunk, Vec<u8>) -> Result<Vec<u8>, RestoreBackupError>,
+    {
+        let contents: HashMap<String, Vec<u8>> = contents
+            .into_iter()
+            .map(|content| (content.chunk_identifier, content.chunk_content))
+            .collect();
+
+        let mut completed = Vec::new();
+        for chunk in batch {
+            let file = self.chunk_files.next().expect("Every chunk belongs to a file");
+            let is_current_file = match self.current_file {
+                Some((current, _)) => current == file,
+                None => false,
+            };
+            if !is_current_file {
+                if let Some((previous, _)) = self.current_file.take() {
+                    completed.push(previous);
+                }
+                debug!("Write chunks to {:?}", self.temp_paths[file]);
+                let fhandle = utils::open_file_to_append(&self.temp_paths[file])?;
+                self.current_file = Some((file, fhandle));
+            }
+
+            let content = contents
+                .get(&chunk.chunk_identifier)
+                .ok_or(RestoreBackupError::ChunkNotAvailable(
+                    chunk.chunk_identifier.clone(),
+                ))?
+                .clone();
+            let content = decode(chunk, content)?;
+            let fhandle = &mut self.current_file.as_mut().unwrap().1;
+            utils::append_file_content(&content, fhandle)?;
+            debug!("Restored chunk {}", chunk.chunk_identifier);
+        }
+        Ok(completed)
+    }
+
+    /// Close the last file and return it, if any chunk was written.
+    pub fn finish(self) -> Option<usize> {
+        self.current_file.map(|(file, _)| file)
+    }
+}
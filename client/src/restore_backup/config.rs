@@ -1,10 +1,54 @@
 use std::path::PathBuf;
 use std::str;
+use glob::{Pattern, PatternError};
+use libc;
+
+use config::{DEFAULT_BATCH_SIZE, DEFAULT_CONCURRENCY};
+use xattr::XattrFilter;
+use super::selector::BackupSelector;
+
+/// How to handle files, that already exist in the restore directory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictPolicy {
+    /// Abort the restore before any file is written.
+    Fail,
+    /// Keep the existing file.
+    Skip,
+    Overwrite,
+    /// Overwrite the existing file, unless it has the same content.
+    OverwriteIfDifferent,
+    /// Restore the file next to the existing one, with a suffix.
+    Rename,
+}
+
+impl ConflictPolicy {
+    pub fn from_name(name: &str) -> Option<ConflictPolicy> {
+        match name {
+            "fail" => Some(ConflictPolicy::Fail),
+            "skip" => Some(ConflictPolicy::Skip),
+            "overwrite" => Some(ConflictPolicy::Overwrite),
+            "overwrite-if-different" => Some(ConflictPolicy::OverwriteIfDifferent),
+            "rename" => Some(ConflictPolicy::Rename),
+            _ => None,
+        }
+    }
+}
 
 /// Parameters that are required for a restore.
 pub struct RestoreBackupConfig {
-    pub backup_id: String,
+    pub backup: BackupSelector,
     pub restore_dir: PathBuf,
+    /// Paths and glob patterns (relative to the backup root) to restore. Empty to restore all.
+    pub include: Vec<Pattern>,
+    /// Maximum number of (uncompressed) chunk content bytes requested in one message.
+    pub batch_size: u64,
+    /// Number of messages that are in flight at the same time.
+    pub concurrency: usize,
+    /// Restore the owner and group of files and folders (only when running as root).
+    pub restore_ownership: bool,
+    /// Extended attributes (by namespace) to restore.
+    pub xattr_filter: XattrFilter,
+    pub conflict_policy: ConflictPolicy,
 }
 
 quick_error! {
@@ -12,13 +56,31 @@ quick_error! {
     pub enum RestoreBackupConfigError {
         NonExistingDirectory(dirname: String) {}
         InvalidBackupId(id: String) {}
+        SelectorWithBackupId(id: String) {}
+        InvalidDateFormat(date: String) {}
+        IncludePatternError(err: PatternError) {
+            from()
+            display("IncludePatternError: {}", err)
+            cause(err)
+        }
+        InvalidBatchSize(batch_size: String) {}
+        InvalidConcurrency(concurrency: String) {}
+        InvalidXattrNamespace(namespace: String) {}
+        InvalidConflictPolicy(name: String) {}
     }
 }
 
 impl RestoreBackupConfig {
     pub fn new(
-        backup_id: &str,
+        backup: BackupSelector,
         local_restore_dir: &str,
+        include: &[&str],
+        batch_size: Option<&str>,
+        concurrency: Option<&str>,
+        skip_ownership: bool,
+        xattr_include: Option<&str>,
+        xattr_exclude: Option<&str>,
+        conflict_policy: Option<&str>,
     ) -> Result<RestoreBackupConfig, RestoreBackupConfigError> {
         let restore_dir = PathBuf::from(local_restore_dir);
         if !restore_dir.is_dir() {
@@ -27,15 +89,60 @@ impl RestoreBackupConfig {
             ));
         }
 
-        let backup_id = String::from(backup_id);
-        if backup_id.len() != 64 {
-            // This validation is hash dependent.
-            return Err(RestoreBackupConfigError::InvalidBackupId(backup_id));
+        let mut include_patterns = Vec::new();
+        for pattern in include {
+            include_patterns.push(Pattern::new(pattern)?);
+        }
+
+        let batch_size = match batch_size {
+            Some(batch_size) => {
+                match batch_size.parse() {
+                    Ok(size) if size > 0 => size,
+                    _ => {
+                        return Err(RestoreBackupConfigError::InvalidBatchSize(batch_size.into()));
+                    }
+                }
+            }
+            None => DEFAULT_BATCH_SIZE,
+        };
+
+        let concurrency = match concurrency {
+            Some(concurrency) => {
+                match concurrency.parse() {
+                    Ok(concurrency) if concurrency > 0 => concurrency,
+                    _ => {
+                        return Err(RestoreBackupConfigError::InvalidConcurrency(
+                            concurrency.into(),
+                        ));
+                    }
+                }
+            }
+            None => DEFAULT_CONCURRENCY,
+        };
+
+        let xattr_filter = XattrFilter::new(xattr_include, xattr_exclude).map_err(|namespace| {
+            RestoreBackupConfigError::InvalidXattrNamespace(namespace)
+        })?;
+
+        let conflict_policy = match conflict_policy {
+            Some(name) => {
+                ConflictPolicy::from_name(name).ok_or(
+                    RestoreBackupConfigError::InvalidConflictPolicy(name.into()),
+                )?
+            }
+            None => ConflictPolicy::Fail,
         };
 
         Ok(RestoreBackupConfig {
-            backup_id,
+            backup,
             restore_dir,
+            include: include_patterns,
+            batch_size,
+            concurrency,
+            // Only root can give files to other users.
+            restore_ownership: !skip_ownership && unsafe { libc::geteuid() } == 0,
+            xattr_filter,
+            conflict_policy,
         })
     }
 }
@@ -1,5 +1,7 @@
 use std::io;
 use chunk_index::DatabaseError;
+use encryption::EncryptionError;
+use list_backups::ListBackupsError;
 
 
 quick_error!{
@@ -15,6 +17,11 @@ quick_error!{
             display("I/O Error occured during restore: {} ", err)
             cause(err)
         }
+        EncryptionError(err: EncryptionError) {
+            from()
+            display("Chunk could not be decrypted during restore: {} ", err)
+            cause(err)
+        }
         NodeCommunicationError {
             description("The node did not respond with the expected message")
         }
@@ -26,5 +33,17 @@ quick_error!{
             description("Chunk is not available on node")
             display("Chunk {} is not available on the node", err)
         }
+        ListBackupsError(err: ListBackupsError) {
+            from()
+            display("The backups could not be listed: {} ", err)
+            cause(err)
+        }
+        PathExists(path: String) {
+            description("A file to restore already exists")
+            display("The file {} already exists (see the conflict policy)", path)
+        }
+        NoMatchingBackup {
+            description("No backup matches the selector")
+        }
     }
 }
@@ -1,34 +1,43 @@
+pub mod assembler;
 pub mod config;
 pub mod error;
+pub mod selector;
 pub mod utils;
 pub use self::error::RestoreBackupError;
-pub use self::config::RestoreBackupConfig;
+pub use self::config::{ConflictPolicy, RestoreBackupConfig};
+pub use self::selector::BackupSelector;
 
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 
-use tokio_core;
-use tokio_service::Service;
-use tokio_proto::TcpClient;
-use tokio_core::reactor::{Core, Handle};
-use futures::*;
-
 use chrono::prelude::*;
+use glob::Pattern;
+use libc;
+use uuid::Uuid;
 
-use redbackup_protocol::RedClientProto;
+use redbackup_protocol::{Message, MessageKind, Session};
 use redbackup_protocol::message::*;
 
 use super::Progress;
 use super::config::Config;
-use super::chunk_index::ChunkIndex;
+use super::chunk_index::{ChunkIndex, FileType};
+use super::chunk_index::schema::{Chunk, File as DbFile, Xattr};
+use super::compression::Compression;
+use super::encryption::Encryption;
+use super::create_backup::create_utils;
+use super::xattr;
+use self::assembler::FileAssembler;
 
 /// Implementation of the restore process
 pub struct RestoreBackupContext {
     config: Config,
     restore_config: RestoreBackupConfig,
-    event_loop: Core,
-    handle: Handle,
+    session: Session,
     progress_sender: Sender<Progress>,
 }
 
@@ -39,67 +48,201 @@ impl RestoreBackupContext {
         restore_config: RestoreBackupConfig,
         progress_sender: Sender<Progress>,
     ) -> Result<Self, RestoreBackupError> {
-        let event_loop = tokio_core::reactor::Core::new()?;
-        let handle = event_loop.handle();
+        let session = Session::new(config.addr)?;
 
         Ok(Self {
             config,
             restore_config,
-            event_loop,
-            handle,
+            session,
             progress_sender,
         })
     }
 
     /// The restore process
     pub fn run(&mut self) -> Result<(), RestoreBackupError> {
+        let backup_id = self.restore_config.backup.resolve(
+            &mut self.session,
+            &self.config.encryption,
+        )?;
+        let journal_path = self.restore_config.restore_dir.join(
+            format!(".redbackup-restore-{}.db", backup_id),
+        );
 
-        info!("Restore chunk index");
-        let chunk_index = self.restore_chunk_index()?;
+        let (chunk_index, files, restored, resuming) = if journal_path.is_file() {
+            info!("Resume interrupted restore of backup {}", backup_id);
+            let chunk_index = ChunkIndex::new(journal_path, Utc::now())?;
+
+            info!("Select files to restore");
+            let files = select_files(
+                &chunk_index,
+                &self.restore_config.restore_dir,
+                &self.restore_config.include,
+            )?;
+            let (files, restored) = self.resume_files(&chunk_index, files)?;
+            (chunk_index, files, restored, true)
+        } else {
+            info!("Restore backup {}", backup_id);
+            let chunk_index = fetch_chunk_index(
+                &mut self.session,
+                &self.config.encryption,
+                &backup_id,
+            )?;
+
+            info!("Select files to restore");
+            let files = select_files(
+                &chunk_index,
+                &self.restore_config.restore_dir,
+                &self.restore_config.include,
+            )?;
+            // Nothing is written to the restore directory, before the conflicts are resolved.
+            let files = self.resolve_conflicts(files)?;
+            let journal = self.start_journal(&chunk_index, journal_path)?;
+            (journal, files, HashSet::new(), false)
+        };
 
         info!("Restore folder structure");
-        Self::restore_folder_structure(&self.restore_config.restore_dir, &chunk_index, None)?;
+        let restore_dir = self.restore_config.restore_dir.clone();
+        self.restore_folder_structure(&restore_dir, &chunk_index, None)?;
 
         info!("Restore files");
-        self.restore_chunks(&chunk_index)?;
+        self.restore_chunks(&chunk_index, &files, &restored, resuming)?;
+
+        info!("Restore symlinks, hardlinks and special files");
+        self.restore_special_files(&chunk_index, &files, &restored)?;
 
+        info!("Restore file and folder metadata");
+        self.restore_metadata(&chunk_index, &files)?;
+
+        debug!("Remove journal {:?}", chunk_index.get_file_name());
+        fs::remove_file(chunk_index.get_file_name())?;
         info!("Successfully finished restoring all files.");
         Ok(())
     }
 
-    /// Reconstruct the chunk index of the specified backup
-    fn restore_chunk_index(&mut self) -> Result<ChunkIndex, RestoreBackupError> {
-        let chunk_identifier = &self.restore_config.backup_id;
-        debug!(
-            "Request chunk index {} from node at {}",
-            chunk_identifier,
-            self.config.addr
-        );
-        let message = GetChunks::new(vec![chunk_identifier.clone()]);
-        let request = TcpClient::new(RedClientProto)
-            .connect(&self.config.addr, &self.handle.clone())
-            .and_then(|client| client.call(message));
-        let response = self.event_loop.run(request).map_err(
-            |e| RestoreBackupError::from(e),
-        )?;
+    /// Copy the fetched chunk index to the journal path in the restore directory.
+    ///
+    /// The chunk index is kept there until the restore is finished, as it contains the journal
+    /// of the restored files. An interrupted restore is resumed from it.
+    fn start_journal(
+        &self,
+        chunk_index: &ChunkIndex,
+        journal_path: PathBuf,
+    ) -> Result<ChunkIndex, RestoreBackupError> {
+        utils::create_folder(&self.restore_config.restore_dir)?;
+        // An interrupted copy must not be mistaken for the journal of a restore.
+        let temp_path = utils::temp_path(&journal_path);
+        utils::remove_if_exists(&temp_path)?;
+        fs::copy(chunk_index.get_file_name(), &temp_path)?;
+        utils::replace_file(&temp_path, &journal_path)?;
+        Ok(ChunkIndex::new(journal_path, Utc::now())?)
+    }
 
-        let chunks = match response.body {
-            MessageKind::ReturnChunks(body) => Some(body.chunks),
-            _ => None,
-        }.ok_or(RestoreBackupError::NodeCommunicationError)?;
+    fn is_included(&self, path: &Path) -> bool {
+        utils::is_included(&self.restore_config.include, path)
+    }
 
-        let chunk: &ChunkContentElement = chunks.get(0).ok_or(
-            RestoreBackupError::RootHandleChunkNotAvailable(chunk_identifier.clone()),
-        )?;
+    /// Find the selected files in the journal of an interrupted restore.
+    ///
+    /// Journaled files keep the path they were restored to. They are returned (by id and path)
+    /// as restored, if they still exist as restored. The conflict policy is applied to the
+    /// remaining files, including journaled files that were changed since.
+    fn resume_files(
+        &self,
+        chunk_index: &ChunkIndex,
+        files: Vec<(PathBuf, DbFile)>,
+    ) -> Result<(Vec<(PathBuf, DbFile)>, HashSet<(i32, PathBuf)>), RestoreBackupError> {
+        let mut journal: HashMap<i32, Vec<PathBuf>> = HashMap::new();
+        for (file_id, path) in chunk_index.get_restored_files()? {
+            journal.entry(file_id).or_insert_with(Vec::new).push(path);
+        }
+
+        let mut pending = Vec::new();
+        let mut resumed = Vec::new();
+        let mut restored = HashSet::new();
+        for (path, file) in files {
+            // Copies of a hardlinked file, whose original is not included, share the same id.
+            let restored_path = match journal.get_mut(&file.id) {
+                Some(paths) => {
+                    match paths.iter().position(|restored_path| *restored_path == path) {
+                        Some(index) => Some(paths.remove(index)),
+                        None => paths.pop(),
+                    }
+                }
+                None => None,
+            };
+            match restored_path {
+                Some(restored_path) => {
+                    if utils::is_restored(&restored_path, &file)? {
+                        debug!("Verified restored {:?}", restored_path);
+                        restored.insert((file.id, restored_path.clone()));
+                        resumed.push((restored_path, file));
+                    } else {
+                        info!("Restored {:?} was changed since", restored_path);
+                        pending.push((restored_path, file));
+                    }
+                }
+                None => pending.push((path, file)),
+            }
+        }
 
-        let now = Utc::now();
-        let path = PathBuf::from(format!("/tmp/{}.db", now.to_rfc3339()));
-        utils::restore_file_content(&chunk.chunk_content.as_slice(), &path)?;
-        Ok(ChunkIndex::new(path, now)?)
+        let mut files = self.resolve_conflicts(pending)?;
+        files.extend(resumed);
+        Ok((files, restored))
     }
 
-    /// Recreate the whole folder structure of the backup recursively
+    /// Apply the conflict policy to the selected files, that already exist.
+    ///
+    /// Skipped files are removed, renamed files get their new path. Everything is checked before
+    /// any file is written, so a restore that fails on a conflict does not change anything.
+    fn resolve_conflicts(
+        &self,
+        files: Vec<(PathBuf, DbFile)>,
+    ) -> Result<Vec<(PathBuf, DbFile)>, RestoreBackupError> {
+        let policy = self.restore_config.conflict_policy;
+        let mut resolved = Vec::with_capacity(files.len());
+        for (path, file) in files {
+            let metadata = match fs::symlink_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                    resolved.push((path, file));
+                    continue;
+                }
+                Err(err) => return Err(RestoreBackupError::from(err)),
+            };
+
+            // A folder can not be replaced by a file.
+            if metadata.is_dir() && policy != ConflictPolicy::Fail &&
+                policy != ConflictPolicy::Rename
+            {
+                warn!("Skipped {:?}, a folder exists at its path", path);
+                continue;
+            }
+            match policy {
+                ConflictPolicy::Fail => {
+                    return Err(RestoreBackupError::PathExists(path.to_string_lossy().into_owned()))
+                }
+                ConflictPolicy::Skip => info!("Skipped existing {:?}", path),
+                ConflictPolicy::Overwrite => resolved.push((path, file)),
+                ConflictPolicy::OverwriteIfDifferent => {
+                    if utils::has_same_content(&path, &file)? {
+                        info!("Skipped unchanged {:?}", path);
+                    } else {
+                        resolved.push((path, file));
+                    }
+                }
+                ConflictPolicy::Rename => {
+                    let unique_path = utils::unique_path(&path);
+                    info!("Restore existing {:?} as {:?}", path, unique_path);
+                    resolved.push((unique_path, file));
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Recreate the included folder structure of the backup recursively
     fn restore_folder_structure(
+        &self,
         root_folder: &PathBuf,
         chunk_index: &ChunkIndex,
         parent_folder_id: Option<i32>,
@@ -112,58 +255,460 @@ impl RestoreBackupContext {
         let path = root_folder;
 
         for folder in folders {
-            debug!("Restore folder {:?}", path);
             let mut path = path.clone();
-            path.push(&folder.name);
-            utils::create_folder(&path)?;
-            Self::restore_folder_structure(&path, &chunk_index, Some(folder.id))?;
+            path.push(folder.os_name());
+            if self.is_included(path.strip_prefix(&self.restore_config.restore_dir).unwrap()) {
+                debug!("Restore folder {:?}", path);
+                utils::create_folder(&path)?;
+            }
+            // Subfolders may be included, even if their parent is not.
+            self.restore_folder_structure(&path, &chunk_index, Some(folder.id))?;
         }
         Ok(())
     }
 
-    /// Reassemble files from all chunks in the chunk index
-    fn restore_chunks(&mut self, chunk_index: &ChunkIndex) -> Result<(), RestoreBackupError> {
-        let chunks = chunk_index.get_all_chunks()?;
+    /// Reassemble the selected files from their chunks
+    ///
+    /// The chunks are requested in batches, several batches at the same time. The chunk contents
+    /// are written in the order of the files and their chunks. Every completed file is recorded
+    /// in the journal. When resuming, the complete chunks of partially restored files are kept.
+    fn restore_chunks(
+        &mut self,
+        chunk_index: &ChunkIndex,
+        files: &[(PathBuf, DbFile)],
+        restored: &HashSet<(i32, PathBuf)>,
+        resuming: bool,
+    ) -> Result<(), RestoreBackupError> {
+        // All chunks in the order they are written, and the file each chunk belongs to.
+        let mut paths = Vec::new();
+        let mut chunks = Vec::new();
+        let mut chunk_files = Vec::new();
+        for &(ref path, ref file) in files {
+            // Only regular files have content.
+            if FileType::of_file(&file)? != FileType::Regular ||
+                restored.contains(&(file.id, path.clone()))
+            {
+                continue;
+            }
+            // The parent folder is not restored, if only the file is included.
+            if let Some(parent) = path.parent() {
+                utils::create_folder(&parent.to_path_buf())?;
+            }
+
+            // Chunks are appended in the order of their predecessors.
+            let file_chunks = chunk_index.get_chunks_by_file(file.id)?;
+            let temp_path = utils::temp_path(&path);
+            let complete_chunks = if resuming {
+                let chunk_sizes: Vec<i64> =
+                    file_chunks.iter().map(|chunk| chunk.chunk_size).collect();
+                utils::complete_chunks(&temp_path, &chunk_sizes)?
+            } else {
+                utils::remove_if_exists(&temp_path)?;
+                0
+            };
+            if complete_chunks == file_chunks.len() {
+                debug!("Restore complete file {:?}", path);
+                utils::open_file_to_append(&temp_path)?;
+                Self::complete_file(chunk_index, path, file.id)?;
+                continue;
+            }
+            if complete_chunks > 0 {
+                info!("Resume {:?} after {} chunks", path, complete_chunks);
+            }
+            for chunk in file_chunks.into_iter().skip(complete_chunks) {
+                chunks.push(chunk);
+                chunk_files.push(paths.len());
+            }
+            paths.push((path.clone(), file.id));
+        }
         let mut progress = Progress::new(self.progress_sender.clone(), chunks.len());
-        for chunk in chunks {
-            debug!("Request chunk {}", chunk.chunk_identifier);
-            let chunk_content = self.request_chunk(chunk.chunk_identifier.clone())?;
 
-            debug!("Restore path for chunk {}", chunk_content.chunk_identifier);
-            let mut path = self.restore_config.restore_dir.clone();
-            path.push(chunk_index.get_file_path(chunk.file)?);
+        // Files are written to a temporary path and moved into place when they are complete.
+        // The batches are requested with a sliding window of requests in flight.
+        let batches = create_utils::batch_chunks(chunks, self.restore_config.batch_size);
+        let file_paths: Vec<PathBuf> = paths.iter().map(|&(ref path, _)| path.clone()).collect();
+        let mut assembler = FileAssembler::new(&file_paths, chunk_files);
+        {
+            let concurrency = self.restore_config.concurrency;
+            let encryption = &self.config.encryption;
+            let mut sent_batches = batches.iter();
+            self.session.call_window(
+                batches.len(),
+                |index| -> Result<Message, RestoreBackupError> {
+                    Ok(Self::request_chunks(&batches[index]))
+                },
+                concurrency,
+                |response| {
+                    let batch = sent_batches.next().unwrap();
+                    let contents = match response.body {
+                        MessageKind::ReturnChunks(body) => Some(body.chunks),
+                        _ => None,
+                    }.ok_or(RestoreBackupError::NodeCommunicationError)?;
+                    let completed = assembler.write_batch(batch, contents, |chunk, content| {
+                        decode_chunk(encryption, chunk, content)
+                    })?;
+                    for file in completed {
+                        let (ref path, file_id) = paths[file];
+                        Self::complete_file(chunk_index, path, file_id)?;
+                    }
+                    for _ in batch {
+                        progress.increment();
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+        if let Some(file) = assembler.finish() {
+            let (ref path, file_id) = paths[file];
+            Self::complete_file(chunk_index, path, file_id)?;
+        }
+        Ok(())
+    }
+
+    /// Move a file, whose chunks are all written, into place and record it in the journal.
+    fn complete_file(
+        chunk_index: &ChunkIndex,
+        path: &PathBuf,
+        file_id: i32,
+    ) -> Result<(), RestoreBackupError> {
+        utils::replace_file(&utils::temp_path(path), path)?;
+        chunk_index.add_restored_file(file_id, path)?;
+        Ok(())
+    }
+
+    /// Create the selected files without content. Hardlinks are created after the files they
+    /// point to.
+    fn restore_special_files(
+        &self,
+        chunk_index: &ChunkIndex,
+        files: &[(PathBuf, DbFile)],
+        restored: &HashSet<(i32, PathBuf)>,
+    ) -> Result<(), RestoreBackupError> {
+        // Restored files may have been renamed because of a conflict.
+        let restored_paths: HashMap<i32, &PathBuf> =
+            files.iter().map(|&(ref path, ref file)| (file.id, path)).collect();
+
+        for &(ref path, ref file) in files {
+            let file_type = FileType::of_file(&file)?;
+            if file_type == FileType::Regular || restored.contains(&(file.id, path.clone())) {
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                utils::create_folder(&parent.to_path_buf())?;
+            }
 
-            utils::restore_file_content(&chunk_content.chunk_content.as_slice(), &path)?;
-            debug!("Restored chunk {} to {:?}", chunk.chunk_identifier, path);
-            progress.increment();
+            // Like regular files, the files are created at a temporary path and moved into place.
+            let temp_path = utils::temp_path(path);
+            utils::remove_if_exists(&temp_path)?;
+            match file_type {
+                FileType::Symlink => {
+                    let target = file.os_link_target().ok_or(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Symlink without target in chunk index",
+                    ))?;
+                    utils::create_symlink(&PathBuf::from(target), &temp_path)?;
+                }
+                FileType::Hardlink => {
+                    let original = file.hardlink.ok_or(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Hardlink without original file in chunk index",
+                    ))?;
+                    // A skipped original file exists at its path in the backup.
+                    let original_path = match restored_paths.get(&original) {
+                        Some(original_path) => (*original_path).clone(),
+                        None => {
+                            let mut original_path = self.restore_config.restore_dir.clone();
+                            original_path.push(chunk_index.get_file_path(original)?);
+                            original_path
+                        }
+                    };
+                    utils::create_hardlink(&original_path, &temp_path)?;
+                }
+                _ => {
+                    let mode = file.mode.unwrap_or(0o644) as u32;
+                    let device = file.device.unwrap_or(0) as u64;
+                    match utils::create_node(&temp_path, file_type, mode, device) {
+                        Err(ref err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                            warn!("Could not create {} {:?} ({})", file_type.name(), path, err);
+                            continue;
+                        }
+                        result => result?,
+                    }
+                }
+            }
+            utils::replace_file(&temp_path, path)?;
+            chunk_index.add_restored_file(file.id, path)?;
         }
         Ok(())
     }
 
-    /// Query a chunk from the node by chunk identifier
-    fn request_chunk(
-        &mut self,
-        chunk_identifier: String,
-    ) -> Result<ChunkContentElement, RestoreBackupError> {
-        let message = GetChunks::new(vec![chunk_identifier.clone()]);
-        let request = TcpClient::new(RedClientProto)
-            .connect(&self.config.addr, &self.handle.clone())
-            .and_then(|client| client.call(message))
-            .and_then(|response| match response.body {
-                MessageKind::ReturnChunks(body) => Ok(body.chunks),
-                _ => Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Chunk not contained in node response",
-                )),
-            });
-        let mut chunk_contents = self.event_loop.run(request).map_err(
-            |e| RestoreBackupError::from(e),
-        )?;
+    /// Apply permissions, ownership and timestamps to all restored files and folders.
+    fn restore_metadata(
+        &self,
+        chunk_index: &ChunkIndex,
+        files: &[(PathBuf, DbFile)],
+    ) -> Result<(), RestoreBackupError> {
+        for &(ref path, ref file) in files {
+            match FileType::of_file(&file)? {
+                // A hardlink shares the metadata with the file it points to.
+                FileType::Hardlink => continue,
+                // Only the owner of a symlink can be changed (without following it).
+                FileType::Symlink => {
+                    let xattrs = chunk_index.get_xattrs_by_file(file.id)?;
+                    self.apply_metadata(path, None, file.uid, file.gid, xattrs, None, None)?
+                }
+                // Device nodes can not be created without root privileges.
+                FileType::CharDevice | FileType::BlockDevice if !path.exists() => continue,
+                _ => {
+                    let xattrs = chunk_index.get_xattrs_by_file(file.id)?;
+                    self.apply_metadata(
+                        path,
+                        file.mode,
+                        file.uid,
+                        file.gid,
+                        xattrs,
+                        Some(file.last_change_date),
+                        file.access_date,
+                    )?
+                }
+            }
+        }
+        let restore_dir = self.restore_config.restore_dir.clone();
+        self.restore_folder_metadata(&restore_dir, chunk_index, None)
+    }
+
+    /// Apply the metadata of all folders recursively.
+    ///
+    /// A folder is handled after its content, as restoring the content changes the modification
+    /// date of the folder (and a read-only folder can not be written to).
+    fn restore_folder_metadata(
+        &self,
+        root_folder: &PathBuf,
+        chunk_index: &ChunkIndex,
+        parent_folder_id: Option<i32>,
+    ) -> Result<(), RestoreBackupError> {
+        for folder in chunk_index.get_folders_by_parent(parent_folder_id)? {
+            let mut path = root_folder.clone();
+            path.push(folder.os_name());
+            self.restore_folder_metadata(&path, chunk_index, Some(folder.id))?;
+            // Folders outside of the included paths are not restored.
+            if !path.is_dir() {
+                continue;
+            }
+            let xattrs = chunk_index.get_xattrs_by_folder(folder.id)?;
+            self.apply_metadata(
+                &path,
+                folder.mode,
+                folder.uid,
+                folder.gid,
+                xattrs,
+                folder.last_change_date,
+                folder.access_date,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Apply the metadata, that is available in the chunk index, to a file or folder.
+    fn apply_metadata(
+        &self,
+        path: &PathBuf,
+        mode: Option<i32>,
+        uid: Option<i64>,
+        gid: Option<i64>,
+        xattrs: Vec<Xattr>,
+        last_change_date: Option<NaiveDateTime>,
+        access_date: Option<NaiveDateTime>,
+    ) -> Result<(), RestoreBackupError> {
+        debug!("Restore metadata of {:?}", path);
+        // The owner is set first, as changing it may reset the setuid and setgid bits.
+        if let (true, Some(uid), Some(gid)) = (self.restore_config.restore_ownership, uid, gid) {
+            match utils::set_ownership(path, uid as u32, gid as u32) {
+                Err(ref err) if err.raw_os_error() == Some(libc::EPERM) => {
+                    warn!("Could not restore the owner of {:?} ({})", path, err);
+                }
+                result => result?,
+            }
+        }
+        // Extended attributes are set before the permissions, as a read-only file or folder can
+        // not be changed afterwards. POSIX ACLs are set after, as they extend the permissions.
+        let (acls, xattrs): (Vec<Xattr>, Vec<Xattr>) =
+            xattrs.into_iter().partition(|xattr| xattr::is_acl(&xattr.name));
+        self.restore_xattrs(path, xattrs)?;
+        if let Some(mode) = mode {
+            utils::set_permissions(path, mode as u32)?;
+        }
+        self.restore_xattrs(path, acls)?;
+        if let Some(last_change_date) = last_change_date {
+            let access_date = access_date.unwrap_or(last_change_date);
+            utils::set_file_times(path, &access_date, &last_change_date)?;
+        }
+        Ok(())
+    }
+
+    /// Set the selected extended attributes of a file or folder.
+    fn restore_xattrs(
+        &self,
+        path: &PathBuf,
+        xattrs: Vec<Xattr>,
+    ) -> Result<(), RestoreBackupError> {
+        for xattr in xattrs {
+            if !self.restore_config.xattr_filter.matches(&xattr.name) {
+                debug!("Skipped extended attribute {} of {:?}", xattr.name, path);
+                continue;
+            }
+            match xattr::write_xattr(path, &xattr.name, &xattr.value) {
+                // Some namespaces require privileges or are not supported by the file system.
+                Err(ref err) if err.raw_os_error() == Some(libc::EPERM) ||
+                                    err.raw_os_error() == Some(libc::ENOTSUP) => {
+                    warn!(
+                        "Could not restore extended attribute {} of {:?} ({})",
+                        xattr.name,
+                        path,
+                        err
+                    );
+                }
+                result => result?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the message to query the chunks of a batch from the node.
+    fn request_chunks(batch: &[Chunk]) -> Message {
+        let mut chunk_identifiers: Vec<String> = batch
+            .iter()
+            .map(|chunk| chunk.chunk_identifier.clone())
+            .collect();
+        chunk_identifiers.sort();
+        chunk_identifiers.dedup();
+        debug!("Request {} chunks", chunk_identifiers.len());
+        GetChunks::new(chunk_identifiers)
+    }
+}
+
+/// Get the included files of the chunk index, with the path they are restored to.
+///
+/// A hardlink, whose original file is not included, is restored as copy of the original file.
+pub fn select_files(
+    chunk_index: &ChunkIndex,
+    restore_dir: &Path,
+    include: &[Pattern],
+) -> Result<Vec<(PathBuf, DbFile)>, RestoreBackupError> {
+    let mut files = Vec::new();
+    for file in chunk_index.get_all_files()? {
+        let local_path = chunk_index.get_file_path(file.id)?;
+        if !utils::is_included(include, &local_path) {
+            continue;
+        }
+        let file = match (FileType::of_file(&file)?, file.hardlink) {
+            (FileType::Hardlink, Some(original)) => {
+                if utils::is_included(include, &chunk_index.get_file_path(original)?) {
+                    file
+                } else {
+                    chunk_index.get_file(original)?
+                }
+            }
+            _ => file,
+        };
+        files.push((restore_dir.join(local_path), file));
+    }
+    Ok(files)
+}
+
+/// Decrypt and decompress the content of a chunk received from the node.
+fn decode_chunk(
+    encryption: &Option<Encryption>,
+    chunk: &Chunk,
+    content: Vec<u8>,
+) -> Result<Vec<u8>, RestoreBackupError> {
+    let content = utils::decode_chunk_content(content, encryption)?;
+    Ok(Compression::of_chunk(chunk)?.decompress(content)?)
+}
+
+/// A chunk index in a temporary file, which is removed when it is dropped.
+pub struct TempChunkIndex {
+    chunk_index: ChunkIndex,
+}
+
+impl TempChunkIndex {
+    /// Get a new path for a temporary chunk index.
+    pub fn temp_path() -> PathBuf {
+        env::temp_dir().join(format!("redbackup-{}.db", Uuid::new_v4()))
+    }
+
+    /// Open the chunk index at path, which is removed when the `TempChunkIndex` is dropped.
+    pub fn new(path: PathBuf, creation_date: DateTime<Utc>) -> Result<Self, RestoreBackupError> {
+        match ChunkIndex::new(path.clone(), creation_date) {
+            Ok(chunk_index) => Ok(TempChunkIndex { chunk_index }),
+            Err(err) => {
+                let _ = fs::remove_file(path);
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl Deref for TempChunkIndex {
+    type Target = ChunkIndex;
 
-        chunk_contents.pop().ok_or(
-            RestoreBackupError::ChunkNotAvailable(
-                chunk_identifier.clone(),
-            ),
-        )
+    fn deref(&self) -> &ChunkIndex {
+        &self.chunk_index
     }
 }
+
+impl Drop for TempChunkIndex {
+    fn drop(&mut self) {
+        let path = self.chunk_index.get_file_name();
+        debug!("Remove temporary chunk index {:?}", path);
+        if let Err(err) = fs::remove_file(&path) {
+            warn!("Could not remove temporary chunk index {:?}: {}", path, err);
+        }
+    }
+}
+
+/// Request the chunk index of a backup from the node and store it in a temporary file, which is
+/// removed when the returned chunk index is dropped.
+pub fn fetch_chunk_index(
+    session: &mut Session,
+    encryption: &Option<Encryption>,
+    backup_id: &str,
+) -> Result<TempChunkIndex, RestoreBackupError> {
+    let path = TempChunkIndex::temp_path();
+    if let Err(err) = fetch_chunk_index_to(session, encryption, backup_id, &path) {
+        let _ = fs::remove_file(&path);
+        return Err(err);
+    }
+    TempChunkIndex::new(path, Utc::now())
+}
+
+/// Request the chunk index of a backup from the node and store it at the given (new) path.
+pub fn fetch_chunk_index_to(
+    session: &mut Session,
+    encryption: &Option<Encryption>,
+    backup_id: &str,
+    path: &PathBuf,
+) -> Result<(), RestoreBackupError> {
+    debug!(
+        "Request chunk index {} from node at {}",
+        backup_id,
+        session.addr()
+    );
+    let message = GetChunks::new(vec![backup_id.into()]);
+    let response = session.call(message).map_err(
+        |e| RestoreBackupError::from(e),
+    )?;
+
+    let chunks = match response.body {
+        MessageKind::ReturnChunks(body) => Some(body.chunks),
+        _ => None,
+    }.ok_or(RestoreBackupError::NodeCommunicationError)?;
+
+    let chunk: &ChunkContentElement = chunks.get(0).ok_or(
+        RestoreBackupError::RootHandleChunkNotAvailable(backup_id.into()),
+    )?;
+
+    let chunk_content = utils::decode_chunk_content(chunk.chunk_content.clone(), encryption)?;
+    utils::restore_file_content(&chunk_content.as_slice(), path)?;
+    Ok(())
+}
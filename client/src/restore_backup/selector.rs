@@ -0,0 +1,86 @@
+use chrono::prelude::*;
+
+use redbackup_protocol::Session;
+
+use encryption::Encryption;
+use list_backups::{self, BackupInfo, ListBackupsFilter};
+use super::config::RestoreBackupConfigError;
+use super::error::RestoreBackupError;
+
+/// Selects the backup to restore.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BackupSelector {
+    /// A backup by its id (the hash of its chunk index).
+    Id(String),
+    /// The most recent backup, that matches the filter and was created before the date.
+    Latest {
+        filter: ListBackupsFilter,
+        before: Option<DateTime<Utc>>,
+    },
+}
+
+impl BackupSelector {
+    /// Select a backup by its id, or the most recent backup with "latest".
+    ///
+    /// The filter and the date (format: %Y-%m-%dT%H:%M, UTC) only apply to "latest".
+    pub fn new(
+        backup: &str,
+        filter: ListBackupsFilter,
+        before: Option<&str>,
+    ) -> Result<BackupSelector, RestoreBackupConfigError> {
+        if backup != "latest" {
+            if backup.len() != 64 {
+                // This validation is hash dependent.
+                return Err(RestoreBackupConfigError::InvalidBackupId(backup.into()));
+            }
+            if !filter.is_empty() || before.is_some() {
+                return Err(RestoreBackupConfigError::SelectorWithBackupId(backup.into()));
+            }
+            return Ok(BackupSelector::Id(backup.into()));
+        }
+
+        let before = match before {
+            Some(before) => {
+                let date = NaiveDateTime::parse_from_str(before, "%Y-%m-%dT%H:%M").map_err(|_| {
+                    RestoreBackupConfigError::InvalidDateFormat(before.into())
+                })?;
+                Some(DateTime::from_utc(date, Utc))
+            }
+            None => None,
+        };
+        Ok(BackupSelector::Latest { filter, before })
+    }
+
+    /// Get the id of the selected backup, by the root handles on the node.
+    pub fn resolve(
+        &self,
+        session: &mut Session,
+        encryption: &Option<Encryption>,
+    ) -> Result<String, RestoreBackupError> {
+        let (filter, before) = match *self {
+            BackupSelector::Id(ref backup_id) => return Ok(backup_id.clone()),
+            BackupSelector::Latest { ref filter, before } => (filter, before),
+        };
+
+        let backups = list_backups::list_backups(session, encryption, filter)?;
+        select_latest(backups, before).ok_or(RestoreBackupError::NoMatchingBackup)
+    }
+}
+
+/// Get the id of the most recent backup, that was created before the date (if any).
+///
+/// Backups without manifest are ignored, as their creation date is unknown.
+pub fn select_latest(backups: Vec<BackupInfo>, before: Option<DateTime<Utc>>) -> Option<String> {
+    backups
+        .into_iter()
+        .filter_map(|backup| {
+            let BackupInfo { backup_id, manifest, .. } = backup;
+            manifest.map(|manifest| (manifest.creation_date, backup_id))
+        })
+        .filter(|&(creation_date, _)| match before {
+            Some(before) => creation_date < before,
+            None => true,
+        })
+        .max()
+        .map(|(_, backup_id)| backup_id)
+}
@@ -1,20 +1,279 @@
-use std::path::PathBuf;
-use std::io::{Error, Write};
-use std::fs::{OpenOptions, DirBuilder};
+use std::path::{Path, PathBuf};
+use std::io::{Error, ErrorKind, Write};
+use std::fs::{self, File, OpenOptions, DirBuilder, Permissions};
+use std::ffi::{CString, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{symlink, OpenOptionsExt, PermissionsExt};
 
-/// Writes the content buffer to a file path.
+use chrono::prelude::*;
+use glob::Pattern;
+use libc;
+
+use chunk_index::FileType;
+use chunk_index::schema::File as DbFile;
+use create_backup::create_utils;
+use encryption::{Encryption, EncryptionError};
+
+/// Check if a path of the chunk index is selected by the include patterns (all without patterns).
+///
+/// The patterns are relative to the backup root, and a selected folder includes its content.
+pub fn is_included(include: &[Pattern], path: &Path) -> bool {
+    if include.is_empty() {
+        return true;
+    }
+    // Paths in the chunk index start with the name of the backup root folder.
+    let mut local_path: PathBuf = path.iter().skip(1).collect();
+    loop {
+        if include.iter().any(|pattern| pattern.matches_path(&local_path)) {
+            return true;
+        }
+        if !local_path.pop() {
+            return false;
+        }
+    }
+}
+
+/// Writes the content buffer to a new file path, which only the owner can read and write.
 pub fn restore_file_content(content: &[u8], path: &PathBuf) -> Result<(), Error> {
     debug!("Restore file content to {:?}", path);
-    let mut fhandle = OpenOptions::new().write(true).create_new(true).open(&path)?;
+    let mut fhandle = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?;
     debug!("Opened file {:?} to write", path);
     fhandle.write_all(content)?;
     debug!("Restored file {:?}", path);
     Ok(())
 }
 
+/// Create a new, empty file to append chunk contents to.
+pub fn create_file(path: &PathBuf) -> Result<File, Error> {
+    debug!("Create file {:?}", path);
+    OpenOptions::new().write(true).create_new(true).open(&path)
+}
+
+/// Get the temporary path, a file is restored to before it is moved into place.
+pub fn temp_path(path: &PathBuf) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".redbackup-tmp");
+    path.with_file_name(name)
+}
+
+/// Remove a file (e.g. leftovers of an aborted restore), if it exists.
+pub fn remove_if_exists(path: &PathBuf) -> Result<(), Error> {
+    match fs::remove_file(path) {
+        Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Open a partially restored file to append further chunk contents (created if missing).
+pub fn open_file_to_append(path: &PathBuf) -> Result<File, Error> {
+    debug!("Open file {:?} to append", path);
+    OpenOptions::new().append(true).create(true).open(&path)
+}
+
+/// Count the chunks, that were completely written to a partially restored file.
+///
+/// The file is truncated after the last complete chunk, as the following chunk may have been
+/// written partially. A missing file has no complete chunks.
+pub fn complete_chunks(path: &PathBuf, chunk_sizes: &[i64]) -> Result<usize, Error> {
+    let len = match fs::symlink_metadata(path) {
+        Ok(ref metadata) if metadata.is_file() => metadata.len(),
+        Ok(_) => {
+            remove_if_exists(path)?;
+            return Ok(0);
+        }
+        Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let mut complete = 0;
+    let mut complete_len = 0;
+    for chunk_size in chunk_sizes {
+        if complete_len + *chunk_size as u64 > len {
+            break;
+        }
+        complete += 1;
+        complete_len += *chunk_size as u64;
+    }
+    if complete_len < len {
+        debug!("Truncate {:?} to {} bytes", path, complete_len);
+        OpenOptions::new().write(true).open(path)?.set_len(complete_len)?;
+    }
+    Ok(complete)
+}
+
+/// Move a restored file from its temporary path into place (replaces an existing file).
+pub fn replace_file(temp_path: &PathBuf, path: &PathBuf) -> Result<(), Error> {
+    debug!("Move {:?} to {:?}", temp_path, path);
+    fs::rename(temp_path, path)
+}
+
+/// Get a path next to an existing file, that does not exist yet (e.g. "notes.txt.restored-2").
+pub fn unique_path(path: &PathBuf) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_os_string();
+    let mut counter = 1;
+    loop {
+        let mut unique_name = name.clone();
+        unique_name.push(".restored");
+        if counter > 1 {
+            unique_name.push(format!("-{}", counter));
+        }
+        let unique_path = path.with_file_name(unique_name);
+        if fs::symlink_metadata(&unique_path).is_err() {
+            return unique_path;
+        }
+        counter += 1;
+    }
+}
+
+/// Check if an existing file has the same content as a file of the chunk index.
+///
+/// Regular files are compared by their content hash, symlinks by their target. Other files
+/// (and files of older chunk indices without content hash) are always considered different.
+pub fn has_same_content(path: &PathBuf, file: &DbFile) -> Result<bool, Error> {
+    let metadata = fs::symlink_metadata(path)?;
+    match FileType::of_file(file)? {
+        FileType::Regular => {
+            match file.content_hash {
+                Some(ref content_hash) if metadata.is_file() &&
+                                              metadata.len() == file.size as u64 => {
+                    Ok(create_utils::file_hash(path)? == *content_hash)
+                }
+                _ => Ok(false),
+            }
+        }
+        FileType::Symlink if metadata.file_type().is_symlink() => {
+            Ok(Some(fs::read_link(path)?.into_os_string()) == file.os_link_target())
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Check if a file of the journal of an interrupted restore still exists as it was restored.
+///
+/// Regular files are verified by their content hash (by their size in older chunk indices without
+/// content hash), symlinks by their target. Other files only need to exist.
+pub fn is_restored(path: &PathBuf, file: &DbFile) -> Result<bool, Error> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    match FileType::of_file(file)? {
+        FileType::Regular if file.content_hash.is_none() => {
+            Ok(metadata.is_file() && metadata.len() == file.size as u64)
+        }
+        FileType::Regular | FileType::Symlink => has_same_content(path, file),
+        _ => Ok(true),
+    }
+}
+
+/// Append the content buffer to an open file.
+pub fn append_file_content(content: &[u8], fhandle: &mut File) -> Result<(), Error> {
+    fhandle.write_all(content)
+}
+
+/// Decode a chunk content received from the node (decrypt, if enabled).
+pub fn decode_chunk_content(
+    content: Vec<u8>,
+    encryption: &Option<Encryption>,
+) -> Result<Vec<u8>, EncryptionError> {
+    match *encryption {
+        Some(ref encryption) => encryption.decrypt(&content),
+        None => Ok(content),
+    }
+}
+
 /// Create a folder recursively (with parent folders)
 pub fn create_folder(path: &PathBuf) -> Result<(), Error> {
     debug!("Create folder {:?}", path);
     DirBuilder::new().recursive(true).create(path)?;
     Ok(())
 }
+
+/// Create a symlink at path, that points to target.
+pub fn create_symlink(target: &PathBuf, path: &PathBuf) -> Result<(), Error> {
+    debug!("Create symlink {:?} to {:?}", path, target);
+    symlink(target, path)
+}
+
+/// Create a hardlink at path to an already restored file.
+pub fn create_hardlink(original: &PathBuf, path: &PathBuf) -> Result<(), Error> {
+    debug!("Create hardlink {:?} to {:?}", path, original);
+    fs::hard_link(original, path)
+}
+
+/// Create a FIFO or a device node (creating device nodes requires root privileges).
+pub fn create_node(
+    path: &PathBuf,
+    file_type: FileType,
+    mode: u32,
+    device: u64,
+) -> Result<(), Error> {
+    debug!("Create {} {:?}", file_type.name(), path);
+    let kind = match file_type {
+        FileType::Fifo => libc::S_IFIFO,
+        FileType::CharDevice => libc::S_IFCHR,
+        FileType::BlockDevice => libc::S_IFBLK,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("A {} is not a node", file_type.name()),
+            ))
+        }
+    };
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mode = kind | (mode & 0o7777) as libc::mode_t;
+    if unsafe { libc::mknod(c_path.as_ptr(), mode, device as libc::dev_t) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Set the permission bits of a restored file or folder.
+pub fn set_permissions(path: &PathBuf, mode: u32) -> Result<(), Error> {
+    debug!("Set permissions of {:?} to {:o}", path, mode);
+    fs::set_permissions(path, Permissions::from_mode(mode & 0o7777))
+}
+
+/// Set the owner and group of a restored file or folder (symlinks are not followed).
+pub fn set_ownership(path: &PathBuf, uid: u32, gid: u32) -> Result<(), Error> {
+    debug!("Set ownership of {:?} to {}:{}", path, uid, gid);
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    if unsafe { libc::lchown(c_path.as_ptr(), uid, gid) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Set the access and modification date of a restored file or folder.
+///
+/// The dates are local dates, as they are stored in the chunk index.
+pub fn set_file_times(
+    path: &PathBuf,
+    access_date: &NaiveDateTime,
+    last_change_date: &NaiveDateTime,
+) -> Result<(), Error> {
+    debug!("Set file times of {:?}", path);
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let times = [to_timeval(access_date), to_timeval(last_change_date)];
+    if unsafe { libc::utimes(c_path.as_ptr(), times.as_ptr()) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn to_timeval(date: &NaiveDateTime) -> libc::timeval {
+    // Local dates that do not exist (e.g. due to a DST change) are interpreted as UTC.
+    let seconds = match Local.from_local_datetime(date).earliest() {
+        Some(local_date) => local_date.timestamp(),
+        None => date.timestamp(),
+    };
+    libc::timeval {
+        tv_sec: seconds as libc::time_t,
+        tv_usec: (date.nanosecond() / 1000) as libc::suseconds_t,
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::prelude::*;
+use list_backups::{BackupInfo, BackupManifest, ListBackupsFilter};
+use restore_backup::BackupSelector;
+use restore_backup::selector;
+use restore_backup::config::RestoreBackupConfigError;
+
+const BACKUP_ID: &'static str = "7fcaddc8772aaa616f43361c217c23d308e933465b2099d00ba1418fec1839f2";
+
+#[test]
+fn select_backup_by_id() {
+    let selector = BackupSelector::new(BACKUP_ID, ListBackupsFilter::default(), None)
+        .expect("Could not create selector");
+    assert_eq!(selector, BackupSelector::Id(String::from(BACKUP_ID)));
+
+    match BackupSelector::new("7fcaddc8", ListBackupsFilter::default(), None) {
+        Err(RestoreBackupConfigError::InvalidBackupId(id)) => assert_eq!(id, "7fcaddc8"),
+        _ => panic!("A short backup id is invalid"),
+    }
+
+    let filter = ListBackupsFilter::new(Some("warp"), None, None, None);
+    match BackupSelector::new(BACKUP_ID, filter, None) {
+        Err(RestoreBackupConfigError::SelectorWithBackupId(_)) => {}
+        _ => panic!("A filter only applies to the latest backup"),
+    }
+}
+
+#[test]
+fn select_latest_backup() {
+    let filter = ListBackupsFilter::new(Some("warp"), None, None, Some("weekly"));
+    let selector = BackupSelector::new("latest", filter.clone(), Some("2018-02-26T07:08"))
+        .expect("Could not create selector");
+    assert_eq!(
+        selector,
+        BackupSelector::Latest {
+            filter,
+            before: Some(Utc.ymd(2018, 2, 26).and_hms(7, 8, 0)),
+        }
+    );
+
+    match BackupSelector::new("latest", ListBackupsFilter::default(), Some("yesterday")) {
+        Err(RestoreBackupConfigError::InvalidDateFormat(date)) => assert_eq!(date, "yesterday"),
+        _ => panic!("The date format is invalid"),
+    }
+}
+
+fn backup(backup_id: &str, creation_date: Option<DateTime<Utc>>) -> BackupInfo {
+    BackupInfo {
+        backup_id: String::from(backup_id),
+        expiration_date: Utc.ymd(2030, 1, 1).and_hms(0, 0, 0),
+        manifest: creation_date.map(|creation_date| {
+            BackupManifest {
+                hostname: Some(String::from("warp")),
+                source_path: String::from("/home/aisatsana"),
+                label: None,
+                tags: Vec::new(),
+                creation_date,
+                file_count: None,
+                total_size: None,
+            }
+        }),
+    }
+}
+
+fn backups() -> Vec<BackupInfo> {
+    vec![
+        backup("monday", Some(Utc.ymd(2018, 2, 26).and_hms(7, 8, 9))),
+        backup("wednesday", Some(Utc.ymd(2018, 2, 28).and_hms(7, 8, 9))),
+        backup("unknown", None),
+        backup("tuesday", Some(Utc.ymd(2018, 2, 27).and_hms(7, 8, 9))),
+    ]
+}
+
+#[test]
+fn select_latest_of_backups() {
+    assert_eq!(
+        selector::select_latest(backups(), None),
+        Some(String::from("wednesday"))
+    );
+}
+
+#[test]
+fn select_latest_of_backups_before_date() {
+    let before = Utc.ymd(2018, 2, 28).and_hms(0, 0, 0);
+    assert_eq!(
+        selector::select_latest(backups(), Some(before)),
+        Some(String::from("tuesday"))
+    );
+    // Only backups created before the date are selected.
+    let before = Utc.ymd(2018, 2, 27).and_hms(7, 8, 9);
+    assert_eq!(
+        selector::select_latest(backups(), Some(before)),
+        Some(String::from("monday"))
+    );
+}
+
+#[test]
+fn select_latest_without_matching_backup() {
+    let before = Utc.ymd(2018, 2, 26).and_hms(0, 0, 0);
+    assert_eq!(selector::select_latest(backups(), Some(before)), None);
+    assert_eq!(selector::select_latest(vec![backup("unknown", None)], None), None);
+    assert_eq!(selector::select_latest(Vec::new(), None), None);
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use super::test_data;
+use browse_backup::{self, BrowseBackupError};
+use chunk_index::schema::*;
+
+#[test]
+fn list_entries() {
+    let chunk_index = test_data::prepare_chunk_index("browse_backup_list_entries");
+    let root = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &root);
+    let folder = chunk_index
+        .add_folder(NewFolder {
+            name: String::from("music"),
+            parent_folder: Some(root.id),
+            mode: None,
+            uid: None,
+            gid: None,
+            last_change_date: None,
+            access_date: None,
+            raw_name: None,
+        })
+        .expect("Could not add folder");
+    test_data::prepare_file(&chunk_index, &folder);
+
+    let entries = browse_backup::list_entries(&chunk_index, &PathBuf::new(), false)
+        .expect("Could not list entries");
+    let paths: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+    assert_eq!(paths, vec![PathBuf::from("bibio"), PathBuf::from("music")]);
+    assert_eq!(entries[0].kind, "regular");
+    assert_eq!(entries[0].size, Some(9));
+    assert_eq!(entries[0].last_change_date, Some(file.last_change_date));
+    assert_eq!(entries[1].kind, "folder");
+    assert_eq!(entries[1].size, None);
+
+    let entries = browse_backup::list_entries(&chunk_index, &PathBuf::new(), true)
+        .expect("Could not list entries recursively");
+    let paths: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            PathBuf::from("bibio"),
+            PathBuf::from("music"),
+            PathBuf::from("music/bibio"),
+        ]
+    );
+
+    let entries = browse_backup::list_entries(&chunk_index, &PathBuf::from("music/bibio"), false)
+        .expect("Could not list file");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].path, PathBuf::from("music/bibio"));
+
+    match browse_backup::list_entries(&chunk_index, &PathBuf::from("music/missing"), false) {
+        Err(BrowseBackupError::PathNotFound(path)) => assert_eq!(path, "music/missing"),
+        _ => panic!("A missing path must not be listed"),
+    }
+}
@@ -1,6 +1,9 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
 use std::path::PathBuf;
 
 use super::test_data;
+use chrono::prelude::*;
 use chunk_index::schema::*;
 
 
@@ -17,6 +20,12 @@ fn add_folders() {
     let folder2 = NewFolder {
         name: String::from("bibio"),
         parent_folder: Some(folder1.id),
+        mode: None,
+        uid: None,
+        gid: None,
+        last_change_date: None,
+        access_date: None,
+        raw_name: None,
     };
 
     assert!(chunk_index.add_folder(folder2).is_ok());
@@ -42,12 +51,37 @@ fn add_chunks() {
         ),
         file: file.id,
         predecessor: Some(chunk1.id),
+        chunk_offset: 9,
+        chunk_size: 4,
+        compression: None,
     };
     chunk_index.add_chunk(chunk2).expect(
         "Chunk could not be added",
     );
 }
 
+#[test]
+fn add_same_chunk_twice_to_file() {
+    let chunk_index = test_data::prepare_chunk_index("add_same_chunk_twice_to_file");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+    let chunk1 = test_data::prepare_chunk(&chunk_index, &file);
+
+    let chunk2 = NewChunk {
+        chunk_identifier: chunk1.chunk_identifier.clone(),
+        file: file.id,
+        predecessor: Some(chunk1.id),
+        chunk_offset: 9,
+        chunk_size: 9,
+        compression: None,
+    };
+    let chunk2 = chunk_index.add_chunk(chunk2).expect(
+        "Chunk could not be added",
+    );
+    assert_eq!(chunk2.predecessor, Some(chunk1.id));
+    assert!(chunk1.id != chunk2.id);
+}
+
 
 #[test]
 fn get_all_chunks() {
@@ -63,6 +97,44 @@ fn get_all_chunks() {
     assert_eq!(chunks, vec![chunk1]);
 }
 
+#[test]
+fn get_chunks_by_file() {
+    let chunk_index = test_data::prepare_chunk_index("get_chunks_by_file");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+    let chunk1 = test_data::prepare_chunk(&chunk_index, &file);
+
+    let chunk2 = chunk_index
+        .add_chunk(NewChunk {
+            chunk_identifier: String::from(
+                "f6056ef7890a99494c34951817c2ed4fd3608a8488ef0ae6f2afac93ed76854e",
+            ),
+            file: file.id,
+            predecessor: Some(chunk1.id),
+            chunk_offset: 9,
+            chunk_size: 4,
+            compression: None,
+        })
+        .expect("Chunk 2 could not be added");
+    let chunk3 = chunk_index
+        .add_chunk(NewChunk {
+            chunk_identifier: String::from(
+                "0596c5800313885c1a4886e2b45f6389bc573c9487d892f02119d7f1f0ddf579",
+            ),
+            file: file.id,
+            predecessor: Some(chunk2.id),
+            chunk_offset: 13,
+            chunk_size: 44,
+            compression: None,
+        })
+        .expect("Chunk 3 could not be added");
+
+    let chunks = chunk_index.get_chunks_by_file(file.id).expect(
+        "Could not get chunks by file",
+    );
+    assert_eq!(chunks, vec![chunk1, chunk2, chunk3]);
+}
+
 #[test]
 fn get_file_path() {
     let chunk_index = test_data::prepare_chunk_index("get_file_path");
@@ -88,6 +160,12 @@ fn get_folders_by_parent() {
     let folder2 = NewFolder {
         name: String::from("bibio"),
         parent_folder: Some(folder1.id),
+        mode: None,
+        uid: None,
+        gid: None,
+        last_change_date: None,
+        access_date: None,
+        raw_name: None,
     };
 
     let folder2 = chunk_index.add_folder(folder2).expect(
@@ -104,3 +182,236 @@ fn get_folders_by_parent() {
     );
     assert_eq!(folders_under_folder1, vec![folder2.clone()]);
 }
+
+#[test]
+fn set_manifest() {
+    let chunk_index = test_data::prepare_chunk_index("set_manifest");
+    assert_eq!(chunk_index.get_manifest().expect("Could not get manifest"), None);
+
+    let first = NewManifest {
+        source_path: String::from("/home/aisatsana"),
+        creation_date: NaiveDate::from_ymd(2016, 11, 28).and_hms(7, 8, 9),
+        key_fingerprint: None,
+        hostname: None,
+        label: None,
+        file_count: None,
+        total_size: None,
+    };
+    chunk_index.set_manifest(first).expect("Could not set manifest");
+
+    // A chunk index has only one manifest.
+    let second = NewManifest {
+        source_path: String::from("/home/bibio"),
+        creation_date: NaiveDate::from_ymd(2016, 11, 29).and_hms(7, 8, 9),
+        key_fingerprint: Some(String::from("fingerprint")),
+        hostname: Some(String::from("warp")),
+        label: Some(String::from("weekly")),
+        file_count: Some(2),
+        total_size: Some(18),
+    };
+    let manifest = chunk_index.set_manifest(second).expect("Could not set manifest");
+
+    assert_eq!(
+        chunk_index.get_manifest().expect("Could not get manifest"),
+        Some(manifest.clone())
+    );
+    assert_eq!(manifest.source_path, "/home/bibio");
+    assert_eq!(manifest.key_fingerprint, Some(String::from("fingerprint")));
+    assert_eq!(manifest.hostname, Some(String::from("warp")));
+    assert_eq!(manifest.label, Some(String::from("weekly")));
+    assert!(!manifest.completed);
+
+    chunk_index.set_completed().expect("Could not complete backup");
+    assert!(chunk_index.get_manifest().unwrap().unwrap().completed);
+}
+
+#[test]
+fn set_chunks_uploaded() {
+    let chunk_index = test_data::prepare_chunk_index("set_chunks_uploaded");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+    let chunk = test_data::prepare_chunk(&chunk_index, &file);
+    assert!(!chunk.uploaded);
+    assert_eq!(chunk_index.get_chunks_to_upload().unwrap(), vec![chunk.clone()]);
+
+    chunk_index
+        .set_chunks_uploaded(&[String::from("unknown")])
+        .expect("Could not set chunks uploaded");
+    assert_eq!(chunk_index.get_chunks_to_upload().unwrap().len(), 1);
+    chunk_index
+        .set_chunks_uploaded(&[chunk.chunk_identifier])
+        .expect("Could not set chunks uploaded");
+    assert!(chunk_index.get_chunks_to_upload().unwrap().is_empty());
+}
+
+#[test]
+fn set_tags() {
+    let chunk_index = test_data::prepare_chunk_index("set_tags");
+    assert!(chunk_index.get_tags().expect("Could not get tags").is_empty());
+
+    chunk_index
+        .set_tags(&[String::from("music"), String::from("home")])
+        .expect("Could not set tags");
+    chunk_index.set_tags(&[String::from("weekly"), String::from("home")]).expect(
+        "Could not set tags",
+    );
+    assert_eq!(
+        chunk_index.get_tags().expect("Could not get tags"),
+        vec![String::from("home"), String::from("weekly")]
+    );
+}
+
+#[test]
+fn restored_files() {
+    let chunk_index = test_data::prepare_chunk_index("restored_files");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+    assert!(chunk_index.get_restored_files().unwrap().is_empty());
+
+    let path = PathBuf::from("/tmp/restore/root/bibio.restored");
+    chunk_index.add_restored_file(file.id, &path).expect("Could not add restored file");
+    assert_eq!(
+        chunk_index.get_restored_files().expect("Could not get restored files"),
+        vec![(file.id, path)]
+    );
+}
+
+#[test]
+fn get_file_by_path() {
+    let chunk_index = test_data::prepare_chunk_index("get_file_by_path");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+
+    let path = PathBuf::from(format!("{}/{}", folder.name, file.name));
+    assert_eq!(
+        chunk_index.get_file_by_path(&path).expect("Could not get file by path"),
+        Some(file)
+    );
+
+    let path = PathBuf::from(format!("{}/{}", folder.name, "missing"));
+    assert_eq!(
+        chunk_index.get_file_by_path(&path).expect("Could not get file by path"),
+        None
+    );
+}
+
+#[test]
+fn add_xattrs() {
+    let chunk_index = test_data::prepare_chunk_index("add_xattrs");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+
+    chunk_index
+        .add_xattr(NewXattr {
+            file: Some(file.id),
+            folder: None,
+            name: String::from("user.comment"),
+            value: b"bibio".to_vec(),
+        })
+        .expect("Could not add file xattr");
+    chunk_index
+        .add_xattr(NewXattr {
+            file: None,
+            folder: Some(folder.id),
+            name: String::from("security.selinux"),
+            value: b"unconfined_u:object_r:user_home_t:s0".to_vec(),
+        })
+        .expect("Could not add folder xattr");
+
+    let file_xattrs = chunk_index.get_xattrs_by_file(file.id).expect(
+        "Could not get xattrs by file",
+    );
+    assert_eq!(file_xattrs.len(), 1);
+    assert_eq!(file_xattrs[0].name, "user.comment");
+    assert_eq!(file_xattrs[0].value, b"bibio".to_vec());
+
+    let folder_xattrs = chunk_index.get_xattrs_by_folder(folder.id).expect(
+        "Could not get xattrs by folder",
+    );
+    assert_eq!(folder_xattrs.len(), 1);
+    assert_eq!(folder_xattrs[0].name, "security.selinux");
+}
+
+#[test]
+fn get_file_path_with_raw_names() {
+    let chunk_index = test_data::prepare_chunk_index("get_file_path_with_raw_names");
+    let folder = test_data::prepare_folder(&chunk_index);
+
+    // "caf\xe9" is a Latin-1 encoded name, that is not valid unicode.
+    let (name, raw_name) = encode_name(OsStr::from_bytes(b"caf\xe9"));
+    assert_eq!(name, "caf\u{fffd}");
+    assert_eq!(raw_name, Some(b"caf\xe9".to_vec()));
+    assert_eq!(encode_name(OsStr::new("bibio")), (String::from("bibio"), None));
+
+    let file = chunk_index
+        .add_file(NewFile {
+            name,
+            last_change_date: NaiveDate::from_ymd(2016, 11, 28).and_hms(7, 8, 9),
+            folder: folder.id,
+            size: 0,
+            mode: None,
+            uid: None,
+            gid: None,
+            access_date: None,
+            file_type: String::from("regular"),
+            link_target: None,
+            hardlink: None,
+            device: None,
+            raw_name,
+            raw_link_target: None,
+            content_hash: None,
+        })
+        .expect("Could not add file with raw name");
+
+    let path = chunk_index.get_file_path(file.id).expect(
+        "Could not get file path",
+    );
+    let mut expected = PathBuf::from(&folder.name);
+    expected.push(OsStr::from_bytes(b"caf\xe9"));
+    assert_eq!(path, expected);
+}
+
+#[test]
+fn get_file_by_path_with_raw_names() {
+    let chunk_index = test_data::prepare_chunk_index("get_file_by_path_with_raw_names");
+    let folder = test_data::prepare_folder(&chunk_index);
+
+    // Both Latin-1 encoded names have the same lossy name "caf\u{fffd}".
+    let mut files = Vec::new();
+    for raw in &[&b"caf\xe9"[..], &b"caf\xe8"[..]] {
+        let (name, raw_name) = encode_name(OsStr::from_bytes(raw));
+        let file = chunk_index
+            .add_file(NewFile {
+                name,
+                last_change_date: NaiveDate::from_ymd(2016, 11, 28).and_hms(7, 8, 9),
+                folder: folder.id,
+                size: 0,
+                mode: None,
+                uid: None,
+                gid: None,
+                access_date: None,
+                file_type: String::from("regular"),
+                link_target: None,
+                hardlink: None,
+                device: None,
+                raw_name,
+                raw_link_target: None,
+                content_hash: None,
+            })
+            .expect("Could not add file with raw name");
+        files.push(file);
+    }
+
+    for (raw, file) in [&b"caf\xe9"[..], &b"caf\xe8"[..]].iter().zip(&files) {
+        let mut path = PathBuf::from(&folder.name);
+        path.push(OsStr::from_bytes(raw));
+        let found = chunk_index.get_file_by_path(&path).expect(
+            "Could not get file by path",
+        );
+        assert_eq!(found.map(|found| found.id), Some(file.id));
+    }
+
+    let mut path = PathBuf::from(&folder.name);
+    path.push(OsStr::from_bytes(b"caf\xe7"));
+    assert_eq!(chunk_index.get_file_by_path(&path).unwrap(), None);
+}
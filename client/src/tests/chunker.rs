@@ -0,0 +1,62 @@
+use create_backup::chunker::{Chunker, MIN_CHUNK_SIZE, MAX_CHUNK_SIZE};
+
+/// Generate reproducible pseudo random test data (xorshift).
+fn random_data(len: usize) -> Vec<u8> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+fn chunks_of(data: &[u8]) -> Vec<Vec<u8>> {
+    Chunker::new(data)
+        .map(|chunk| chunk.expect("Chunker returned an Error"))
+        .collect()
+}
+
+#[test]
+fn empty_input_has_no_chunks() {
+    assert_eq!(chunks_of(&[]).len(), 0);
+}
+
+#[test]
+fn small_input_is_a_single_chunk() {
+    let chunks = chunks_of(b"redbackup");
+    assert_eq!(chunks, vec![b"redbackup".to_vec()]);
+}
+
+#[test]
+fn chunks_reassemble_to_input() {
+    let data = random_data(16 * 1024 * 1024);
+    let chunks = chunks_of(&data);
+
+    assert!(chunks.len() > 1);
+    let (last, others) = chunks.split_last().unwrap();
+    assert!(others.iter().all(|c| {
+        c.len() >= MIN_CHUNK_SIZE && c.len() <= MAX_CHUNK_SIZE
+    }));
+    assert!(last.len() <= MAX_CHUNK_SIZE);
+    assert_eq!(chunks.concat(), data);
+}
+
+#[test]
+fn modification_only_changes_surrounding_chunks() {
+    let data = random_data(16 * 1024 * 1024);
+    let mut modified = data.clone();
+    for i in 0..100 {
+        modified.insert(8 * 1024 * 1024 + i, 42);
+    }
+
+    let chunks = chunks_of(&data);
+    let modified_chunks = chunks_of(&modified);
+    let changed = modified_chunks
+        .iter()
+        .filter(|c| !chunks.contains(c))
+        .count();
+    assert!(changed <= 2);
+}
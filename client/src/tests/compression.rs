@@ -0,0 +1,64 @@
+use compression::Compression;
+
+fn compressible_content() -> Vec<u8> {
+    b"redbackup ".iter().cycle().take(10 * 1024).cloned().collect()
+}
+
+/// Pseudo random content, that can not be compressed.
+fn incompressible_content() -> Vec<u8> {
+    let mut state: u32 = 42;
+    (0..10 * 1024)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn from_name() {
+    assert_eq!(Compression::from_name("none"), Some(Compression::None));
+    assert_eq!(Compression::from_name("zstd"), Some(Compression::Zstd));
+    assert_eq!(Compression::from_name("lz4"), Some(Compression::Lz4));
+    assert_eq!(Compression::from_name("gzip"), None);
+    assert_eq!(Compression::from_name(Compression::Lz4.name()), Some(Compression::Lz4));
+}
+
+#[test]
+fn uncompressed_is_stored_as_null() {
+    assert_eq!(Compression::None.to_column(), None);
+    assert_eq!(Compression::Zstd.to_column(), Some(String::from("zstd")));
+}
+
+#[test]
+fn zstd_roundtrip() {
+    let content = compressible_content();
+    let compressed = Compression::Zstd.compress(content.clone()).unwrap();
+    assert!(compressed.len() < content.len());
+    assert_eq!(Compression::Zstd.decompress(compressed).unwrap(), content);
+}
+
+#[test]
+fn lz4_roundtrip() {
+    let content = compressible_content();
+    let compressed = Compression::Lz4.compress(content.clone()).unwrap();
+    assert!(compressed.len() < content.len());
+    assert_eq!(Compression::Lz4.decompress(compressed).unwrap(), content);
+}
+
+#[test]
+fn none_leaves_content_untouched() {
+    let content = compressible_content();
+    assert_eq!(Compression::None.compress(content.clone()).unwrap(), content);
+    assert_eq!(Compression::None.decompress(content.clone()).unwrap(), content);
+}
+
+#[test]
+fn max_compressed_size() {
+    let content = incompressible_content();
+    let size = content.len() as u64;
+    for compression in &[Compression::None, Compression::Zstd, Compression::Lz4] {
+        let compressed = compression.compress(content.clone()).unwrap();
+        assert!(compressed.len() as u64 <= compression.max_compressed_size(size));
+    }
+}
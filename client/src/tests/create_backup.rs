@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::prelude::*;
+use chunk_index::ChunkIndex;
+use chunk_index::schema::NewManifest;
+use create_backup::find_resumable_chunk_index;
+
+/// Create an empty chunk index storage for a test.
+fn prepare_storage(test_name: &str) -> PathBuf {
+    let storage = PathBuf::from(format!("{}/test-storage-{}", env!("OUT_DIR"), test_name));
+    if storage.exists() {
+        fs::remove_dir_all(&storage).unwrap();
+    }
+    fs::create_dir_all(&storage).unwrap();
+    storage
+}
+
+/// Add the chunk index of a backup created on the given day to the storage.
+fn add_backup(storage: &PathBuf, day: u32, source_path: &str, completed: bool) {
+    let creation_date = Utc.ymd(2018, 3, day).and_hms(12, 0, 0);
+    let path = storage.join(format!("chunk_index-{}.db", creation_date.to_rfc3339()));
+    let chunk_index = ChunkIndex::new(path, creation_date).unwrap();
+    chunk_index
+        .set_manifest(NewManifest {
+            source_path: source_path.into(),
+            creation_date: creation_date.naive_utc(),
+            key_fingerprint: None,
+            hostname: None,
+            label: None,
+            file_count: None,
+            total_size: None,
+        })
+        .unwrap();
+    if completed {
+        chunk_index.set_completed().unwrap();
+    }
+}
+
+fn resumable_day(storage: &PathBuf, source_path: &str) -> Option<u32> {
+    find_resumable_chunk_index(storage, source_path, &None)
+        .unwrap()
+        .map(|(_, manifest)| manifest.creation_date.day())
+}
+
+#[test]
+fn resume_last_unfinished_backup() {
+    let storage = prepare_storage("resume_last_unfinished_backup");
+    add_backup(&storage, 1, "/home/aisatsana", false);
+    add_backup(&storage, 2, "/home/aisatsana", true);
+    add_backup(&storage, 3, "/home/aisatsana", false);
+    // Backups of other paths are ignored.
+    add_backup(&storage, 4, "/home/bibio", true);
+
+    assert_eq!(resumable_day(&storage, "/home/aisatsana"), Some(3));
+}
+
+#[test]
+fn do_not_resume_superseded_backup() {
+    let storage = prepare_storage("do_not_resume_superseded_backup");
+    add_backup(&storage, 1, "/home/aisatsana", false);
+    add_backup(&storage, 2, "/home/aisatsana", true);
+
+    assert_eq!(resumable_day(&storage, "/home/aisatsana"), None);
+    assert_eq!(resumable_day(&storage, "/home/bibio"), None);
+}
@@ -1,16 +1,35 @@
+use std::io::prelude::*;
+use std::fs;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::symlink;
+use std::path::PathBuf;
+
 use glob::Pattern;
 
 use super::test_data;
 use create_backup::create_chunk_index::CreateChunkIndex;
+use chunk_index::FileType;
+use chunk_index::schema::NewChunk;
+use encryption::Encryption;
+use compression::Compression;
+use xattr::XattrFilter;
 
 #[test]
 fn new() {
     let fnname = "create_chunk_index_new";
     let chunk_index = test_data::prepare_chunk_index(fnname);
     let path = test_data::prepare_fs_structure(fnname);
-    CreateChunkIndex::new(&chunk_index, &path, &vec![]).expect(
-        "Could not create chunk index builder",
-    );
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
 }
 
 #[test]
@@ -18,9 +37,16 @@ fn build() {
     let fnname = "create_chunk_index_build";
     let chunk_index = test_data::prepare_chunk_index(fnname);
     let path = test_data::prepare_fs_structure(fnname);
-    CreateChunkIndex::new(&chunk_index, &path, &vec![]).expect(
-        "Could not create chunk index builder",
-    );
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
 
     let chunks = chunk_index.get_all_chunks().expect(
         "Could not get all chunks",
@@ -43,8 +69,16 @@ fn exclude_glob() {
     let chunk_index = test_data::prepare_chunk_index(fnname);
     let path = test_data::prepare_fs_structure(fnname);
     let exclude = vec![Pattern::new("app/*.rs").unwrap()];
-    CreateChunkIndex::new(&chunk_index, &path, &exclude)
-        .expect("Could not create chunk index builder");
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &exclude,
+        &None,
+        Compression::None,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
 
     let chunks = chunk_index.get_all_chunks().expect(
         "Could not get all chunks",
@@ -59,3 +93,270 @@ fn exclude_glob() {
 
     assert_eq!(chunk_identifiers, expected_chunk_identifiers);
 }
+
+#[test]
+fn encrypted_chunk_identifiers() {
+    let fnname = "create_chunk_index_encrypted_chunk_identifiers";
+    let chunk_index = test_data::prepare_chunk_index(fnname);
+    let path = test_data::prepare_fs_structure(fnname);
+    let encryption = Some(Encryption::from_passphrase(b"redbackup"));
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &encryption,
+        Compression::None,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
+
+    let chunks = chunk_index.get_all_chunks().expect(
+        "Could not get all chunks",
+    );
+    assert_eq!(chunks.len(), 2);
+    // The plaintext hashes must not be revealed to the node.
+    assert!(chunks.iter().all(|c| {
+        c.chunk_identifier != "0596c5800313885c1a4886e2b45f6389bc573c9487d892f02119d7f1f0ddf579" &&
+            c.chunk_identifier != "7fcaddc8772aaa616f43361c217c23d308e933465b2099d00ba1418fec1839f2"
+    }));
+}
+
+#[test]
+fn compressed_chunks() {
+    let fnname = "create_chunk_index_compressed_chunks";
+    let chunk_index = test_data::prepare_chunk_index(fnname);
+    let path = test_data::prepare_fs_structure(fnname);
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::Zstd,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
+
+    let chunks = chunk_index.get_all_chunks().expect(
+        "Could not get all chunks",
+    );
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks.iter().all(|c| {
+        Compression::of_chunk(c).unwrap() == Compression::Zstd
+    }));
+    // The chunk size is the size of the uncompressed content.
+    let mut chunk_sizes: Vec<i64> = chunks.iter().map(|c| c.chunk_size).collect();
+    chunk_sizes.sort();
+    assert_eq!(chunk_sizes, vec![9, 44]);
+}
+
+#[test]
+fn reuse_chunks_of_previous_chunk_index() {
+    let fnname = "create_chunk_index_reuse_chunks_of_previous_chunk_index";
+    let previous = test_data::prepare_chunk_index(&format!("{}_previous", fnname));
+    let path = test_data::prepare_fs_structure(fnname);
+    CreateChunkIndex::new(
+        &previous,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create previous chunk index");
+
+    // Change one of the files, the other one is unchanged.
+    let mut hello_world = path.clone();
+    hello_world.push("app/hello_world.rs");
+    fs::File::create(&hello_world)
+        .and_then(|mut f| f.write_all(b"fn main() {}"))
+        .expect("Could not change hello_world test file");
+
+    // Mark the chunk of the unchanged file, so it can only come from the previous chunk index.
+    let mut redbackup = PathBuf::from(path.file_name().unwrap());
+    redbackup.push("documents/redbackup.txt");
+    let file = previous
+        .get_file_by_path(&redbackup)
+        .expect("Could not get file by path")
+        .expect("Unchanged file not in previous chunk index");
+    let chunk = previous.get_chunks_by_file(file.id).unwrap().remove(0);
+    previous
+        .add_chunk(NewChunk {
+            chunk_identifier: String::from("previous"),
+            file: file.id,
+            predecessor: Some(chunk.id),
+            chunk_offset: 9,
+            chunk_size: 0,
+            compression: None,
+        })
+        .expect("Could not mark chunk");
+
+    let chunk_index = test_data::prepare_chunk_index(fnname);
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &Some(previous),
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
+
+    let chunks = chunk_index.get_all_chunks().expect(
+        "Could not get all chunks",
+    );
+    let mut chunk_identifiers: Vec<String> =
+        chunks.iter().map(|c| c.chunk_identifier.clone()).collect();
+    chunk_identifiers.sort();
+
+    // The unchanged file reuses both chunks, the changed file is read again.
+    assert_eq!(chunk_identifiers.len(), 3);
+    assert!(chunk_identifiers.contains(&String::from(
+        "7fcaddc8772aaa616f43361c217c23d308e933465b2099d00ba1418fec1839f2",
+    )));
+    assert!(chunk_identifiers.contains(&String::from("previous")));
+    assert!(!chunk_identifiers.contains(&String::from(
+        "0596c5800313885c1a4886e2b45f6389bc573c9487d892f02119d7f1f0ddf579",
+    )));
+}
+
+#[test]
+fn symlinks_and_hardlinks() {
+    let fnname = "create_chunk_index_symlinks_and_hardlinks";
+    let chunk_index = test_data::prepare_chunk_index(fnname);
+    let path = test_data::prepare_fs_structure(fnname);
+    let mut documents = path.clone();
+    documents.push("documents");
+    symlink("redbackup.txt", documents.join("symlink.txt")).expect("Could not create symlink");
+    fs::hard_link(
+        documents.join("redbackup.txt"),
+        documents.join("hardlink.txt"),
+    ).expect("Could not create hardlink");
+
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
+
+    let mut relative_path = PathBuf::from(path.file_name().unwrap());
+    relative_path.push("documents");
+    let link = chunk_index
+        .get_file_by_path(&relative_path.join("symlink.txt"))
+        .unwrap()
+        .expect("Symlink not in chunk index");
+    assert_eq!(FileType::of_file(&link).unwrap(), FileType::Symlink);
+    assert_eq!(link.link_target, Some(String::from("redbackup.txt")));
+    assert!(chunk_index.get_chunks_by_file(link.id).unwrap().is_empty());
+
+    // One of the two links is stored as hardlink to the other one.
+    let original = chunk_index
+        .get_file_by_path(&relative_path.join("redbackup.txt"))
+        .unwrap()
+        .unwrap();
+    let hardlink = chunk_index
+        .get_file_by_path(&relative_path.join("hardlink.txt"))
+        .unwrap()
+        .unwrap();
+    let (original, hardlink) = if original.hardlink.is_some() {
+        (hardlink, original)
+    } else {
+        (original, hardlink)
+    };
+    assert_eq!(FileType::of_file(&hardlink).unwrap(), FileType::Hardlink);
+    assert_eq!(hardlink.hardlink, Some(original.id));
+    assert_eq!(chunk_index.get_chunks_by_file(original.id).unwrap().len(), 1);
+    assert!(chunk_index.get_chunks_by_file(hardlink.id).unwrap().is_empty());
+}
+
+#[test]
+fn follow_symlinks() {
+    let fnname = "create_chunk_index_follow_symlinks";
+    let chunk_index = test_data::prepare_chunk_index(fnname);
+    let path = test_data::prepare_fs_structure(fnname);
+    symlink("app", path.join("symlink")).expect("Could not create symlink");
+
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &None,
+        true,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
+
+    let mut relative_path = PathBuf::from(path.file_name().unwrap());
+    relative_path.push("symlink/hello_world.rs");
+    let file = chunk_index
+        .get_file_by_path(&relative_path)
+        .unwrap()
+        .expect("File behind symlink not in chunk index");
+    assert_eq!(FileType::of_file(&file).unwrap(), FileType::Regular);
+    assert_eq!(chunk_index.get_all_chunks().unwrap().len(), 3);
+}
+
+#[test]
+fn follow_symlinks_skips_loops() {
+    let fnname = "create_chunk_index_follow_symlinks_skips_loops";
+    let chunk_index = test_data::prepare_chunk_index(fnname);
+    let path = test_data::prepare_fs_structure(fnname);
+    symlink("..", path.join("app").join("loop")).expect("Could not create symlink");
+
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &None,
+        true,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
+
+    // The files are only added once, not again through the symlink.
+    assert_eq!(chunk_index.get_all_files().unwrap().len(), 2);
+}
+
+#[test]
+fn non_unicode_file_names() {
+    let fnname = "create_chunk_index_non_unicode_file_names";
+    let chunk_index = test_data::prepare_chunk_index(fnname);
+    let path = test_data::prepare_fs_structure(fnname);
+    let name = OsStr::from_bytes(b"caf\xe9.txt");
+    fs::File::create(path.join("documents").join(name))
+        .and_then(|mut f| f.write_all(b"redbackup"))
+        .expect("Could not create file with non unicode name");
+
+    CreateChunkIndex::new(
+        &chunk_index,
+        &path,
+        &vec![],
+        &None,
+        Compression::None,
+        &None,
+        false,
+        &XattrFilter::default(),
+    ).expect("Could not create chunk index builder");
+
+    let file = chunk_index
+        .get_all_files()
+        .unwrap()
+        .into_iter()
+        .find(|file| file.raw_name.is_some())
+        .expect("File with non unicode name not in chunk index");
+    assert_eq!(file.os_name(), name);
+    assert_eq!(
+        chunk_index.get_file_path(file.id).unwrap(),
+        PathBuf::from(path.file_name().unwrap()).join("documents").join(name)
+    );
+}
@@ -1,3 +1,4 @@
+use chunk_index::schema::Chunk;
 use create_backup::create_utils;
 use super::test_data;
 
@@ -22,3 +23,48 @@ fn file_hash() {
     let hash = create_utils::file_hash(&path).expect("file_hash returned an Error");
     assert_eq!(hash, expected_checksum);
 }
+
+#[test]
+fn read_chunk_content() {
+    let mut path = test_data::prepare_fs_structure("create_utils_read_chunk_content");
+    path.push("documents/redbackup.txt");
+
+    let content = create_utils::read_chunk_content(&path, 3, 4)
+        .expect("read_chunk_content returned an Error");
+    assert_eq!(content, b"back".to_vec());
+
+    assert!(create_utils::read_chunk_content(&path, 3, 42).is_err());
+}
+
+#[test]
+fn content_hash() {
+    let hash = create_utils::content_hash(b"redbackup");
+    assert_eq!(
+        hash,
+        "7fcaddc8772aaa616f43361c217c23d308e933465b2099d00ba1418fec1839f2"
+    );
+}
+
+#[test]
+fn batch_chunks() {
+    let chunk = |id: i32, chunk_size: i64| {
+        Chunk {
+            id,
+            chunk_identifier: format!("chunk-{}", id),
+            file: 1,
+            predecessor: None,
+            chunk_offset: 0,
+            chunk_size,
+            compression: None,
+            uploaded: false,
+        }
+    };
+    let chunks = vec![chunk(1, 4), chunk(2, 4), chunk(3, 12), chunk(4, 2), chunk(5, 6)];
+
+    let batches = create_utils::batch_chunks(chunks, 8);
+    let batch_ids: Vec<Vec<i32>> = batches
+        .iter()
+        .map(|batch| batch.iter().map(|chunk| chunk.id).collect())
+        .collect();
+    assert_eq!(batch_ids, vec![vec![1, 2], vec![3], vec![4, 5]]);
+}
@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use super::test_data;
+use diff_backups::{self, BackupChange, ChangeKind};
+
+#[test]
+fn diff_chunk_indexes() {
+    let old = test_data::prepare_chunk_index("diff_backups_old");
+    let root = test_data::prepare_folder(&old);
+    let file = test_data::prepare_named_file(&old, &root, "unchanged", 9, 0o644);
+    test_data::prepare_named_chunk(&old, &file, "a");
+    let file = test_data::prepare_named_file(&old, &root, "modified", 9, 0o644);
+    test_data::prepare_named_chunk(&old, &file, "b");
+    let file = test_data::prepare_named_file(&old, &root, "chmod", 9, 0o644);
+    test_data::prepare_named_chunk(&old, &file, "c");
+    let file = test_data::prepare_named_file(&old, &root, "removed", 9, 0o644);
+    test_data::prepare_named_chunk(&old, &file, "d");
+
+    let new = test_data::prepare_chunk_index("diff_backups_new");
+    let root = test_data::prepare_folder(&new);
+    let file = test_data::prepare_named_file(&new, &root, "unchanged", 9, 0o644);
+    test_data::prepare_named_chunk(&new, &file, "a");
+    let file = test_data::prepare_named_file(&new, &root, "modified", 9, 0o644);
+    test_data::prepare_named_chunk(&new, &file, "e");
+    let file = test_data::prepare_named_file(&new, &root, "chmod", 9, 0o600);
+    test_data::prepare_named_chunk(&new, &file, "c");
+    let folder = test_data::prepare_subfolder(&new, &root, "added");
+    test_data::prepare_named_file(&new, &folder, "file", 9, 0o644);
+
+    let changes = diff_backups::diff_chunk_indexes(&old, &new).expect("Could not diff backups");
+    let change = |path: &str, change: ChangeKind, kind: &str, details: &[&str]| {
+        BackupChange {
+            path: PathBuf::from(path),
+            change,
+            kind: String::from(kind),
+            details: details.iter().map(|detail| String::from(*detail)).collect(),
+        }
+    };
+    assert_eq!(
+        changes,
+        vec![
+            change("added", ChangeKind::Added, "folder", &[]),
+            change("added/file", ChangeKind::Added, "regular", &[]),
+            change("chmod", ChangeKind::MetadataChanged, "regular", &["mode"]),
+            change("modified", ChangeKind::Modified, "regular", &["content"]),
+            change("removed", ChangeKind::Removed, "regular", &[]),
+        ]
+    );
+
+    let json = diff_backups::to_json(&changes[2..3]).expect("Could not serialize changes");
+    assert_eq!(
+        json,
+        "[\n  {\n    \"path\": \"chmod\",\n    \"change\": \"metadata_changed\",\n    \
+         \"kind\": \"regular\",\n    \"details\": [\n      \"mode\"\n    ]\n  }\n]"
+    );
+}
+
+#[test]
+fn diff_identical_chunk_indexes() {
+    let chunk_index = test_data::prepare_chunk_index("diff_backups_identical");
+    let root = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &root);
+    test_data::prepare_chunk(&chunk_index, &file);
+
+    let changes = diff_backups::diff_chunk_indexes(&chunk_index, &chunk_index)
+        .expect("Could not diff backups");
+    assert!(changes.is_empty());
+}
@@ -0,0 +1,51 @@
+use encryption::{Encryption, EncryptionError};
+
+#[test]
+fn encrypt_and_decrypt() {
+    let encryption = Encryption::from_passphrase(b"correct horse battery staple");
+    let encrypted = encryption.encrypt(b"redbackup").expect(
+        "encrypt returned an Error",
+    );
+    assert!(encrypted.len() > "redbackup".len());
+
+    let decrypted = encryption.decrypt(&encrypted).expect(
+        "decrypt returned an Error",
+    );
+    assert_eq!(decrypted, b"redbackup".to_vec());
+}
+
+#[test]
+fn encryption_is_deterministic() {
+    let encryption = Encryption::from_passphrase(b"correct horse battery staple");
+    let first = encryption.encrypt(b"redbackup").unwrap();
+    let second = encryption.encrypt(b"redbackup").unwrap();
+    assert_eq!(first, second);
+
+    let other = encryption.encrypt(b"redbackuq").unwrap();
+    assert!(first[..12] != other[..12]);
+}
+
+#[test]
+fn decrypt_with_wrong_key_fails() {
+    let encryption = Encryption::from_passphrase(b"correct horse battery staple");
+    let encrypted = encryption.encrypt(b"redbackup").unwrap();
+
+    let wrong = Encryption::from_passphrase(b"incorrect horse battery staple");
+    match wrong.decrypt(&encrypted) {
+        Err(EncryptionError::DecryptionFailed) => {}
+        other => panic!("Expected DecryptionFailed, got {:?}", other),
+    }
+}
+
+#[test]
+fn decrypt_tampered_content_fails() {
+    let encryption = Encryption::from_passphrase(b"correct horse battery staple");
+    let mut encrypted = encryption.encrypt(b"redbackup").unwrap();
+    encrypted[14] ^= 1;
+    assert!(encryption.decrypt(&encrypted).is_err());
+
+    match encryption.decrypt(&encrypted[..20]) {
+        Err(EncryptionError::InvalidCiphertext) => {}
+        other => panic!("Expected InvalidCiphertext, got {:?}", other),
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use redbackup_protocol::message::ChunkContentElement;
+
+use chrono::prelude::*;
+use chunk_index::schema::Chunk;
+use create_backup::create_utils;
+use restore_backup::RestoreBackupError;
+use restore_backup::assembler::FileAssembler;
+use restore_backup::utils;
+use super::test_data;
+
+fn chunk(id: i32, file: i32, chunk_identifier: &str) -> Chunk {
+    Chunk {
+        id,
+        chunk_identifier: chunk_identifier.into(),
+        file,
+        predecessor: None,
+        chunk_offset: 0,
+        chunk_size: 3,
+        compression: None,
+        uploaded: false,
+    }
+}
+
+/// The contents the node returns for a batch (every chunk only once).
+fn contents_of(batch: &[Chunk]) -> Vec<ChunkContentElement> {
+    let mut identifiers: Vec<String> = batch
+        .iter()
+        .map(|chunk| chunk.chunk_identifier.clone())
+        .collect();
+    identifiers.sort();
+    identifiers.dedup();
+    identifiers
+        .into_iter()
+        .map(|identifier| {
+            ChunkContentElement {
+                chunk_content: identifier.to_uppercase().into_bytes(),
+                chunk_identifier: identifier,
+                expiration_date: Utc::now(),
+                root_handle: false,
+            }
+        })
+        .collect()
+}
+
+fn decode(_: &Chunk, content: Vec<u8>) -> Result<Vec<u8>, RestoreBackupError> {
+    Ok(content)
+}
+
+#[test]
+fn write_batches_to_files_in_order() {
+    let root = test_data::prepare_fs_structure("file_assembler_write_batches");
+    let paths: Vec<PathBuf> = vec![root.join("a"), root.join("b"), root.join("c")];
+    // Chunk "abc" is shared by all files, and batches end in the middle of files.
+    let chunks = vec![
+        chunk(1, 1, "abc"),
+        chunk(2, 1, "def"),
+        chunk(3, 2, "abc"),
+        chunk(4, 3, "ghi"),
+        chunk(5, 3, "abc"),
+    ];
+    let chunk_files = vec![0, 0, 1, 2, 2];
+    let batches = create_utils::batch_chunks(chunks, 7);
+    assert_eq!(batches.len(), 3);
+
+    let mut assembler = FileAssembler::new(&paths, chunk_files);
+    let mut completed = Vec::new();
+    for batch in batches.iter() {
+        completed.push(
+            assembler
+                .write_batch(batch, contents_of(batch), decode)
+                .unwrap(),
+        );
+    }
+    assert_eq!(completed, vec![vec![], vec![0, 1], vec![]]);
+    assert_eq!(assembler.finish(), Some(2));
+
+    let content = |path: &PathBuf| {
+        create_utils::read_file_content(&utils::temp_path(path)).unwrap()
+    };
+    assert_eq!(content(&paths[0]), b"ABCDEF".to_vec());
+    assert_eq!(content(&paths[1]), b"ABC".to_vec());
+    assert_eq!(content(&paths[2]), b"GHIABC".to_vec());
+}
+
+#[test]
+fn write_batch_fails_on_missing_chunk() {
+    let root = test_data::prepare_fs_structure("file_assembler_missing_chunk");
+    let paths = vec![root.join("a")];
+    let batch = vec![chunk(1, 1, "abc"), chunk(2, 1, "def")];
+
+    let mut assembler = FileAssembler::new(&paths, vec![0, 0]);
+    let result = assembler.write_batch(&batch, contents_of(&batch[..1]), decode);
+    match result {
+        Err(RestoreBackupError::ChunkNotAvailable(identifier)) => assert_eq!(identifier, "def"),
+        _ => panic!("Expected the chunk to be not available"),
+    }
+}
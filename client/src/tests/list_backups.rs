@@ -0,0 +1,42 @@
+use chrono::prelude::*;
+use list_backups::{BackupInfo, BackupManifest, ListBackupsFilter};
+
+fn backup(manifest: Option<BackupManifest>) -> BackupInfo {
+    BackupInfo {
+        backup_id: String::from("7fcaddc8772aaa616f43361c217c23d308e933465b2099d00ba1418fec1839f2"),
+        expiration_date: Utc.ymd(2030, 1, 1).and_hms(0, 0, 0),
+        manifest,
+    }
+}
+
+#[test]
+fn filter_backups() {
+    let tagged = backup(Some(BackupManifest {
+        hostname: Some(String::from("warp")),
+        source_path: String::from("/home/aisatsana"),
+        label: Some(String::from("weekly")),
+        tags: vec![String::from("home"), String::from("music")],
+        creation_date: Utc.ymd(2018, 2, 26).and_hms(7, 8, 9),
+        file_count: Some(2),
+        total_size: Some(18),
+    }));
+    let without_manifest = backup(None);
+
+    let all = ListBackupsFilter::default();
+    assert!(all.matches(&tagged));
+    assert!(all.matches(&without_manifest));
+
+    let filter = ListBackupsFilter::new(
+        Some("warp"),
+        Some("/home/aisatsana"),
+        Some("music"),
+        Some("weekly"),
+    );
+    assert!(filter.matches(&tagged));
+    assert!(!filter.matches(&without_manifest));
+
+    assert!(!ListBackupsFilter::new(Some("bibio"), None, None, None).matches(&tagged));
+    assert!(!ListBackupsFilter::new(None, Some("/home/bibio"), None, None).matches(&tagged));
+    assert!(!ListBackupsFilter::new(None, None, Some("work"), None).matches(&tagged));
+    assert!(!ListBackupsFilter::new(None, None, None, Some("daily")).matches(&tagged));
+}
@@ -7,8 +7,47 @@ pub mod chunk_index;
 #[cfg(test)]
 pub mod create_chunk_index;
 
+#[cfg(test)]
+pub mod chunker;
+
 #[cfg(test)]
 pub mod create_utils;
 
+#[cfg(test)]
+pub mod create_backup;
+
 #[cfg(test)]
 pub mod restore_backup_utils;
+
+#[cfg(test)]
+pub mod restore_backup;
+
+#[cfg(test)]
+pub mod file_assembler;
+
+#[cfg(test)]
+pub mod encryption;
+
+#[cfg(test)]
+pub mod compression;
+
+#[cfg(test)]
+pub mod xattr;
+
+#[cfg(test)]
+pub mod browse_backup;
+
+#[cfg(all(test, feature = "mount"))]
+pub mod mount_backup;
+
+#[cfg(test)]
+pub mod diff_backups;
+
+#[cfg(test)]
+pub mod list_backups;
+
+#[cfg(test)]
+pub mod backup_selector;
+
+#[cfg(test)]
+pub mod verify_backup;
@@ -0,0 +1,87 @@
+use chunk_index::schema::Chunk;
+use mount_backup::{chunk_slices, ChunkCache};
+
+/// The chunks of a file of 25 bytes, in chunks of 10, 10 and 5 bytes.
+fn chunks() -> Vec<Chunk> {
+    vec![(1, 0, 10), (2, 10, 10), (3, 20, 5)]
+        .into_iter()
+        .map(|(id, chunk_offset, chunk_size)| {
+            Chunk {
+                id,
+                chunk_identifier: format!("chunk{}", id),
+                file: 1,
+                predecessor: if id > 1 { Some(id - 1) } else { None },
+                chunk_offset,
+                chunk_size,
+                compression: None,
+                uploaded: true,
+            }
+        })
+        .collect()
+}
+
+fn slices(offset: u64, size: u64) -> Vec<(i32, usize, usize)> {
+    chunk_slices(chunks(), offset, size)
+        .into_iter()
+        .map(|(chunk, range)| (chunk.id, range.start, range.end))
+        .collect()
+}
+
+#[test]
+fn chunk_slices_within_one_chunk() {
+    assert_eq!(slices(2, 5), vec![(1, 2, 7)]);
+    assert_eq!(slices(10, 10), vec![(2, 0, 10)]);
+}
+
+#[test]
+fn chunk_slices_spanning_several_chunks() {
+    assert_eq!(slices(5, 10), vec![(1, 5, 10), (2, 0, 5)]);
+    assert_eq!(slices(8, 14), vec![(1, 8, 10), (2, 0, 10), (3, 0, 2)]);
+    assert_eq!(slices(0, 25), vec![(1, 0, 10), (2, 0, 10), (3, 0, 5)]);
+}
+
+#[test]
+fn chunk_slices_beyond_end_of_file() {
+    assert_eq!(slices(18, 100), vec![(2, 8, 10), (3, 0, 5)]);
+    assert!(slices(25, 10).is_empty());
+    assert!(slices(40, 10).is_empty());
+}
+
+#[test]
+fn chunk_slices_of_unordered_chunks() {
+    let mut chunks = chunks();
+    chunks.reverse();
+    let ids: Vec<i32> = chunk_slices(chunks, 0, 25)
+        .into_iter()
+        .map(|(chunk, _)| chunk.id)
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[test]
+fn cache_drops_least_recently_used_chunks() {
+    let mut cache = ChunkCache::new(10);
+    cache.insert("a".into(), vec![1; 4]);
+    cache.insert("b".into(), vec![2; 4]);
+    assert_eq!(cache.get("a"), Some(&vec![1; 4]));
+
+    // "b" was used least recently and is dropped.
+    cache.insert("c".into(), vec![3; 4]);
+    assert!(cache.contains("a"));
+    assert!(!cache.contains("b"));
+    assert!(cache.contains("c"));
+    assert_eq!(cache.size(), 8);
+}
+
+#[test]
+fn cache_keeps_chunk_larger_than_capacity() {
+    let mut cache = ChunkCache::new(10);
+    cache.insert("a".into(), vec![1; 4]);
+    cache.insert("b".into(), vec![2; 20]);
+    assert!(!cache.contains("a"));
+    assert_eq!(cache.get("b"), Some(&vec![2; 20]));
+    assert_eq!(cache.size(), 20);
+
+    cache.insert("b".into(), vec![3; 2]);
+    assert_eq!(cache.size(), 2);
+}
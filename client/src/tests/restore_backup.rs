@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use chrono::prelude::*;
+use glob::Pattern;
+
+use chunk_index::{ChunkIndex, FileType};
+use chunk_index::schema::*;
+use restore_backup;
+use super::test_data;
+
+fn add_hardlink(chunk_index: &ChunkIndex, folder: &Folder, name: &str, original: &File) -> File {
+    chunk_index
+        .add_file(NewFile {
+            name: String::from(name),
+            last_change_date: NaiveDate::from_ymd(2016, 11, 28).and_hms(7, 8, 9),
+            folder: folder.id,
+            size: original.size,
+            mode: Some(0o644),
+            uid: Some(1000),
+            gid: Some(1000),
+            access_date: None,
+            file_type: String::from("hardlink"),
+            link_target: None,
+            hardlink: Some(original.id),
+            device: None,
+            raw_name: None,
+            raw_link_target: None,
+            content_hash: None,
+        })
+        .expect("Hardlink could not be added")
+}
+
+/// The chunk identifiers, that are requested to restore the selected files.
+fn requested_chunks(chunk_index: &ChunkIndex, files: &[(PathBuf, File)]) -> Vec<String> {
+    let mut chunk_identifiers = Vec::new();
+    for &(_, ref file) in files {
+        if FileType::of_file(file).unwrap() != FileType::Regular {
+            continue;
+        }
+        for chunk in chunk_index.get_chunks_by_file(file.id).unwrap() {
+            chunk_identifiers.push(chunk.chunk_identifier);
+        }
+    }
+    chunk_identifiers.sort();
+    chunk_identifiers
+}
+
+#[test]
+fn select_files() {
+    let chunk_index = test_data::prepare_chunk_index("restore_select_files");
+    let root = test_data::prepare_folder(&chunk_index);
+    let music = test_data::prepare_subfolder(&chunk_index, &root, "music");
+    let documents = test_data::prepare_subfolder(&chunk_index, &root, "documents");
+    let song = test_data::prepare_named_file(&chunk_index, &music, "song", 9, 0o644);
+    test_data::prepare_named_chunk(&chunk_index, &song, "a");
+    let other_song = test_data::prepare_named_file(&chunk_index, &music, "other", 9, 0o644);
+    test_data::prepare_named_chunk(&chunk_index, &other_song, "b");
+    let letter = test_data::prepare_named_file(&chunk_index, &documents, "letter", 9, 0o644);
+    test_data::prepare_named_chunk(&chunk_index, &letter, "c");
+    let hardlink = add_hardlink(&chunk_index, &documents, "song", &song);
+
+    let restore_dir = PathBuf::from("/restore");
+    let include = vec![Pattern::new("documents").unwrap()];
+    let files = restore_backup::select_files(&chunk_index, &restore_dir, &include)
+        .expect("Could not select files");
+    let selected: Vec<(PathBuf, i32)> = files
+        .iter()
+        .map(|&(ref path, ref file)| (path.clone(), file.id))
+        .collect();
+    // The original of the hardlink is not included, it is restored as copy of the original.
+    assert_eq!(
+        selected,
+        vec![
+            (PathBuf::from("/restore/aisatsana/documents/letter"), letter.id),
+            (PathBuf::from("/restore/aisatsana/documents/song"), song.id),
+        ]
+    );
+    assert_eq!(requested_chunks(&chunk_index, &files), vec!["a", "c"]);
+
+    let include = vec![
+        Pattern::new("documents").unwrap(),
+        Pattern::new("music/song").unwrap(),
+    ];
+    let files = restore_backup::select_files(&chunk_index, &restore_dir, &include)
+        .expect("Could not select files");
+    let selected: Vec<(PathBuf, i32)> = files
+        .iter()
+        .map(|&(ref path, ref file)| (path.clone(), file.id))
+        .collect();
+    // With its original, the hardlink is restored as hardlink.
+    assert_eq!(
+        selected,
+        vec![
+            (PathBuf::from("/restore/aisatsana/music/song"), song.id),
+            (PathBuf::from("/restore/aisatsana/documents/letter"), letter.id),
+            (PathBuf::from("/restore/aisatsana/documents/song"), hardlink.id),
+        ]
+    );
+    assert_eq!(requested_chunks(&chunk_index, &files), vec!["a", "c"]);
+}
@@ -1,3 +1,11 @@
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::PathBuf;
+
+use chrono::prelude::*;
+use glob::Pattern;
+
+use chunk_index::FileType;
 use restore_backup::utils;
 use create_backup;
 use super::test_data;
@@ -11,6 +19,9 @@ fn restore_file_content() {
     utils::restore_file_content(&content, &path).expect("restore_file_content returned an Error");
 
     assert!(path.is_file());
+    // The content may be decrypted data of the backup.
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
 
     let real_content = create_backup::create_utils::read_file_content(&path)
         .expect("Could not read file content for verification");
@@ -25,3 +36,198 @@ fn create_folder() {
     utils::create_folder(&path).expect("create_folder returned an Error");
     assert!(path.is_dir());
 }
+
+#[test]
+fn create_file_and_append_content() {
+    let mut path = test_data::prepare_fs_structure("utils_create_file_and_append_content");
+    path.push("documents/redbackup3.txt");
+
+    let mut fhandle = utils::create_file(&path).expect("create_file returned an Error");
+    utils::append_file_content(b"red", &mut fhandle).expect("append_file_content returned an Error");
+    utils::append_file_content(b"backup", &mut fhandle).expect("append_file_content returned an Error");
+
+    let real_content = create_backup::create_utils::read_file_content(&path)
+        .expect("Could not read file content for verification");
+    assert_eq!(real_content, b"redbackup".to_vec());
+    assert!(utils::create_file(&path).is_err());
+}
+
+#[test]
+fn set_permissions() {
+    let mut path = test_data::prepare_fs_structure("utils_set_permissions");
+    path.push("app/hello_world.rs");
+
+    utils::set_permissions(&path, 0o751).expect("set_permissions returned an Error");
+
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o7777, 0o751);
+}
+
+#[test]
+fn set_file_times() {
+    let mut path = test_data::prepare_fs_structure("utils_set_file_times");
+    path.push("documents/redbackup.txt");
+    let access_date = NaiveDate::from_ymd(2016, 11, 29).and_hms_micro(7, 8, 9, 10);
+    let last_change_date = NaiveDate::from_ymd(2016, 11, 28).and_hms_micro(7, 8, 9, 10);
+
+    utils::set_file_times(&path, &access_date, &last_change_date)
+        .expect("set_file_times returned an Error");
+
+    let metadata = fs::metadata(&path).unwrap();
+    let accessed = DateTime::<Local>::from(metadata.accessed().unwrap());
+    let modified = DateTime::<Local>::from(metadata.modified().unwrap());
+    assert_eq!(accessed.naive_local(), access_date);
+    assert_eq!(modified.naive_local(), last_change_date);
+}
+
+#[test]
+fn create_symlink_and_hardlink() {
+    let root = test_data::prepare_fs_structure("utils_create_symlink_and_hardlink");
+    let mut original = root.clone();
+    original.push("documents/redbackup.txt");
+
+    let mut symlink = root.clone();
+    symlink.push("documents/symlink.txt");
+    utils::create_symlink(&PathBuf::from("redbackup.txt"), &symlink)
+        .expect("create_symlink returned an Error");
+    assert_eq!(
+        fs::read_link(&symlink).unwrap(),
+        PathBuf::from("redbackup.txt")
+    );
+
+    let mut hardlink = root.clone();
+    hardlink.push("app/hardlink.txt");
+    utils::create_hardlink(&original, &hardlink).expect("create_hardlink returned an Error");
+    assert_eq!(
+        fs::metadata(&hardlink).unwrap().ino(),
+        fs::metadata(&original).unwrap().ino()
+    );
+}
+
+#[test]
+fn create_fifo() {
+    let mut path = test_data::prepare_fs_structure("utils_create_fifo");
+    path.push("fifo");
+
+    utils::create_node(&path, FileType::Fifo, 0o640, 0).expect("create_node returned an Error");
+
+    let metadata = fs::symlink_metadata(&path).unwrap();
+    assert!(metadata.file_type().is_fifo());
+    assert!(utils::create_node(&path, FileType::Regular, 0o640, 0).is_err());
+}
+
+#[test]
+fn is_included() {
+    let path = PathBuf::from("root/documents/letters/redbackup.txt");
+    assert!(utils::is_included(&vec![], &path));
+
+    let include = vec![Pattern::new("documents").unwrap()];
+    assert!(utils::is_included(&include, &path));
+    assert!(utils::is_included(&include, &PathBuf::from("root/documents")));
+    assert!(!utils::is_included(&include, &PathBuf::from("root/app/hello_world.rs")));
+    assert!(!utils::is_included(&include, &PathBuf::from("root/documents.txt")));
+
+    let include = vec![Pattern::new("*/letters/*.txt").unwrap()];
+    assert!(utils::is_included(&include, &path));
+    assert!(!utils::is_included(&include, &PathBuf::from("root/documents/letters")));
+}
+
+#[test]
+fn temp_path_and_replace_file() {
+    let root = test_data::prepare_fs_structure("utils_temp_path_and_replace_file");
+    let path = root.join("documents/redbackup.txt");
+
+    let temp_path = utils::temp_path(&path);
+    assert_eq!(temp_path, root.join("documents/.redbackup.txt.redbackup-tmp"));
+    let mut fhandle = utils::create_file(&temp_path).expect("create_file returned an Error");
+    utils::append_file_content(b"restored", &mut fhandle).expect("Could not append content");
+
+    // Leftovers of an aborted restore are removed.
+    utils::remove_if_exists(&temp_path).expect("remove_if_exists returned an Error");
+    assert!(!temp_path.exists());
+    utils::remove_if_exists(&temp_path).expect("remove_if_exists returned an Error");
+    let mut fhandle = utils::create_file(&temp_path).expect("create_file returned an Error");
+    utils::append_file_content(b"restored", &mut fhandle).expect("Could not append content");
+
+    utils::replace_file(&temp_path, &path).expect("replace_file returned an Error");
+    assert!(!temp_path.exists());
+    let real_content = create_backup::create_utils::read_file_content(&path)
+        .expect("Could not read file content for verification");
+    assert_eq!(real_content, b"restored".to_vec());
+}
+
+#[test]
+fn unique_path() {
+    let root = test_data::prepare_fs_structure("utils_unique_path");
+    let path = root.join("documents/redbackup.txt");
+
+    let unique_path = utils::unique_path(&path);
+    assert_eq!(unique_path, root.join("documents/redbackup.txt.restored"));
+    fs::File::create(&unique_path).expect("Could not create file");
+    assert_eq!(
+        utils::unique_path(&path),
+        root.join("documents/redbackup.txt.restored-2")
+    );
+}
+
+#[test]
+fn has_same_content() {
+    let root = test_data::prepare_fs_structure("utils_has_same_content");
+    let chunk_index = test_data::prepare_chunk_index("utils_has_same_content");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+
+    // Without content hash, files can not be compared.
+    let path = root.join("documents/redbackup.txt");
+    assert!(!utils::has_same_content(&path, &file).unwrap());
+
+    chunk_index
+        .set_content_hash(
+            file.id,
+            "7fcaddc8772aaa616f43361c217c23d308e933465b2099d00ba1418fec1839f2",
+        )
+        .expect("Could not set content hash");
+    let file = chunk_index.get_file(file.id).unwrap();
+    assert!(utils::has_same_content(&path, &file).unwrap());
+    assert!(!utils::has_same_content(&root.join("app/hello_world.rs"), &file).unwrap());
+}
+
+#[test]
+fn complete_chunks() {
+    let root = test_data::prepare_fs_structure("utils_complete_chunks");
+    let path = root.join("documents/.partial.redbackup-tmp");
+    assert_eq!(utils::complete_chunks(&path, &[3, 6]).unwrap(), 0);
+
+    // The second chunk was written partially.
+    let mut fhandle = utils::create_file(&path).expect("create_file returned an Error");
+    utils::append_file_content(b"redback", &mut fhandle).expect("Could not append content");
+    assert_eq!(utils::complete_chunks(&path, &[3, 6]).unwrap(), 1);
+    assert_eq!(fs::metadata(&path).unwrap().len(), 3);
+
+    let mut fhandle = utils::open_file_to_append(&path).expect("open_file_to_append failed");
+    utils::append_file_content(b"backup", &mut fhandle).expect("Could not append content");
+    assert_eq!(utils::complete_chunks(&path, &[3, 6]).unwrap(), 2);
+    let real_content = create_backup::create_utils::read_file_content(&path)
+        .expect("Could not read file content for verification");
+    assert_eq!(real_content, b"redbackup".to_vec());
+}
+
+#[test]
+fn is_restored() {
+    let root = test_data::prepare_fs_structure("utils_is_restored");
+    let chunk_index = test_data::prepare_chunk_index("utils_is_restored");
+    let folder = test_data::prepare_folder(&chunk_index);
+    let file = test_data::prepare_file(&chunk_index, &folder);
+
+    // Without content hash, only the size is verified.
+    let path = root.join("documents/redbackup.txt");
+    assert!(utils::is_restored(&path, &file).unwrap());
+    assert!(!utils::is_restored(&root.join("app/hello_world.rs"), &file).unwrap());
+    assert!(!utils::is_restored(&root.join("missing.txt"), &file).unwrap());
+
+    chunk_index
+        .set_content_hash(file.id, &"0".repeat(64))
+        .expect("Could not set content hash");
+    let file = chunk_index.get_file(file.id).unwrap();
+    assert!(!utils::is_restored(&path, &file).unwrap());
+}
@@ -24,6 +24,12 @@ pub fn prepare_folder(chunk_index: &ChunkIndex) -> Folder {
     let folder = NewFolder {
         name: String::from("aisatsana"),
         parent_folder: None,
+        mode: Some(0o755),
+        uid: Some(1000),
+        gid: Some(1000),
+        last_change_date: Some(NaiveDate::from_ymd(2016, 11, 28).and_hms(7, 8, 9)),
+        access_date: Some(NaiveDate::from_ymd(2016, 11, 29).and_hms(7, 8, 9)),
+        raw_name: None,
     };
 
     chunk_index.add_folder(folder.clone()).expect(
@@ -36,6 +42,18 @@ pub fn prepare_file(chunk_index: &ChunkIndex, folder: &Folder) -> File {
         name: String::from("bibio"),
         last_change_date: NaiveDate::from_ymd(2016, 11, 28).and_hms_milli(7, 8, 9, 10),
         folder: folder.id,
+        size: 9,
+        mode: Some(0o644),
+        uid: Some(1000),
+        gid: Some(1000),
+        access_date: Some(NaiveDate::from_ymd(2016, 11, 29).and_hms(7, 8, 9)),
+        file_type: String::from("regular"),
+        link_target: None,
+        hardlink: None,
+        device: None,
+        raw_name: None,
+        raw_link_target: None,
+        content_hash: None,
     };
     chunk_index.add_file(file).expect("File could not be added")
 }
@@ -47,12 +65,75 @@ pub fn prepare_chunk(chunk_index: &ChunkIndex, file: &File) -> Chunk {
         ),
         file: file.id,
         predecessor: None,
+        chunk_offset: 0,
+        chunk_size: 9,
+        compression: None,
     };
     chunk_index.add_chunk(chunk).expect(
         "Chunk could not be added",
     )
 }
 
+pub fn prepare_subfolder(chunk_index: &ChunkIndex, parent: &Folder, name: &str) -> Folder {
+    let folder = NewFolder {
+        name: String::from(name),
+        parent_folder: Some(parent.id),
+        mode: None,
+        uid: None,
+        gid: None,
+        last_change_date: None,
+        access_date: None,
+        raw_name: None,
+    };
+    chunk_index.add_folder(folder).expect(
+        "Subfolder could not be added",
+    )
+}
+
+pub fn prepare_named_file(
+    chunk_index: &ChunkIndex,
+    folder: &Folder,
+    name: &str,
+    size: i64,
+    mode: i32,
+) -> File {
+    let file = NewFile {
+        name: String::from(name),
+        last_change_date: NaiveDate::from_ymd(2016, 11, 28).and_hms(7, 8, 9),
+        folder: folder.id,
+        size,
+        mode: Some(mode),
+        uid: Some(1000),
+        gid: Some(1000),
+        access_date: None,
+        file_type: String::from("regular"),
+        link_target: None,
+        hardlink: None,
+        device: None,
+        raw_name: None,
+        raw_link_target: None,
+        content_hash: None,
+    };
+    chunk_index.add_file(file).expect("File could not be added")
+}
+
+pub fn prepare_named_chunk(
+    chunk_index: &ChunkIndex,
+    file: &File,
+    chunk_identifier: &str,
+) -> Chunk {
+    let chunk = NewChunk {
+        chunk_identifier: String::from(chunk_identifier),
+        file: file.id,
+        predecessor: None,
+        chunk_offset: 0,
+        chunk_size: file.size,
+        compression: None,
+    };
+    chunk_index.add_chunk(chunk).expect(
+        "Chunk could not be added",
+    )
+}
 
 /// Creates a testing file struture.
 ///
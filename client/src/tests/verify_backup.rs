@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use super::test_data;
+use chunk_index::ChunkIndex;
+use chunk_index::schema::*;
+use verify_backup::{self, Mismatch, MismatchKind};
+
+const REDBACKUP_HASH: &'static str =
+    "7fcaddc8772aaa616f43361c217c23d308e933465b2099d00ba1418fec1839f2";
+
+fn add_file(chunk_index: &ChunkIndex, folder: &Folder, name: &str, size: i64, hash: &str) {
+    let file = test_data::prepare_named_file(chunk_index, folder, name, size, 0o644);
+    chunk_index.set_content_hash(file.id, hash).expect(
+        "Could not set content hash",
+    );
+}
+
+#[test]
+fn verify_local_dir() {
+    let root = test_data::prepare_fs_structure("verify_local_dir");
+    let chunk_index = test_data::prepare_chunk_index("verify_local_dir");
+    let root_folder = test_data::prepare_folder(&chunk_index);
+    let documents = test_data::prepare_subfolder(&chunk_index, &root_folder, "documents");
+    add_file(&chunk_index, &documents, "redbackup.txt", 9, REDBACKUP_HASH);
+    let app = test_data::prepare_subfolder(&chunk_index, &root_folder, "app");
+    add_file(&chunk_index, &app, "hello_world.rs", 44, REDBACKUP_HASH);
+    add_file(&chunk_index, &root_folder, "missing.txt", 9, "");
+
+    let report = verify_backup::verify_local_dir(&chunk_index, &root)
+        .expect("Could not verify local directory");
+    assert!(!report.is_ok());
+    assert_eq!(report.checked_entries, 5);
+    let mismatch = |path: &str, kind: MismatchKind, detail: Option<&str>| {
+        Mismatch {
+            path: PathBuf::from(path),
+            kind,
+            detail: detail.map(String::from),
+        }
+    };
+    assert_eq!(
+        report.mismatches,
+        vec![
+            mismatch("app/hello_world.rs", MismatchKind::Different, Some("content")),
+            mismatch("missing.txt", MismatchKind::Missing, None),
+        ]
+    );
+}
+
+#[test]
+fn verify_extra_files() {
+    let root = test_data::prepare_fs_structure("verify_extra_files");
+    let chunk_index = test_data::prepare_chunk_index("verify_extra_files");
+    let root_folder = test_data::prepare_folder(&chunk_index);
+    let documents = test_data::prepare_subfolder(&chunk_index, &root_folder, "documents");
+    add_file(&chunk_index, &documents, "redbackup.txt", 9, REDBACKUP_HASH);
+
+    let report = verify_backup::verify_local_dir(&chunk_index, &root)
+        .expect("Could not verify local directory");
+    assert_eq!(
+        report.mismatches,
+        vec![
+            Mismatch {
+                path: PathBuf::from("app"),
+                kind: MismatchKind::Extra,
+                detail: None,
+            },
+        ]
+    );
+    assert!(!report.is_ok());
+}
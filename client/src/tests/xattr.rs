@@ -0,0 +1,46 @@
+use xattr::{self, XattrFilter};
+use super::test_data;
+
+#[test]
+fn filter_namespaces() {
+    let all = XattrFilter::default();
+    assert!(all.matches("user.comment"));
+    assert!(all.matches("system.posix_acl_access"));
+
+    let filter = XattrFilter::new(Some("user, security"), Some("security"))
+        .expect("Could not create filter");
+    assert!(filter.matches("user.comment"));
+    assert!(!filter.matches("security.selinux"));
+    assert!(!filter.matches("system.posix_acl_access"));
+
+    let filter = XattrFilter::new(None, Some("system")).expect("Could not create filter");
+    assert!(filter.matches("security.selinux"));
+    assert!(!filter.matches("system.posix_acl_default"));
+
+    assert_eq!(
+        XattrFilter::new(Some("user,unknown"), None),
+        Err(String::from("unknown"))
+    );
+}
+
+#[test]
+fn is_acl() {
+    assert!(xattr::is_acl("system.posix_acl_access"));
+    assert!(xattr::is_acl("system.posix_acl_default"));
+    assert!(!xattr::is_acl("system.nfs4_acl"));
+    assert!(!xattr::is_acl("user.posix_acl_access"));
+}
+
+#[test]
+fn write_and_read_xattrs() {
+    let mut path = test_data::prepare_fs_structure("xattr_write_and_read_xattrs");
+    path.push("documents/redbackup.txt");
+
+    xattr::write_xattr(&path, "user.redbackup", b"aisatsana")
+        .expect("write_xattr returned an Error");
+
+    let xattrs = xattr::read_xattrs(&path).expect("read_xattrs returned an Error");
+    assert!(xattrs.contains(
+        &(String::from("user.redbackup"), b"aisatsana".to_vec()),
+    ));
+}
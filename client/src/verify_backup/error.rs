@@ -0,0 +1,35 @@
+use std::io;
+use chunk_index::DatabaseError;
+use restore_backup::RestoreBackupError;
+
+
+quick_error!{
+    #[derive(Debug)]
+    pub enum VerifyBackupError {
+        IoError(err: io::Error) {
+            from()
+            display("I/O Error occured during verification: {} ", err)
+            cause(err)
+        }
+        DatabaseError(err: DatabaseError) {
+            from()
+            display("Database Error occured during verification: {} ", err)
+            cause(err)
+        }
+        ChunkIndexNotAvailable(err: RestoreBackupError) {
+            from()
+            display("The chunk index could not be fetched from the node: {} ", err)
+            cause(err)
+        }
+        BackupNotSelected(err: RestoreBackupError) {
+            display("The backup to verify could not be selected: {} ", err)
+            cause(err)
+        }
+        NodeCommunicationError {
+            description("The node did not respond with the expected message")
+        }
+        EmptyBackup {
+            description("The backup has no root folder")
+        }
+    }
+}
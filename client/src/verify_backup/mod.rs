@@ -0,0 +1,318 @@
+pub mod error;
+pub use self::error::VerifyBackupError;
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use redbackup_protocol::{Message, MessageKind, Session};
+use redbackup_protocol::message::GetChunks;
+
+use super::Progress;
+use super::config::{Config, DEFAULT_BATCH_SIZE, DEFAULT_CONCURRENCY};
+use super::chunk_index::{ChunkIndex, FileType};
+use super::chunk_index::schema::File;
+use super::create_backup::create_utils;
+use super::restore_backup::{self, BackupSelector};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MismatchKind {
+    /// In the backup, but not in the local directory.
+    Missing,
+    /// In the local directory, but not in the backup (e.g. excluded or created later).
+    Extra,
+    /// The type, size, content or link target differs.
+    Different,
+}
+
+impl MismatchKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            MismatchKind::Missing => "missing",
+            MismatchKind::Extra => "extra",
+            MismatchKind::Different => "different",
+        }
+    }
+}
+
+/// A file or folder of the local directory, that does not match the backup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// Path relative to the backup root.
+    pub path: PathBuf,
+    pub kind: MismatchKind,
+    /// What differs, e.g. "type", "size" or "content" (None if missing or extra).
+    pub detail: Option<String>,
+}
+
+/// Result of the verification of a local directory against a backup.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VerifyReport {
+    /// Mismatches sorted by path.
+    pub mismatches: Vec<Mismatch>,
+    /// Number of files and folders of the backup, that were compared.
+    pub checked_entries: usize,
+    /// Number of chunks, that were fetched from the node (only in deep mode).
+    pub checked_chunks: usize,
+    /// Chunks the node did not return.
+    pub missing_chunks: Vec<String>,
+    /// Chunks whose content does not match their identifier (SHA-256).
+    pub corrupt_chunks: Vec<String>,
+}
+
+impl VerifyReport {
+    /// Whether the local directory matches the backup and all checked chunks are intact.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty() && self.missing_chunks.is_empty() &&
+            self.corrupt_chunks.is_empty()
+    }
+
+    fn add(&mut self, path: PathBuf, kind: MismatchKind, detail: Option<&str>) {
+        debug!("{} {:?} ({:?})", kind.name(), path, detail);
+        self.mismatches.push(Mismatch {
+            path,
+            kind,
+            detail: detail.map(String::from),
+        });
+    }
+}
+
+/// Context to verify a backup against a local directory, without writing any files.
+pub struct VerifyBackupContext {
+    config: Config,
+    session: Session,
+    progress_sender: Sender<Progress>,
+}
+
+impl VerifyBackupContext {
+    pub fn new(
+        config: Config,
+        progress_sender: Sender<Progress>,
+    ) -> Result<Self, VerifyBackupError> {
+        let session = Session::new(config.addr)?;
+
+        Ok(Self {
+            config,
+            session,
+            progress_sender,
+        })
+    }
+
+    /// Compare the local directory (e.g. the backup source or a restored backup root) with the
+    /// backup. In deep mode, every chunk of the backup is also fetched from the node and checked.
+    pub fn run(
+        &mut self,
+        backup: &BackupSelector,
+        local_dir: &Path,
+        deep: bool,
+    ) -> Result<VerifyReport, VerifyBackupError> {
+        let backup_id = backup
+            .resolve(&mut self.session, &self.config.encryption)
+            .map_err(VerifyBackupError::BackupNotSelected)?;
+        info!(
+            "Request chunk index {} from node at {}",
+            backup_id,
+            self.config.addr
+        );
+        let chunk_index = restore_backup::fetch_chunk_index(
+            &mut self.session,
+            &self.config.encryption,
+            &backup_id,
+        )?;
+
+        info!("Compare {:?} with the backup", local_dir);
+        let mut report = verify_local_dir(&chunk_index, local_dir)?;
+
+        if deep {
+            info!("Check the chunks on the node");
+            self.verify_chunks(&chunk_index, &mut report)?;
+        }
+        Ok(report)
+    }
+
+    /// Fetch all chunks of the backup from the node and compare their hash with their identifier.
+    ///
+    /// The chunks are checked as they are stored on the node, i.e. compressed and encrypted.
+    fn verify_chunks(
+        &mut self,
+        chunk_index: &ChunkIndex,
+        report: &mut VerifyReport,
+    ) -> Result<(), VerifyBackupError> {
+        let mut chunks = chunk_index.get_all_chunks()?;
+        chunks.sort_by(|a, b| a.chunk_identifier.cmp(&b.chunk_identifier));
+        chunks.dedup_by(|a, b| a.chunk_identifier == b.chunk_identifier);
+        let mut progress = Progress::new(self.progress_sender.clone(), chunks.len());
+
+        let batches = create_utils::batch_chunks(chunks, DEFAULT_BATCH_SIZE);
+        let mut sent_batches = batches.iter();
+        self.session.call_window(
+            batches.len(),
+            |index| -> Result<Message, VerifyBackupError> {
+                Ok(GetChunks::new(
+                    batches[index]
+                        .iter()
+                        .map(|chunk| chunk.chunk_identifier.clone())
+                        .collect(),
+                ))
+            },
+            DEFAULT_CONCURRENCY,
+            |response| {
+                let batch = sent_batches.next().unwrap();
+                let chunk_contents: HashMap<String, Vec<u8>> = match response.body {
+                    MessageKind::ReturnChunks(body) => {
+                        body.chunks
+                            .into_iter()
+                            .map(|chunk| (chunk.chunk_identifier, chunk.chunk_content))
+                            .collect()
+                    }
+                    _ => return Err(VerifyBackupError::NodeCommunicationError),
+                };
+                for chunk in batch {
+                    match chunk_contents.get(&chunk.chunk_identifier) {
+                        None => {
+                            error!("Chunk {} is not available", chunk.chunk_identifier);
+                            report.missing_chunks.push(chunk.chunk_identifier.clone());
+                        }
+                        Some(content) => {
+                            if create_utils::content_hash(content) != chunk.chunk_identifier {
+                                error!("Chunk {} is corrupt", chunk.chunk_identifier);
+                                report.corrupt_chunks.push(chunk.chunk_identifier.clone());
+                            }
+                        }
+                    }
+                    report.checked_chunks += 1;
+                    progress.increment();
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Compare the content of a local directory with the content of the backup root.
+///
+/// Regular files are compared by their content hash (by their size in chunk indices of older
+/// clients), symlinks by their target. Permissions, ownership and dates are not compared.
+pub fn verify_local_dir(
+    chunk_index: &ChunkIndex,
+    local_dir: &Path,
+) -> Result<VerifyReport, VerifyBackupError> {
+    // The backup root is the only folder without parent folder.
+    let root = chunk_index.get_folders_by_parent(None)?.pop().ok_or(
+        VerifyBackupError::EmptyBackup,
+    )?;
+    let mut report = VerifyReport::default();
+    verify_folder(chunk_index, root.id, local_dir, &PathBuf::new(), &mut report)?;
+    report.mismatches.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(report)
+}
+
+fn verify_folder(
+    chunk_index: &ChunkIndex,
+    folder_id: i32,
+    local_dir: &Path,
+    path: &Path,
+    report: &mut VerifyReport,
+) -> Result<(), VerifyBackupError> {
+    let mut names: HashSet<OsString> = HashSet::new();
+
+    for subfolder in chunk_index.get_folders_by_parent(Some(folder_id))? {
+        let subfolder_path = path.join(subfolder.os_name());
+        names.insert(subfolder.os_name());
+        report.checked_entries += 1;
+        match symlink_metadata(&local_dir.join(&subfolder_path))? {
+            Some(ref metadata) if metadata.is_dir() => {
+                verify_folder(chunk_index, subfolder.id, local_dir, &subfolder_path, report)?
+            }
+            Some(_) => report.add(subfolder_path, MismatchKind::Different, Some("type")),
+            None => report.add(subfolder_path, MismatchKind::Missing, None),
+        }
+    }
+
+    for file in chunk_index.get_files_by_folder(folder_id)? {
+        let file_path = path.join(file.os_name());
+        names.insert(file.os_name());
+        report.checked_entries += 1;
+        let local_path = local_dir.join(&file_path);
+        match symlink_metadata(&local_path)? {
+            Some(metadata) => {
+                if let Some(detail) = compare_file(chunk_index, &file, &local_path, &metadata)? {
+                    report.add(file_path, MismatchKind::Different, Some(detail));
+                }
+            }
+            None => report.add(file_path, MismatchKind::Missing, None),
+        }
+    }
+
+    for entry in fs::read_dir(local_dir.join(path))? {
+        let name = entry?.file_name();
+        if !names.contains(&name) {
+            report.add(path.join(name), MismatchKind::Extra, None);
+        }
+    }
+    Ok(())
+}
+
+/// Get what differs between an existing local file and a file of the backup (if anything).
+fn compare_file(
+    chunk_index: &ChunkIndex,
+    file: &File,
+    local_path: &Path,
+    metadata: &Metadata,
+) -> Result<Option<&'static str>, VerifyBackupError> {
+    // A hardlink has the content of the file it points to.
+    let file = match (FileType::of_file(file)?, file.hardlink) {
+        (FileType::Hardlink, Some(original)) => chunk_index.get_file(original)?,
+        _ => file.clone(),
+    };
+    let file_type = match FileType::of_file(&file)? {
+        FileType::Hardlink => FileType::Regular,
+        file_type => file_type,
+    };
+    if FileType::of_metadata(metadata) != Some(file_type) {
+        return Ok(Some("type"));
+    }
+
+    let detail = match file_type {
+        FileType::Regular if metadata.len() != file.size as u64 => Some("size"),
+        FileType::Regular => {
+            let content_hash = match file.content_hash {
+                Some(ref content_hash) => content_hash,
+                None => return Ok(None),
+            };
+            if create_utils::file_hash(&local_path.to_path_buf())? != *content_hash {
+                Some("content")
+            } else {
+                None
+            }
+        }
+        FileType::Symlink => {
+            if Some(fs::read_link(local_path)?.into_os_string()) != file.os_link_target() {
+                Some("link_target")
+            } else {
+                None
+            }
+        }
+        FileType::CharDevice | FileType::BlockDevice => {
+            match file.device {
+                Some(device) if device as u64 != metadata.rdev() => Some("device"),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    Ok(detail)
+}
+
+/// Get the metadata of a path without following symlinks (None if it does not exist).
+fn symlink_metadata(path: &Path) -> Result<Option<Metadata>, io::Error> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,148 @@
+use std::ffi::{CString, OsStr};
+use std::io::Error;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::ptr;
+
+use libc;
+
+/// Namespaces of extended attributes on Linux.
+///
+/// POSIX ACLs are stored in the `system` namespace (`system.posix_acl_access` and
+/// `system.posix_acl_default`), SELinux labels in the `security` namespace.
+pub const NAMESPACES: [&'static str; 4] = ["user", "trusted", "security", "system"];
+
+/// Selects the extended attributes to back up or restore by their namespace.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct XattrFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl XattrFilter {
+    /// Create a filter from comma separated lists of namespaces (e.g. "user,security").
+    ///
+    /// Without include list, all namespaces that are not excluded are selected.
+    /// An unknown namespace is returned as error.
+    pub fn new(include: Option<&str>, exclude: Option<&str>) -> Result<XattrFilter, String> {
+        Ok(XattrFilter {
+            include: Self::parse_namespaces(include)?,
+            exclude: Self::parse_namespaces(exclude)?,
+        })
+    }
+
+    fn parse_namespaces(namespaces: Option<&str>) -> Result<Vec<String>, String> {
+        let mut parsed = Vec::new();
+        for namespace in namespaces.unwrap_or("").split(',') {
+            let namespace = namespace.trim();
+            if namespace.is_empty() {
+                continue;
+            }
+            if !NAMESPACES.contains(&namespace) {
+                return Err(namespace.into());
+            }
+            parsed.push(namespace.into());
+        }
+        Ok(parsed)
+    }
+
+    /// Check if an extended attribute (e.g. "security.selinux") is selected.
+    pub fn matches(&self, name: &str) -> bool {
+        let namespace = name.split('.').next().unwrap_or("");
+        let included = self.include.is_empty() || self.include.iter().any(|n| n == namespace);
+        included && !self.exclude.iter().any(|n| n == namespace)
+    }
+}
+
+/// Check if an extended attribute is a POSIX ACL, which extends the permissions.
+pub fn is_acl(name: &str) -> bool {
+    name.starts_with("system.posix_acl_")
+}
+
+/// Read all extended attributes of a file or folder (symlinks are not followed).
+///
+/// File systems without support for extended attributes have no attributes. Attributes whose
+/// name is not valid unicode are skipped.
+pub fn read_xattrs(path: &PathBuf) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+
+    let names = read_buffer(|buf, size| unsafe {
+        libc::llistxattr(c_path.as_ptr(), buf as *mut libc::c_char, size)
+    });
+    let names = match names {
+        Ok(names) => names,
+        Err(ref err) if err.raw_os_error() == Some(libc::ENOTSUP) => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut xattrs = Vec::new();
+    for name in names.split(|b| *b == 0).filter(|name| !name.is_empty()) {
+        let utf8_name = match OsStr::from_bytes(name).to_str() {
+            Some(utf8_name) => utf8_name,
+            None => {
+                warn!(
+                    "Skipped extended attribute {:?} of {:?}, as its name is not valid unicode",
+                    OsStr::from_bytes(name),
+                    path
+                );
+                continue;
+            }
+        };
+        let c_name = CString::new(name)?;
+        let value = read_buffer(|buf, size| unsafe {
+            libc::lgetxattr(c_path.as_ptr(), c_name.as_ptr(), buf, size)
+        });
+        let value = match value {
+            Ok(value) => value,
+            // The attribute was removed in the meantime.
+            Err(ref err) if err.raw_os_error() == Some(libc::ENODATA) => continue,
+            Err(err) => return Err(err),
+        };
+        xattrs.push((utf8_name.into(), value));
+    }
+    Ok(xattrs)
+}
+
+/// Set an extended attribute of a file or folder (symlinks are not followed).
+pub fn write_xattr(path: &PathBuf, name: &str, value: &[u8]) -> Result<(), Error> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let c_name = CString::new(name)?;
+    let result = unsafe {
+        libc::lsetxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr() as *const libc::c_void,
+            value.len(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Call an xattr function, that fills a buffer, with a buffer of the required size.
+fn read_buffer<F>(read: F) -> Result<Vec<u8>, Error>
+where
+    F: Fn(*mut libc::c_void, libc::size_t) -> libc::ssize_t,
+{
+    loop {
+        // Query the size first, the value may change until it is actually read.
+        let size = read(ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(Error::last_os_error());
+        }
+        let mut buf = vec![0u8; size as usize];
+        let size = read(buf.as_mut_ptr() as *mut libc::c_void, buf.len());
+        if size < 0 {
+            let err = Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ERANGE) {
+                continue;
+            }
+            return Err(err);
+        }
+        buf.truncate(size as usize);
+        return Ok(buf);
+    }
+}
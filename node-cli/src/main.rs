@@ -23,6 +23,24 @@ fn main() {
                     "ip address and port (<ip-address>:<port>) of other known nodes in the network",
                 ),
         )
+        .arg(
+            Arg::with_name("gc-grace-period")
+                .long("gc-grace-period")
+                .takes_value(true)
+                .value_name("DAYS")
+                .default_value("7")
+                .help("days to keep chunks after their expiration date before they are removed"),
+        )
+        .arg(
+            Arg::with_name("max-capacity")
+                .long("max-capacity")
+                .takes_value(true)
+                .value_name("BYTES")
+                .help("maximum number of bytes to store (limited by the free disk space only if omitted)"),
+        )
+        .arg(Arg::with_name("gc-dry-run").long("gc-dry-run").help(
+            "only report which expired chunks would be removed",
+        ))
         .arg(Arg::with_name("ip").help("IP to bind").default_value(
             "0.0.0.0",
         ))
@@ -50,8 +68,20 @@ fn main() {
         .unwrap_or_default()
         .map(|v| v.to_owned())
         .collect::<Vec<_>>();
+    let gc_grace_period = matches.value_of("gc-grace-period").unwrap();
+    let gc_dry_run = matches.is_present("gc-dry-run");
+    let max_capacity = matches.value_of("max-capacity");
 
-    let conf = Config::new(ip, port, storage_dir, db_file, known_nodes).unwrap_or_else(|err| {
+    let conf = Config::new(
+        ip,
+        port,
+        storage_dir,
+        db_file,
+        known_nodes,
+        gc_grace_period,
+        gc_dry_run,
+        max_capacity,
+    ).unwrap_or_else(|err| {
         eprintln!("{}", err);
         process::exit(1);
     });
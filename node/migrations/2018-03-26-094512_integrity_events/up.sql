@@ -0,0 +1,8 @@
+-- Corrupted chunks, that were repaired from a replica ("repaired") or could not be ("lost").
+CREATE TABLE integrity_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    chunk_identifier TEXT NOT NULL,
+    event_date DATETIME NOT NULL,
+    kind TEXT NOT NULL,
+    detail TEXT
+);
@@ -0,0 +1,39 @@
+use super::schema::*;
+use chrono::prelude::*;
+
+pub const REPAIRED: &'static str = "repaired";
+pub const LOST: &'static str = "lost";
+
+/// A corrupted chunk, that was repaired from a replica or is lost.
+#[derive(Queryable, Identifiable, PartialEq, Debug, Clone)]
+#[table_name = "integrity_events"]
+pub struct IntegrityEvent {
+    pub id: i32,
+    pub chunk_identifier: String,
+    pub event_date: NaiveDateTime,
+    /// `REPAIRED` or `LOST`
+    pub kind: String,
+    /// Node the healthy copy was fetched from, or what was wrong with the chunk.
+    pub detail: Option<String>,
+}
+
+#[derive(Insertable, PartialEq, Debug, Clone)]
+#[table_name = "integrity_events"]
+pub struct NewIntegrityEvent {
+    pub chunk_identifier: String,
+    pub event_date: NaiveDateTime,
+    pub kind: String,
+    pub detail: Option<String>,
+}
+
+impl NewIntegrityEvent {
+    /// Create an event that happened now.
+    pub fn new(chunk_identifier: &str, kind: &str, detail: Option<String>) -> Self {
+        NewIntegrityEvent {
+            chunk_identifier: chunk_identifier.into(),
+            event_date: Utc::now().naive_utc(),
+            kind: kind.into(),
+            detail,
+        }
+    }
+}
@@ -2,14 +2,17 @@ use r2d2::{Config, Pool, PooledConnection};
 use diesel::sqlite::SqliteConnection;
 use r2d2_diesel::ConnectionManager;
 use self::diesel::prelude::*;
+use chrono::NaiveDateTime;
 use r2d2;
 use diesel;
 
 mod chunk;
+pub mod integrity_event;
 mod schema;
 
 pub use self::chunk::Chunk;
-use self::schema::chunks;
+pub use self::integrity_event::{IntegrityEvent, NewIntegrityEvent};
+use self::schema::{chunks, integrity_events};
 
 embed_migrations!("migrations");
 no_arg_sql_function!(RANDOM, (), "Represents the sql RANDOM() function");
@@ -110,6 +113,54 @@ impl ChunkTable {
             .map_err(|e| DatabaseError::from(e))
     }
 
+    /// Load all chunks that expired before the given date.
+    pub fn load_expired_chunks(
+        &self,
+        expired_before: &NaiveDateTime,
+    ) -> Result<Vec<Chunk>, DatabaseError> {
+        let conn = self.get_db_connection()?;
+        chunks::dsl::chunks
+            .filter(chunks::dsl::expiration_date.lt(expired_before))
+            .load(&*conn)
+            .map_err(|e| DatabaseError::from(e))
+    }
+
+    /// Remove a chunk from the database, if it is still expired before the given date.
+    ///
+    /// As the expiration date may have been postponed in the meantime, it is checked again in
+    /// the same transaction. `remove_content` is executed within this transaction as well, so
+    /// the chunk stays in the database if its content could not be removed.
+    /// Returns whether the chunk was removed.
+    pub fn remove_expired_chunk<F, E>(
+        &self,
+        chunk_identifier: &str,
+        expired_before: &NaiveDateTime,
+        remove_content: F,
+    ) -> Result<bool, E>
+    where
+        F: FnOnce() -> Result<(), E>,
+        E: From<DatabaseError> + From<diesel::result::Error>,
+    {
+        let conn = self.get_db_connection()?;
+        trace!("Remove expired chunk {} as transaction", chunk_identifier);
+        conn.transaction::<_, E, _>(|| {
+            let db_chunk: Option<Chunk> = chunks::dsl::chunks
+                .find(chunk_identifier)
+                .first(&*conn)
+                .optional()?;
+
+            match db_chunk {
+                Some(ref chunk) if chunk.expiration_date < *expired_before => {
+                    diesel::delete(chunks::dsl::chunks.find(chunk_identifier))
+                        .execute(&*conn)?;
+                    remove_content()?;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        })
+    }
+
     /// Update a chunk in the database (postpone the expiration date if appropriate).
     pub fn update_chunk(&self, chunky: &Chunk) -> Result<Chunk, DatabaseError> {
         let conn = self.get_db_connection()?;
@@ -178,4 +229,41 @@ impl ChunkTable {
             .first::<Chunk>(&*conn)
             .map_err(|e| DatabaseError::from(e))
     }
+
+    /// Record that a corrupted chunk was repaired (or could not be).
+    pub fn add_integrity_event(&self, event: &NewIntegrityEvent) -> Result<(), DatabaseError> {
+        let conn = self.get_db_connection()?;
+        diesel::insert(event).into(integrity_events::table).execute(
+            &*conn,
+        )?;
+        Ok(())
+    }
+
+    pub fn get_integrity_events(&self) -> Result<Vec<IntegrityEvent>, DatabaseError> {
+        let conn = self.get_db_connection()?;
+        integrity_events::dsl::integrity_events
+            .order(integrity_events::dsl::id)
+            .load(&*conn)
+            .map_err(|e| DatabaseError::from(e))
+    }
+
+    /// Remove a chunk, whose content is lost, from the database and record the event.
+    ///
+    /// As the node no longer reports the chunk as available, clients send it again with their
+    /// next backup.
+    pub fn mark_chunk_lost(
+        &self,
+        chunk_identifier: &str,
+        event: &NewIntegrityEvent,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.get_db_connection()?;
+        conn.transaction::<_, DatabaseError, _>(|| {
+            diesel::delete(chunks::dsl::chunks.find(chunk_identifier))
+                .execute(&*conn)?;
+            diesel::insert(event).into(integrity_events::table).execute(
+                &*conn,
+            )?;
+            Ok(())
+        })
+    }
 }
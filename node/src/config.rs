@@ -6,6 +6,7 @@ use std::string::String;
 use std::path::PathBuf;
 use std;
 
+use chrono::Duration;
 use dns_lookup::lookup_host;
 
 /// Configuration of a node
@@ -14,6 +15,12 @@ pub struct Config {
     pub storage_location: PathBuf,
     pub db_location: String,
     pub known_nodes: Vec<SocketAddr>,
+    /// Time after the expiration date until a chunk is removed by the garbage collection.
+    pub gc_grace_period: Duration,
+    /// Only report what the garbage collection would remove.
+    pub gc_dry_run: bool,
+    /// Maximum number of bytes the node stores (unlimited if not set).
+    pub max_capacity: Option<u64>,
 }
 
 quick_error! {
@@ -39,6 +46,14 @@ quick_error! {
         NoIPsFound(msg: String){
             display("{}", msg)
         }
+        InvalidGracePeriod(err: std::num::ParseIntError) {
+            display("Invalid garbage collection grace period given ({})", err)
+            cause(err)
+        }
+        InvalidMaxCapacity(err: std::num::ParseIntError) {
+            display("Invalid maximum capacity given ({})", err)
+            cause(err)
+        }
     }
 }
 
@@ -49,6 +64,9 @@ impl Config {
         storage_location: &str,
         db_location: &str,
         known_nodes_strs: Vec<String>,
+        gc_grace_period_days: &str,
+        gc_dry_run: bool,
+        max_capacity: Option<&str>,
     ) -> Result<Config, ParseError> {
         let ip = ip.parse()?;
         let port = port.parse()?;
@@ -58,6 +76,17 @@ impl Config {
 
         let db_location = db_location.to_owned();
 
+        let gc_grace_period = Duration::days(gc_grace_period_days.parse().map_err(|e| {
+            ParseError::InvalidGracePeriod(e)
+        })?);
+
+        let max_capacity = match max_capacity {
+            Some(max_capacity) => Some(max_capacity.parse().map_err(|e| {
+                ParseError::InvalidMaxCapacity(e)
+            })?),
+            None => None,
+        };
+
         // Collect and parse all specified node hostnames
         let mut known_nodes = Vec::new();
         for known_node in known_nodes_strs {
@@ -100,6 +129,9 @@ impl Config {
             storage_location,
             db_location,
             known_nodes,
+            gc_grace_period,
+            gc_dry_run,
+            max_capacity,
         })
     }
 }
@@ -57,6 +57,8 @@ pub fn run(config: Config) {
             chunk_table.clone(),
             storage.clone(),
             config.known_nodes.clone(),
+            config.gc_grace_period,
+            config.gc_dry_run,
         );
 
         move || {
@@ -65,6 +67,7 @@ pub fn run(config: Config) {
                 cpu_pool.clone(),
                 chunk_table.clone(),
                 storage.clone(),
+                config.max_capacity,
             ))
         }
     });
@@ -0,0 +1,150 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel;
+use futures_cpupool::CpuPool;
+use futures_cpupool::CpuFuture;
+
+use redbackup_storage::{Storage, StorageError};
+use chunk_table::{ChunkTable, DatabaseError};
+
+use super::Task;
+
+/// This task removes chunks, whose expiration date (plus a grace period) has passed.
+pub struct GarbageCollectionTask {
+    pool: CpuPool,
+    storage: Storage,
+    chunk_table: ChunkTable,
+    grace_period: Duration,
+    dry_run: bool,
+}
+
+impl GarbageCollectionTask {
+    pub fn new(
+        storage: Storage,
+        chunk_table: ChunkTable,
+        grace_period: Duration,
+        dry_run: bool,
+    ) -> Self {
+        let pool = CpuPool::new(1);
+        GarbageCollectionTask {
+            storage,
+            pool,
+            chunk_table,
+            grace_period,
+            dry_run,
+        }
+    }
+}
+
+impl Task for GarbageCollectionTask {
+    fn exec(&self) -> CpuFuture<(), ()> {
+        let chunk_table = self.chunk_table.clone();
+        let storage = self.storage.clone();
+        let expired_before = Utc::now().naive_utc() - self.grace_period;
+        let dry_run = self.dry_run;
+
+        self.pool.spawn_fn(move || {
+            info!("begin with garbage collection");
+            match collect_garbage(&chunk_table, &storage, &expired_before, dry_run) {
+                Ok(ref report) if dry_run => {
+                    info!(
+                        "garbage collection (dry run) would reclaim {} chunks ({} bytes)",
+                        report.chunks,
+                        report.bytes
+                    );
+                    Ok(())
+                }
+                Ok(report) => {
+                    info!(
+                        "successfully finished garbage collection, reclaimed {} chunks ({} bytes)",
+                        report.chunks,
+                        report.bytes
+                    );
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("garbage collection has failed with a problem: {}", e);
+                    Err(())
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "garbage collection"
+    }
+}
+
+quick_error!{
+    #[derive(Debug)]
+    pub enum GarbageCollectionError {
+        DatabaseError(err: DatabaseError) {
+            from()
+            from(err: diesel::result::Error) -> (DatabaseError::from(err))
+            display("DatabaseError: {}", err)
+            cause(err)
+        }
+        StorageError(err: StorageError) {
+            from()
+            display("StorageError: {}", err)
+            cause(err)
+        }
+    }
+}
+
+/// Summary of the chunks that were (or in a dry run would be) removed.
+#[derive(Debug, Default, PartialEq)]
+pub struct GarbageCollectionReport {
+    pub chunks: usize,
+    pub bytes: u64,
+}
+
+/// Remove all chunks that expired before the given date from the chunk table and the storage.
+///
+/// In a dry run, nothing is removed and the report only states what would be reclaimed.
+pub fn collect_garbage(
+    chunk_table: &ChunkTable,
+    storage: &Storage,
+    expired_before: &NaiveDateTime,
+    dry_run: bool,
+) -> Result<GarbageCollectionReport, GarbageCollectionError> {
+    let mut report = GarbageCollectionReport::default();
+
+    for chunk in chunk_table.load_expired_chunks(expired_before)? {
+        let identifier = &chunk.chunk_identifier;
+        // A chunk without content still has to be removed from the chunk table.
+        let size = match storage.size(identifier) {
+            Ok(size) => size,
+            Err(StorageError::GetNonExistingChunk(_)) => 0,
+            Err(e) => return Err(GarbageCollectionError::from(e)),
+        };
+
+        if dry_run {
+            debug!(
+                "Chunk {} expired at {} and would be removed",
+                identifier,
+                chunk.expiration_date
+            );
+        } else {
+            let removed = chunk_table.remove_expired_chunk(identifier, expired_before, || {
+                match storage.delete(identifier) {
+                    Ok(()) |
+                    Err(StorageError::DeleteNonExistingChunk(_)) => Ok(()),
+                    Err(e) => Err(GarbageCollectionError::from(e)),
+                }
+            })?;
+            if !removed {
+                debug!("Chunk {} is no longer expired and is kept", identifier);
+                continue;
+            }
+            debug!(
+                "Removed chunk {}, which expired at {}",
+                identifier,
+                chunk.expiration_date
+            );
+        }
+
+        report.chunks += 1;
+        report.bytes += size;
+    }
+    Ok(report)
+}
@@ -1,25 +1,35 @@
+use std::io;
+use std::net::SocketAddr;
+
 use futures_cpupool::CpuPool;
 use futures_cpupool::CpuFuture;
 
+use redbackup_protocol::{MessageKind, Session};
+use redbackup_protocol::message::GetChunks;
 use redbackup_storage::{Storage, StorageError};
-use chunk_table::{ChunkTable, DatabaseError};
+use chunk_table::{ChunkTable, DatabaseError, NewIntegrityEvent};
+use chunk_table::integrity_event::{LOST, REPAIRED};
 
 use super::Task;
 
 /// This task verifies, that the file content in the storage equals to the chunk hash.
+///
+/// Corrupted chunks are quarantined and repaired with a healthy copy from a known node.
 pub struct IntegrityCheckTask {
     pool: CpuPool,
     storage: Storage,
     chunk_table: ChunkTable,
+    known_nodes: Vec<SocketAddr>,
 }
 
 impl IntegrityCheckTask {
-    pub fn new(storage: Storage, chunk_table: ChunkTable) -> Self {
+    pub fn new(storage: Storage, chunk_table: ChunkTable, known_nodes: Vec<SocketAddr>) -> Self {
         let pool = CpuPool::new(1);
         IntegrityCheckTask {
             storage,
             pool,
             chunk_table,
+            known_nodes,
         }
     }
 }
@@ -28,13 +38,26 @@ impl Task for IntegrityCheckTask {
     fn exec(&self) -> CpuFuture<(), ()> {
         let chunk_table = self.chunk_table.clone();
         let storage = self.storage.clone();
+        let known_nodes = self.known_nodes.clone();
         self.pool.spawn_fn(move || {
             info!("begin with integrity check");
-            let result = check_integrity(chunk_table, storage).map_err(|e| {
-                panic!("integrity check has failed with a problem: {}", e);
-            });
-            info!("successfully finished integrity check");
-            result
+            match check_integrity(&chunk_table, &storage, &known_nodes) {
+                Ok(report) => {
+                    info!(
+                        "successfully finished integrity check of {} chunks \
+                         ({} repaired, {} lost, {} deferred)",
+                        report.checked,
+                        report.repaired,
+                        report.lost,
+                        report.deferred
+                    );
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("integrity check has failed with a problem: {}", e);
+                    Err(())
+                }
+            }
         })
     }
 
@@ -51,26 +74,145 @@ quick_error!{
             display("DatabaseError: {}", err)
             cause(err)
         }
-        StorageCorruption(err: StorageError) {
+        StorageError(err: StorageError) {
             from()
-            display("Corruption detected: {}", err)
+            display("StorageError: {}", err)
             cause(err)
         }
-
     }
 }
 
-/// Check the integrity of chunks in the storage.
-fn check_integrity(chunk_table: ChunkTable, storage: Storage) -> Result<(), IntegrityCheckError> {
+/// Summary of the checked chunks.
+#[derive(Debug, Default, PartialEq)]
+pub struct IntegrityCheckReport {
+    pub checked: usize,
+    /// Corrupted (or missing) chunks, that were replaced by a copy from a known node.
+    pub repaired: usize,
+    /// Corrupted (or missing) chunks, that no known node has.
+    pub lost: usize,
+    /// Corrupted (or missing) chunks, that could not be repaired as a known node was not
+    /// reachable. They are kept, to be repaired by a later check.
+    pub deferred: usize,
+}
+
+/// Outcome of the attempt to repair a corrupted chunk.
+#[derive(Debug, PartialEq)]
+enum Repair {
+    /// A healthy copy of this node was put in place.
+    Repaired(SocketAddr),
+    /// None of the known nodes has a healthy copy of the chunk.
+    Absent,
+    /// None of the reachable nodes has a healthy copy, but some nodes were not reachable.
+    Unreachable,
+}
+
+/// Check the integrity of chunks in the storage and repair corrupted chunks.
+pub fn check_integrity(
+    chunk_table: &ChunkTable,
+    storage: &Storage,
+    known_nodes: &[SocketAddr],
+) -> Result<IntegrityCheckReport, IntegrityCheckError> {
+    let mut report = IntegrityCheckReport::default();
     // As for the prototype, the number of chunks to check at the same time is a magic number that is chosen arbitrary.
     // In the future, this number should depend on the number of chunks on the node and other heuristics.
     let chunks = chunk_table.load_random_chunks(5)?;
     for chunk in chunks {
-        storage.verify(&chunk.chunk_identifier)?;
-        debug!(
-            "Integrity check for chunk {} successful",
-            chunk.chunk_identifier
-        );
+        report.checked += 1;
+        let identifier = &chunk.chunk_identifier;
+        let corruption = match storage.verify(identifier) {
+            Ok(()) => {
+                debug!("Integrity check for chunk {} successful", identifier);
+                continue;
+            }
+            Err(err @ StorageError::CorruptedChunk(..)) => {
+                error!("Corruption detected: {}", err);
+                storage.quarantine(identifier)?;
+                format!("{}", err)
+            }
+            Err(err @ StorageError::GetNonExistingChunk(..)) => {
+                error!("Chunk {} is missing in the storage", identifier);
+                format!("{}", err)
+            }
+            Err(err) => return Err(IntegrityCheckError::from(err)),
+        };
+
+        match repair_chunk(storage, known_nodes, identifier)? {
+            Repair::Repaired(node_addr) => {
+                let event =
+                    NewIntegrityEvent::new(identifier, REPAIRED, Some(node_addr.to_string()));
+                chunk_table.add_integrity_event(&event)?;
+                report.repaired += 1;
+            }
+            Repair::Unreachable => {
+                warn!(
+                    "Chunk {} could not be repaired, as not all known nodes are reachable",
+                    identifier
+                );
+                report.deferred += 1;
+            }
+            Repair::Absent => {
+                error!("Chunk {} is lost, no known node has a healthy copy", identifier);
+                let event = NewIntegrityEvent::new(identifier, LOST, Some(corruption));
+                chunk_table.mark_chunk_lost(identifier, &event)?;
+                report.lost += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Fetch a healthy copy of a corrupted chunk from the known nodes, one after the other, and put
+/// it in place.
+fn repair_chunk(
+    storage: &Storage,
+    known_nodes: &[SocketAddr],
+    identifier: &str,
+) -> Result<Repair, IntegrityCheckError> {
+    let mut unreachable = false;
+    for node_addr in known_nodes {
+        let content = match fetch_chunk(*node_addr, identifier) {
+            Ok(Some(content)) => content,
+            Ok(None) => {
+                debug!("Chunk {} is not available on node {}", identifier, node_addr);
+                continue;
+            }
+            Err(err) => {
+                warn!("Could not fetch chunk {} from node {}: {}", identifier, node_addr, err);
+                unreachable = true;
+                continue;
+            }
+        };
+
+        storage.persist(identifier, &content)?;
+        if let Err(err) = storage.verify(identifier) {
+            warn!("The copy of node {} is corrupted as well: {}", node_addr, err);
+            storage.delete(identifier)?;
+            continue;
+        }
+
+        info!("Repaired chunk {} with a copy of node {}", identifier, node_addr);
+        return Ok(Repair::Repaired(*node_addr));
+    }
+    if unreachable {
+        Ok(Repair::Unreachable)
+    } else {
+        Ok(Repair::Absent)
+    }
+}
+
+/// Request the content of a chunk from a node (None if the node does not have it).
+fn fetch_chunk(node_addr: SocketAddr, identifier: &str) -> Result<Option<Vec<u8>>, io::Error> {
+    let mut session = Session::new(node_addr)?;
+    let response = session.call(GetChunks::new(vec![identifier.into()]))?;
+    match response.body {
+        MessageKind::ReturnChunks(body) => {
+            Ok(body.chunks.into_iter().find(|chunk| chunk.chunk_identifier == identifier).map(
+                |chunk| chunk.chunk_content,
+            ))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The node did not respond with the expected message",
+        )),
     }
-    Ok(())
 }
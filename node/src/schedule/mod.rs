@@ -3,6 +3,7 @@ use std::sync::Arc;
 use std::time;
 use std::time::Duration;
 
+use chrono;
 use futures::Future;
 use futures_cpupool::CpuFuture;
 use tokio_core::reactor::Handle;
@@ -12,11 +13,13 @@ use tokio_timer::Timer;
 use redbackup_storage::Storage;
 use chunk_table::ChunkTable;
 
-mod integrity_check;
+pub mod integrity_check;
 mod replication;
+pub mod garbage_collection;
 
 use self::integrity_check::IntegrityCheckTask;
 use self::replication::ReplicateTask;
+use self::garbage_collection::GarbageCollectionTask;
 
 
 /// Setup regularly scheduled tasks in the event loop.
@@ -25,18 +28,28 @@ pub fn setup(
     chunk_table: ChunkTable,
     storage: Storage,
     known_nodes: Vec<SocketAddr>,
+    gc_grace_period: chrono::Duration,
+    gc_dry_run: bool,
 ) {
     // As for the prototype, the duration between checks is a magic number that is chosen arbitrary.
     // In the future, this number should depend on the number of chunks on the node and other heuristics.
     info!("Setting up replication schedule..");
     let timeout = Duration::from_secs(30);
-    let replication_task = ReplicateTask::new(storage.clone(), chunk_table.clone(), known_nodes);
+    let replication_task =
+        ReplicateTask::new(storage.clone(), chunk_table.clone(), known_nodes.clone());
     Schedule::new(handle.clone(), Arc::new(replication_task), timeout).schedule();
 
     info!("Setting up integrity check schedule..");
     let timeout = time::Duration::from_secs(60);
-    let integrity_check_task = IntegrityCheckTask::new(storage, chunk_table);
+    let integrity_check_task =
+        IntegrityCheckTask::new(storage.clone(), chunk_table.clone(), known_nodes);
     Schedule::new(handle.clone(), Arc::new(integrity_check_task), timeout).schedule();
+
+    info!("Setting up garbage collection schedule..");
+    let timeout = time::Duration::from_secs(60 * 60);
+    let garbage_collection_task =
+        GarbageCollectionTask::new(storage, chunk_table, gc_grace_period, gc_dry_run);
+    Schedule::new(handle.clone(), Arc::new(garbage_collection_task), timeout).schedule();
 }
 
 
@@ -1,14 +1,9 @@
 use std::io;
 use std::net::SocketAddr;
 
-use futures::Future;
 use futures_cpupool::{CpuFuture, CpuPool};
-use tokio_core;
-use tokio_core::reactor::Core;
-use tokio_proto::TcpClient;
-use tokio_service::Service;
 
-use redbackup_protocol::RedClientProto;
+use redbackup_protocol::{Message, MessageKind, Session};
 use redbackup_protocol::message::*;
 use redbackup_storage::Storage;
 use chunk_table::{Chunk, ChunkTable, DatabaseError};
@@ -99,7 +94,6 @@ fn replicate(
     let chunks = chunk_table.load_random_chunks(5)?;
     debug!("Loading chunks: {:?}", chunks);
 
-    let mut event_loop = tokio_core::reactor::Core::new()?;
     let chunk_elements: Vec<_> = chunks.clone().into_iter().map(|c| c.into()).collect();
 
     if chunk_elements.len() == 0 {
@@ -113,9 +107,9 @@ fn replicate(
     );
     for node_addr in known_nodes {
         debug!("Replicating selected chunks to node {}", node_addr);
+        let mut session = Session::new(node_addr)?;
 
-        let node_chunks =
-            get_available_chunks_from_node(chunk_elements.clone(), &node_addr, &mut event_loop)?;
+        let node_chunks = get_available_chunks_from_node(chunk_elements.clone(), &mut session)?;
         let mut missing_chunks = chunks.clone();
         info!(
             "{} of total {} chunks are already present on node {}",
@@ -127,9 +121,7 @@ fn replicate(
 
         debug!("missing_chunks: {:?}", missing_chunks);
 
-        for chunk in missing_chunks {
-            send_chunk_to_node(chunk, &storage, &node_addr, &mut event_loop)?;
-        }
+        send_chunks_to_node(missing_chunks, &storage, &mut session)?;
     }
     info!("Replication completed successfully");
     Ok(())
@@ -137,11 +129,10 @@ fn replicate(
 
 fn get_available_chunks_from_node(
     chunk_elements: Vec<ChunkElement>,
-    node_addr: &SocketAddr,
-    event_loop: &mut Core,
+    session: &mut Session,
 ) -> Result<Vec<ChunkElement>, ReplicationError> {
     let req = GetChunkStates::new(chunk_elements);
-    message_node_sync(req, node_addr, event_loop).map(|res| {
+    message_node_sync(req, session).map(|res| {
         match res.body {
             MessageKind::ReturnChunkStates(body) => Ok(body.chunks),
             _ => Err(ReplicationError::NodeCommunicationError),
@@ -149,37 +140,55 @@ fn get_available_chunks_from_node(
     })?
 }
 
-fn send_chunk_to_node(
-    chunk: Chunk,
+/// Send the chunks to the node. The chunks are sent one per message, pipelined on the session.
+fn send_chunks_to_node(
+    chunks: Vec<Chunk>,
     storage: &Storage,
-    node_addr: &SocketAddr,
-    event_loop: &mut Core,
+    session: &mut Session,
 ) -> Result<(), ReplicationError> {
-    let chunk_identifier = chunk.chunk_identifier.clone();
-    debug!("Sending missing chunk {} to node {}", node_addr, node_addr);
-    let chunk = utils::chunk_to_chunk_contents_element(chunk, storage).unwrap();
-    let req = PostChunks::new(vec![chunk]);
-    let acknowledged_chunks = message_node_sync(req, node_addr, event_loop).map(|res| {
-        match res.body {
+    let mut chunk_identifiers = Vec::new();
+    let mut requests = Vec::new();
+    for chunk in chunks {
+        debug!(
+            "Sending missing chunk {} to node {}",
+            chunk.chunk_identifier,
+            session.addr()
+        );
+        chunk_identifiers.push(chunk.chunk_identifier.clone());
+        let chunk = utils::chunk_to_chunk_contents_element(chunk, storage).unwrap();
+        requests.push(PostChunks::new(vec![chunk]));
+    }
+
+    if requests.is_empty() {
+        return Ok(());
+    }
+
+    let peer_addr = session.addr().clone();
+    let responses = session.call_all(requests).map_err(|e| {
+        ReplicationError::MessageSendProblem(e, peer_addr)
+    })?;
+
+    for (chunk_identifier, response) in chunk_identifiers.into_iter().zip(responses) {
+        let acknowledged_chunks = match response.body {
             MessageKind::AcknowledgeChunks(body) => Ok(body.chunks),
             _ => Err(ReplicationError::NodeCommunicationError),
-        }
-    })??;
+        }?;
 
-    let acknowledged_chunk: &ChunkElement = acknowledged_chunks.get(0).ok_or(
-        ReplicationError::ChunkNotAcknowledged(chunk_identifier.clone()),
-    )?;
+        let acknowledged_chunk: &ChunkElement = acknowledged_chunks.get(0).ok_or(
+            ReplicationError::ChunkNotAcknowledged(chunk_identifier.clone()),
+        )?;
 
-    if acknowledged_chunk.chunk_identifier != chunk_identifier {
-        return Err(ReplicationError::WrongChunkAcknowledged(
-            chunk_identifier.clone(),
-            acknowledged_chunk.chunk_identifier.clone(),
-        ));
+        if acknowledged_chunk.chunk_identifier != chunk_identifier {
+            return Err(ReplicationError::WrongChunkAcknowledged(
+                chunk_identifier.clone(),
+                acknowledged_chunk.chunk_identifier.clone(),
+            ));
+        }
+        debug!(
+            "Chunk {} is now replicated",
+            acknowledged_chunk.chunk_identifier
+        );
     }
-    debug!(
-        "Chunk {} is now replicated",
-        acknowledged_chunk.chunk_identifier
-    );
     Ok(())
 }
 
@@ -192,16 +201,9 @@ fn reduce_by_remaining_chunks(elements: &mut Vec<Chunk>, reduction: &Vec<ChunkEl
     });
 }
 
-fn message_node_sync(
-    message: Message,
-    peer_addr: &SocketAddr,
-    event_loop: &mut Core,
-) -> Result<Message, ReplicationError> {
-    let handle = event_loop.handle();
-    let future = TcpClient::new(RedClientProto)
-        .connect(&peer_addr, &handle)
-        .and_then(|client| client.call(message));
-    event_loop.run(future).map_err(|e| {
-        ReplicationError::MessageSendProblem(e, peer_addr.clone())
+fn message_node_sync(message: Message, session: &mut Session) -> Result<Message, ReplicationError> {
+    let peer_addr = session.addr().clone();
+    session.call(message).map_err(|e| {
+        ReplicationError::MessageSendProblem(e, peer_addr)
     })
 }
@@ -1,5 +1,6 @@
 use std::io;
 
+use chrono::Utc;
 use futures::future;
 use futures::Future;
 use futures_cpupool::CpuPool;
@@ -17,6 +18,8 @@ pub struct NodeService {
     pub cpu_pool: CpuPool,
     pub chunk_table: ChunkTable,
     pub storage: Storage,
+    /// Maximum number of bytes the node stores (in addition to the limit of the file system).
+    pub max_capacity: Option<u64>,
 }
 
 impl Service for NodeService {
@@ -28,7 +31,7 @@ impl Service for NodeService {
     fn call(&self, request: Message) -> Self::Future {
         trace!("Handle request message {:?}", request);
         match request.body {
-            MessageKind::GetDesignation(_) => self.handle_designation(),
+            MessageKind::GetDesignation(body) => self.handle_designation(body),
             MessageKind::GetChunkStates(body) => self.handle_get_chunk_states(body),
             MessageKind::PostChunks(body) => self.handle_post_chunks(body),
             MessageKind::GetRootHandles(_) => self.handle_return_root_handles(),
@@ -39,11 +42,17 @@ impl Service for NodeService {
 }
 
 impl NodeService {
-    pub fn new(cpu_pool: CpuPool, chunk_table: ChunkTable, storage: Storage) -> NodeService {
+    pub fn new(
+        cpu_pool: CpuPool,
+        chunk_table: ChunkTable,
+        storage: Storage,
+        max_capacity: Option<u64>,
+    ) -> NodeService {
         NodeService {
             cpu_pool,
             chunk_table,
             storage,
+            max_capacity,
         }
     }
 
@@ -56,10 +65,63 @@ impl NodeService {
         ))
     }
 
-    fn handle_designation(&self) -> Box<Future<Item = Message, Error = io::Error>> {
-        info!("Grant designation");
+    fn handle_designation(
+        &self,
+        body: GetDesignation,
+    ) -> Box<Future<Item = Message, Error = io::Error>> {
+        if body.expiration_date <= Utc::now() {
+            info!(
+                "Deny designation, as the expiration date {} has already passed",
+                body.expiration_date
+            );
+            return Box::new(future::ok(ReturnDesignation::new(false)));
+        }
+
+        let storage = self.storage.clone();
+        let max_capacity = self.max_capacity;
+
         // Create future
-        Box::new(future::ok(ReturnDesignation::new(true)))
+        Box::new(self.cpu_pool.spawn_fn(move || -> Result<_, io::Error> {
+            let estimate_size = body.estimate_size;
+
+            let available_space = match storage.available_space() {
+                Ok(available_space) => available_space,
+                Err(err) => {
+                    let msg = format!("Failed to get the available space: {}", err);
+                    return Ok(InternalError::new(&msg));
+                }
+            };
+            if estimate_size > available_space {
+                info!(
+                    "Deny designation, as {} bytes are requested but only {} bytes are available",
+                    estimate_size,
+                    available_space
+                );
+                return Ok(ReturnDesignation::new(false));
+            }
+
+            if let Some(max_capacity) = max_capacity {
+                let used_space = match storage.used_space() {
+                    Ok(used_space) => used_space,
+                    Err(err) => {
+                        let msg = format!("Failed to get the used space: {}", err);
+                        return Ok(InternalError::new(&msg));
+                    }
+                };
+                if used_space.saturating_add(estimate_size) > max_capacity {
+                    info!(
+                        "Deny designation, as {} bytes are requested but {} of {} bytes are used",
+                        estimate_size,
+                        used_space,
+                        max_capacity
+                    );
+                    return Ok(ReturnDesignation::new(false));
+                }
+            }
+
+            info!("Grant designation for {} bytes", estimate_size);
+            Ok(ReturnDesignation::new(true))
+        }))
     }
 
     fn handle_get_chunk_states(
@@ -1,4 +1,7 @@
 use chrono::NaiveDate;
+use diesel;
+
+use chunk_table::DatabaseError;
 
 use super::chunk_table_utils::ChunkTableUtils;
 use super::test_data::ExampleChunk;
@@ -71,3 +74,67 @@ fn update_chunk_older_date() {
     );
     assert_eq!(original, updated);
 }
+
+#[test]
+fn load_expired_chunks() {
+    let chunk_table = ChunkTableUtils::chunk_table_for_test("load_expired_chunks");
+    ChunkTableUtils::insert_and_verify(&chunk_table, ExampleChunk::one());
+    ChunkTableUtils::insert_and_verify(&chunk_table, ExampleChunk::two());
+    ChunkTableUtils::insert_and_verify(&chunk_table, ExampleChunk::three());
+
+    let expired_before = NaiveDate::from_ymd(2015, 1, 1).and_hms(0, 0, 0);
+    let mut loaded = chunk_table.load_expired_chunks(&expired_before).unwrap();
+    loaded.sort_by(|a, b| a.expiration_date.cmp(&b.expiration_date));
+    assert_eq!(vec![ExampleChunk::three(), ExampleChunk::one()], loaded);
+}
+
+#[test]
+fn remove_expired_chunk() {
+    let chunk_table = ChunkTableUtils::chunk_table_for_test("remove_expired_chunk");
+    let chunk = ChunkTableUtils::insert_and_verify(&chunk_table, ExampleChunk::one());
+
+    let expired_before = NaiveDate::from_ymd(2015, 1, 1).and_hms(0, 0, 0);
+    let removed = chunk_table
+        .remove_expired_chunk::<_, DatabaseError>(
+            &chunk.chunk_identifier,
+            &expired_before,
+            || Ok(()),
+        )
+        .unwrap();
+    assert!(removed);
+    assert!(chunk_table.get_chunk(&chunk.chunk_identifier).is_err());
+}
+
+#[test]
+fn remove_expired_chunk_keeps_postponed_chunk() {
+    let chunk_table =
+        ChunkTableUtils::chunk_table_for_test("remove_expired_chunk_keeps_postponed_chunk");
+    let chunk = ChunkTableUtils::insert_and_verify(&chunk_table, ExampleChunk::one());
+
+    let expired_before = NaiveDate::from_ymd(2014, 1, 1).and_hms(0, 0, 0);
+    let removed = chunk_table
+        .remove_expired_chunk::<_, DatabaseError>(
+            &chunk.chunk_identifier,
+            &expired_before,
+            || panic!("The content of a not expired chunk must not be removed"),
+        )
+        .unwrap();
+    assert!(!removed);
+    assert_eq!(chunk, chunk_table.get_chunk(&chunk.chunk_identifier).unwrap());
+}
+
+#[test]
+fn remove_expired_chunk_rolls_back_on_error() {
+    let chunk_table =
+        ChunkTableUtils::chunk_table_for_test("remove_expired_chunk_rolls_back_on_error");
+    let chunk = ChunkTableUtils::insert_and_verify(&chunk_table, ExampleChunk::one());
+
+    let expired_before = NaiveDate::from_ymd(2015, 1, 1).and_hms(0, 0, 0);
+    let result = chunk_table.remove_expired_chunk(
+        &chunk.chunk_identifier,
+        &expired_before,
+        || Err(DatabaseError::QueryError(diesel::result::Error::RollbackTransaction)),
+    );
+    assert!(result.is_err());
+    assert_eq!(chunk, chunk_table.get_chunk(&chunk.chunk_identifier).unwrap());
+}
@@ -0,0 +1,54 @@
+use chrono::NaiveDate;
+
+use schedule::garbage_collection::{collect_garbage, GarbageCollectionReport};
+use super::service_utils::ServiceUtils;
+use super::test_data::ExampleChunkContentElement;
+
+#[test]
+fn collect_garbage_removes_expired_chunks() {
+    let service = ServiceUtils::service_for_test("collect_garbage_removes_expired_chunks");
+    let element = ExampleChunkContentElement::one();
+    let size = element.chunk_content.len() as u64;
+    ServiceUtils::insert_and_verify(&service, ExampleChunkContentElement::one());
+
+    let expired_before = NaiveDate::from_ymd(2100, 1, 1).and_hms(0, 0, 0);
+    let report = collect_garbage(&service.chunk_table, &service.storage, &expired_before, false)
+        .unwrap();
+
+    assert_eq!(GarbageCollectionReport { chunks: 1, bytes: size }, report);
+    assert!(service.chunk_table.get_chunk(&element.chunk_identifier).is_err());
+    assert!(service.storage.get(&element.chunk_identifier).is_err());
+}
+
+#[test]
+fn collect_garbage_keeps_chunks_in_dry_run() {
+    let service = ServiceUtils::service_for_test("collect_garbage_keeps_chunks_in_dry_run");
+    let element = ExampleChunkContentElement::one();
+    let size = element.chunk_content.len() as u64;
+    ServiceUtils::insert_and_verify(&service, ExampleChunkContentElement::one());
+
+    let expired_before = NaiveDate::from_ymd(2100, 1, 1).and_hms(0, 0, 0);
+    let report = collect_garbage(&service.chunk_table, &service.storage, &expired_before, true)
+        .unwrap();
+
+    assert_eq!(GarbageCollectionReport { chunks: 1, bytes: size }, report);
+    assert!(service.chunk_table.get_chunk(&element.chunk_identifier).is_ok());
+    assert_eq!(
+        element.chunk_content,
+        service.storage.get(&element.chunk_identifier).unwrap()
+    );
+}
+
+#[test]
+fn collect_garbage_keeps_unexpired_chunks() {
+    let service = ServiceUtils::service_for_test("collect_garbage_keeps_unexpired_chunks");
+    let element = ExampleChunkContentElement::one();
+    ServiceUtils::insert_and_verify(&service, ExampleChunkContentElement::one());
+
+    let expired_before = NaiveDate::from_ymd(1970, 1, 1).and_hms(0, 0, 0);
+    let report = collect_garbage(&service.chunk_table, &service.storage, &expired_before, false)
+        .unwrap();
+
+    assert_eq!(GarbageCollectionReport::default(), report);
+    assert!(service.storage.get(&element.chunk_identifier).is_ok());
+}
@@ -0,0 +1,160 @@
+use std::net::{SocketAddr, TcpListener};
+use std::sync::mpsc;
+use std::thread;
+
+use futures::Stream;
+use tokio_core::net::TcpListener as AsyncTcpListener;
+use tokio_core::reactor::Core;
+use tokio_proto::BindServer;
+
+use redbackup_protocol::RedServerProto;
+use chunk_table::integrity_event::{LOST, REPAIRED};
+use schedule::integrity_check::{check_integrity, IntegrityCheckReport};
+use service::NodeService;
+use super::service_utils::ServiceUtils;
+use super::test_data::ExampleChunkContentElement;
+
+/// Serve the chunks of `peer` on a free port, returns the address of the peer.
+fn spawn_peer(peer: NodeService) -> SocketAddr {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+        let addr = "127.0.0.1:0".parse().unwrap();
+        let listener = AsyncTcpListener::bind(&addr, &handle).unwrap();
+        sender.send(listener.local_addr().unwrap()).unwrap();
+        let server = listener.incoming().for_each(|(socket, _)| {
+            let service = NodeService::new(
+                peer.cpu_pool.clone(),
+                peer.chunk_table.clone(),
+                peer.storage.clone(),
+                None,
+            );
+            RedServerProto.bind_server(&handle, socket, service);
+            Ok(())
+        });
+        core.run(server).unwrap();
+    });
+    receiver.recv().unwrap()
+}
+
+/// An address, on which no node listens.
+fn unreachable_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+/// Insert the example chunk with a content, that does not match its identifier.
+fn insert_corrupted_chunk(service: &NodeService) -> String {
+    let mut element = ExampleChunkContentElement::one();
+    element.chunk_content = b"corrupted".to_vec();
+    let identifier = element.chunk_identifier.clone();
+    ServiceUtils::insert_and_verify(service, element);
+    identifier
+}
+
+#[test]
+fn check_integrity_accepts_intact_chunks() {
+    let service = ServiceUtils::service_for_test("check_integrity_accepts_intact_chunks");
+    ServiceUtils::insert_and_verify(&service, ExampleChunkContentElement::one());
+
+    let report = check_integrity(&service.chunk_table, &service.storage, &[]).unwrap();
+
+    assert_eq!(
+        IntegrityCheckReport {
+            checked: 1,
+            repaired: 0,
+            lost: 0,
+            deferred: 0,
+        },
+        report
+    );
+    assert!(service.chunk_table.get_integrity_events().unwrap().is_empty());
+}
+
+#[test]
+fn check_integrity_repairs_chunks_from_peer() {
+    let service = ServiceUtils::service_for_test("check_integrity_repairs_chunks_from_peer");
+    let identifier = insert_corrupted_chunk(&service);
+    let peer = ServiceUtils::service_for_test("check_integrity_repairs_chunks_from_peer_peer");
+    ServiceUtils::insert_and_verify(&peer, ExampleChunkContentElement::one());
+    let peer_addr = spawn_peer(peer);
+
+    let report = check_integrity(&service.chunk_table, &service.storage, &[peer_addr]).unwrap();
+
+    assert_eq!(
+        IntegrityCheckReport {
+            checked: 1,
+            repaired: 1,
+            lost: 0,
+            deferred: 0,
+        },
+        report
+    );
+    assert!(service.storage.verify(&identifier).is_ok());
+    let events = service.chunk_table.get_integrity_events().unwrap();
+    assert_eq!(1, events.len());
+    assert_eq!(REPAIRED, events[0].kind);
+    assert_eq!(Some(peer_addr.to_string()), events[0].detail);
+}
+
+#[test]
+fn check_integrity_marks_chunks_lost_that_no_peer_has() {
+    let service = ServiceUtils::service_for_test("check_integrity_marks_chunks_lost");
+    let identifier = insert_corrupted_chunk(&service);
+    // The peer answers, but does not have the chunk.
+    let peer = ServiceUtils::service_for_test("check_integrity_marks_chunks_lost_peer");
+    let peer_addr = spawn_peer(peer);
+
+    let report = check_integrity(&service.chunk_table, &service.storage, &[peer_addr]).unwrap();
+
+    assert_eq!(
+        IntegrityCheckReport {
+            checked: 1,
+            repaired: 0,
+            lost: 1,
+            deferred: 0,
+        },
+        report
+    );
+    assert!(service.chunk_table.get_chunk(&identifier).is_err());
+    assert!(service.storage.get(&identifier).is_err());
+    assert!(
+        service
+            .storage
+            .location()
+            .join("quarantine")
+            .join(&identifier)
+            .is_file()
+    );
+
+    let events = service.chunk_table.get_integrity_events().unwrap();
+    assert_eq!(1, events.len());
+    assert_eq!(identifier, events[0].chunk_identifier);
+    assert_eq!(LOST, events[0].kind);
+}
+
+#[test]
+fn check_integrity_keeps_chunks_if_a_peer_is_unreachable() {
+    let service = ServiceUtils::service_for_test("check_integrity_keeps_chunks");
+    let identifier = insert_corrupted_chunk(&service);
+    let peer = ServiceUtils::service_for_test("check_integrity_keeps_chunks_peer");
+    let known_nodes = vec![spawn_peer(peer), unreachable_addr()];
+
+    let report = check_integrity(&service.chunk_table, &service.storage, &known_nodes).unwrap();
+
+    assert_eq!(
+        IntegrityCheckReport {
+            checked: 1,
+            repaired: 0,
+            lost: 0,
+            deferred: 1,
+        },
+        report
+    );
+    // The chunk is kept in the chunk table, so a later check tries to repair it again.
+    assert!(service.chunk_table.get_chunk(&identifier).is_ok());
+    assert!(service.chunk_table.get_integrity_events().unwrap().is_empty());
+}
@@ -12,3 +12,9 @@ mod chunk_table;
 
 #[cfg(test)]
 mod service;
+
+#[cfg(test)]
+mod garbage_collection;
+
+#[cfg(test)]
+mod integrity_check;
@@ -1,27 +1,59 @@
 use futures::Future;
 use tokio_service::Service;
-use chrono::Utc;
+use chrono::{Duration, Utc};
 
-use redbackup_protocol::MessageKind;
+use redbackup_protocol::{Message, MessageKind};
 use redbackup_protocol::message::*;
 
 use chunk_table::Chunk;
+use service::NodeService;
 
 use super::test_data::{ExampleChunkContentElement, ExampleChunkElement};
 use super::service_utils::ServiceUtils;
 
-#[test]
-fn allways_give_designation() {
-    let service = ServiceUtils::service_for_test("allways_give_designation");
-    let req_msg = GetDesignation::new(0, Utc::now());
+fn request_designation(service: &NodeService, req_msg: Message) -> bool {
     let res_msg = service.call(req_msg).wait().unwrap();
     if let MessageKind::ReturnDesignation(body) = res_msg.body {
-        assert!(body.designation);
+        body.designation
     } else {
         panic!("Expected ReturnDesignation message!");
     }
 }
 
+#[test]
+fn give_designation() {
+    let service = ServiceUtils::service_for_test("give_designation");
+    let req_msg = GetDesignation::new(1024, Utc::now() + Duration::days(1));
+    assert!(request_designation(&service, req_msg));
+}
+
+#[test]
+fn deny_designation_for_passed_expiration_date() {
+    let service = ServiceUtils::service_for_test("deny_designation_for_passed_expiration_date");
+    let req_msg = GetDesignation::new(1024, Utc::now() - Duration::days(1));
+    assert!(!request_designation(&service, req_msg));
+}
+
+#[test]
+fn deny_designation_exceeding_available_space() {
+    let service = ServiceUtils::service_for_test("deny_designation_exceeding_available_space");
+    let req_msg = GetDesignation::new(u64::max_value(), Utc::now() + Duration::days(1));
+    assert!(!request_designation(&service, req_msg));
+}
+
+#[test]
+fn deny_designation_exceeding_max_capacity() {
+    let mut service = ServiceUtils::service_for_test("deny_designation_exceeding_max_capacity");
+    ServiceUtils::insert_and_verify(&service, ExampleChunkContentElement::one());
+    let used_space = ExampleChunkContentElement::one().chunk_content.len() as u64;
+    service.max_capacity = Some(used_space + 1024);
+
+    let req_msg = GetDesignation::new(1024, Utc::now() + Duration::days(1));
+    assert!(request_designation(&service, req_msg));
+    let req_msg = GetDesignation::new(1025, Utc::now() + Duration::days(1));
+    assert!(!request_designation(&service, req_msg));
+}
+
 #[test]
 fn service_responds_if_wrong_message() {
     let service = ServiceUtils::service_for_test("service_responds_if_wrong_message");
@@ -16,7 +16,7 @@ impl ServiceUtils {
         let chunk_table = ChunkTableUtils::chunk_table_for_test(test_name);
         let storage = Self::storage_for_test(test_name);
         let cpu_pool = CpuPool::new_num_cpus();
-        NodeService::new(cpu_pool, chunk_table, storage)
+        NodeService::new(cpu_pool, chunk_table, storage, None)
     }
 
     pub fn insert_and_verify(service: &NodeService, element: ChunkContentElement) {
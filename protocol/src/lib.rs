@@ -5,6 +5,7 @@
 
 extern crate bytes;
 extern crate chrono;
+extern crate futures;
 extern crate tokio_core;
 extern crate tokio_io;
 extern crate tokio_proto;
@@ -20,12 +21,16 @@ extern crate serde_derive;
 extern crate log;
 
 pub mod message;
+pub mod session;
 
+use std::cmp;
 use std::io;
+use std::mem;
 use std::error::Error;
 
 pub use message::Message;
 pub use message::MessageKind;
+pub use session::Session;
 
 
 use bytes::BytesMut;
@@ -45,7 +50,7 @@ impl<T: AsyncRead + AsyncWrite + 'static> ServerProto<T> for RedServerProto {
     type BindTransport = io::Result<Framed<T, RedCodec>>;
 
     fn bind_transport(&self, io: T) -> io::Result<Framed<T, RedCodec>> {
-        Ok(io.framed(RedCodec {}))
+        Ok(io.framed(RedCodec::new()))
     }
 }
 pub struct RedClientProto;
@@ -57,42 +62,163 @@ impl<T: AsyncRead + AsyncWrite + 'static> ClientProto<T> for RedClientProto {
     type BindTransport = io::Result<Framed<T, RedCodec>>;
 
     fn bind_transport(&self, io: T) -> io::Result<Framed<T, RedCodec>> {
-        Ok(io.framed(RedCodec {}))
+        Ok(io.framed(RedCodec::for_client()))
     }
 }
 
+/// Size of the frame header (payload length as 32 bit big endian integer and flags).
+pub const FRAME_HEADER_SIZE: usize = 5;
+/// Default maximum size of the payload of a single frame.
+pub const MAX_FRAME_SIZE: usize = 1024 * 1024;
+/// Default maximum size of a whole message (the payload of all its frames). As the chunk index
+/// is transferred as a single chunk, this is chosen generously.
+pub const MAX_MESSAGE_SIZE: usize = 1024 * 1024 * 1024;
+/// Flag of the last frame of a message.
+const FLAG_FINAL: u8 = 0x01;
+
 /// The RedCodec struct implements both the encoder and decoder for network messages,
 /// that are exchanged between client and node, and between nodes.
 ///
+/// Every serialised message is sent as a sequence of length-prefixed frames. Each frame holds
+/// at most `max_frame_size` bytes of payload, messages with large chunk contents are therefore
+/// split into several frames. The last frame of a message is marked with a flag.
+///
+/// The frames of a message are collected until the last one is received, as the message can
+/// only be deserialised as a whole. Messages exceeding `max_message_size` are rejected.
+///
 /// The decoder is implemented according to the Tokio traits `Decoder`and `Encoder`.
-pub struct RedCodec;
+/// Invalid frames and messages result in an error, which closes the connection.
+pub struct RedCodec {
+    max_frame_size: usize,
+    max_message_size: usize,
+    /// Payload of the frames of the message that is currently received.
+    partial_message: Vec<u8>,
+    /// Whether the end of the stream is an error, as the requests in flight are not answered.
+    eof_is_error: bool,
+}
+
+impl RedCodec {
+    pub fn new() -> Self {
+        Self::with_limits(MAX_FRAME_SIZE, MAX_MESSAGE_SIZE)
+    }
+
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        Self::with_limits(max_frame_size, MAX_MESSAGE_SIZE)
+    }
+
+    pub fn with_limits(max_frame_size: usize, max_message_size: usize) -> Self {
+        RedCodec {
+            max_frame_size,
+            max_message_size,
+            partial_message: Vec::new(),
+            eof_is_error: false,
+        }
+    }
+
+    /// Create a codec for the client side of a connection. If the node closes the connection,
+    /// the end of the stream is returned as error, so the requests in flight fail instead of
+    /// waiting forever for their responses.
+    pub fn for_client() -> Self {
+        RedCodec {
+            eof_is_error: true,
+            ..Self::new()
+        }
+    }
+}
 
 impl Decoder for RedCodec {
     type Item = Message;
     type Error = io::Error;
 
-    fn decode(&mut self, buf: &mut BytesMut) -> io::Result<Option<Message>> {
-        let len = buf.len();
-        if len == 0 {
-            return Ok(None);
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> io::Result<Option<Message>> {
+        if let Some(message) = self.decode(buf)? {
+            return Ok(Some(message));
         }
-        debug!("Start decoding message");
-        match decode_message(buf) {
-            Err(e) => {
-                debug!(
-                    "Failed decoding message (description: {}, cause: {:?})",
-                    e.description(),
-                    e.cause()
-                );
-                trace!("Buffer content: {:?}", buf);
-                Ok(None)
+        if self.eof_is_error {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "The connection was closed by the peer",
+            ));
+        }
+        if !buf.is_empty() || !self.partial_message.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The connection was closed within a message",
+            ));
+        }
+        Ok(None)
+    }
+
+    fn decode(&mut self, buf: &mut BytesMut) -> io::Result<Option<Message>> {
+        loop {
+            if buf.len() < FRAME_HEADER_SIZE {
+                return Ok(None);
+            }
+            let frame_size = (buf[0] as usize) << 24 | (buf[1] as usize) << 16 |
+                (buf[2] as usize) << 8 | buf[3] as usize;
+            let flags = buf[4];
+
+            if frame_size > self.max_frame_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Frame of {} bytes exceeds the maximum frame size of {} bytes",
+                        frame_size,
+                        self.max_frame_size
+                    ),
+                ));
+            }
+            if self.partial_message.len() + frame_size > self.max_message_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Message exceeds the maximum message size of {} bytes",
+                        self.max_message_size
+                    ),
+                ));
             }
-            Ok(m) => {
-                if m.is_some() {
-                    debug!("Message decoded successfully (to len {})", len);
-                    buf.split_to(len);
-                }
-                Ok(m)
+            if flags & !FLAG_FINAL != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Frame with unknown flags {:#x}", flags),
+                ));
+            }
+            if buf.len() < FRAME_HEADER_SIZE + frame_size {
+                // Wait until the whole frame is received.
+                let missing = FRAME_HEADER_SIZE + frame_size - buf.len();
+                buf.reserve(missing);
+                return Ok(None);
+            }
+
+            buf.split_to(FRAME_HEADER_SIZE);
+            self.partial_message.extend_from_slice(
+                &buf.split_to(frame_size)[..],
+            );
+            trace!("Received frame of {} bytes", frame_size);
+
+            if flags & FLAG_FINAL != 0 {
+                debug!("Start decoding message");
+                let mut message =
+                    BytesMut::from(mem::replace(&mut self.partial_message, Vec::new()));
+                return match decode_message(&mut message) {
+                    Ok(Some(m)) => {
+                        debug!("Message decoded successfully");
+                        Ok(Some(m))
+                    }
+                    Ok(None) => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Received an empty message",
+                    )),
+                    Err(e) => {
+                        debug!(
+                            "Failed decoding message (description: {}, cause: {:?})",
+                            e.description(),
+                            e.cause()
+                        );
+                        trace!("Message content: {:?}", message);
+                        Err(e)
+                    }
+                };
             }
         }
     }
@@ -105,9 +231,90 @@ impl Encoder for RedCodec {
     fn encode(&mut self, msg: Message, buf: &mut BytesMut) -> io::Result<()> {
         debug!("Start encoding message");
         trace!("Message: {:?}", msg);
-        let m = encode_message(msg, buf);
-        debug!("Message encoded successfully");
-        m
+        // The message is serialised directly into the frames, without an intermediate copy.
+        let start = buf.len();
+        let mut writer = FrameWriter::new(buf, self.max_frame_size);
+        let result = msg.serialize(&mut Serializer::new(&mut writer));
+        if let Err(err) = result {
+            writer.buf.truncate(start);
+            return Err(io::Error::new(io::ErrorKind::Other, err));
+        }
+        let number_of_frames = writer.finish();
+        debug!(
+            "Message encoded successfully ({} frames)",
+            number_of_frames
+        );
+        Ok(())
+    }
+}
+
+/// Writes a serialised message into consecutive frames of a buffer.
+///
+/// The header of a frame is written when the frame is full (or the message is finished), as
+/// only then its size and flags are known.
+struct FrameWriter<'a> {
+    buf: &'a mut BytesMut,
+    max_frame_size: usize,
+    /// Position of the header of the current frame in the buffer.
+    header: usize,
+    frames: usize,
+}
+
+impl<'a> FrameWriter<'a> {
+    fn new(buf: &'a mut BytesMut, max_frame_size: usize) -> Self {
+        let mut writer = FrameWriter {
+            buf,
+            max_frame_size,
+            header: 0,
+            frames: 0,
+        };
+        writer.start_frame();
+        writer
+    }
+
+    fn start_frame(&mut self) {
+        self.header = self.buf.len();
+        self.buf.extend_from_slice(&[0; FRAME_HEADER_SIZE]);
+        self.frames += 1;
+    }
+
+    fn frame_size(&self) -> usize {
+        self.buf.len() - self.header - FRAME_HEADER_SIZE
+    }
+
+    fn close_frame(&mut self, flags: u8) {
+        let frame_size = self.frame_size() as u32;
+        self.buf[self.header..self.header + FRAME_HEADER_SIZE].copy_from_slice(
+            &[
+                (frame_size >> 24) as u8,
+                (frame_size >> 16) as u8,
+                (frame_size >> 8) as u8,
+                frame_size as u8,
+                flags,
+            ],
+        );
+    }
+
+    /// Mark the current frame as the last one and return the number of frames.
+    fn finish(mut self) -> usize {
+        self.close_frame(FLAG_FINAL);
+        self.frames
+    }
+}
+
+impl<'a> io::Write for FrameWriter<'a> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.frame_size() >= self.max_frame_size {
+            self.close_frame(0);
+            self.start_frame();
+        }
+        let len = cmp::min(data.len(), self.max_frame_size - self.frame_size());
+        self.buf.extend_from_slice(&data[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
@@ -131,7 +338,12 @@ mod tests {
     use super::message::ReturnDesignation;
     use chrono::{Utc, TimeZone};
     use bytes::BufMut;
+    use futures::Stream;
     use std::error::Error;
+    use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use tokio_proto::BindServer;
 
     #[test]
     fn decode_broken_incomming_message() {
@@ -279,4 +491,249 @@ mod tests {
         assert_eq!(err.kind(), io::ErrorKind::Other);
         assert_eq!(err.description(), "error while decoding value");
     }
+
+    fn example_message() -> Message {
+        Message {
+            timestamp: Utc.ymd(2014, 11, 28).and_hms_milli(7, 8, 9, 10),
+            body: MessageKind::ReturnDesignation(ReturnDesignation { designation: false }),
+        }
+    }
+
+    #[test]
+    fn codec_roundtrip() {
+        let mut codec = RedCodec::new();
+        let mut buf = BytesMut::with_capacity(1024);
+        codec.encode(example_message(), &mut buf).unwrap();
+        // Header of a single final frame with 31 bytes of payload.
+        assert_eq!(&buf[..FRAME_HEADER_SIZE], &[0, 0, 0, 31, 1]);
+
+        let actual = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(actual, example_message());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn codec_splits_message_into_frames() {
+        let mut codec = RedCodec::with_max_frame_size(8);
+        let mut buf = BytesMut::with_capacity(1024);
+        codec.encode(example_message(), &mut buf).unwrap();
+        assert_eq!(buf.len(), 31 + 4 * FRAME_HEADER_SIZE);
+
+        let actual = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(actual, example_message());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn codec_waits_for_incomplete_frames() {
+        let mut codec = RedCodec::with_max_frame_size(8);
+        let mut encoded = BytesMut::with_capacity(1024);
+        codec.encode(example_message(), &mut encoded).unwrap();
+
+        let mut buf = BytesMut::with_capacity(1024);
+        let last = encoded.len() - 1;
+        for byte in encoded[..last].iter() {
+            buf.put_u8(*byte);
+            assert!(codec.decode(&mut buf).unwrap().is_none());
+        }
+        buf.put_u8(encoded[last]);
+        let actual = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(actual, example_message());
+    }
+
+    #[test]
+    fn codec_rejects_oversized_frame() {
+        let mut codec = RedCodec::with_max_frame_size(8);
+        let mut buf = BytesMut::with_capacity(1024);
+        buf.put(&[0, 0, 0, 9, 1][..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn codec_rejects_oversized_message() {
+        let mut codec = RedCodec::with_limits(8, 12);
+        let mut buf = BytesMut::with_capacity(1024);
+        buf.put(&[0, 0, 0, 8, 0][..]);
+        buf.put(&[0; 8][..]);
+        buf.put(&[0, 0, 0, 8, 0][..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn codec_for_client_fails_at_end_of_stream() {
+        let mut buf = BytesMut::with_capacity(1024);
+        assert!(RedCodec::new().decode_eof(&mut buf).unwrap().is_none());
+        let err = RedCodec::for_client().decode_eof(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+
+        // A message, that was received before, is still returned.
+        RedCodec::new().encode(example_message(), &mut buf).unwrap();
+        let actual = RedCodec::for_client().decode_eof(&mut buf).unwrap().unwrap();
+        assert_eq!(actual, example_message());
+
+        buf.put(&[0, 0, 0, 8, 0][..]);
+        let err = RedCodec::new().decode_eof(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn codec_rejects_broken_message() {
+        let mut codec = RedCodec::new();
+        let mut buf = BytesMut::with_capacity(1024);
+        buf.put(&[0, 0, 0, 1, 1, 0][..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+
+    struct EchoService;
+
+    impl tokio_service::Service for EchoService {
+        type Request = Message;
+        type Response = Message;
+        type Error = io::Error;
+        type Future = futures::future::FutureResult<Message, io::Error>;
+
+        fn call(&self, request: Message) -> Self::Future {
+            futures::future::ok(request)
+        }
+    }
+
+    /// Start a server answering with the received messages, returns its address.
+    fn spawn_echo_server() -> std::net::SocketAddr {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut core = tokio_core::reactor::Core::new().unwrap();
+            let handle = core.handle();
+            let addr = "127.0.0.1:0".parse().unwrap();
+            let listener = tokio_core::net::TcpListener::bind(&addr, &handle).unwrap();
+            sender.send(listener.local_addr().unwrap()).unwrap();
+            let server = listener.incoming().for_each(|(socket, _)| {
+                RedServerProto.bind_server(&handle, socket, EchoService);
+                Ok(())
+            });
+            core.run(server).unwrap();
+        });
+        receiver.recv().unwrap()
+    }
+
+    /// Start a proxy to the server, whose connections can be killed. Returns its address and the
+    /// open connections.
+    fn spawn_proxy(server: SocketAddr) -> (SocketAddr, Arc<Mutex<Vec<TcpStream>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(Mutex::new(Vec::new()));
+        let open_connections = connections.clone();
+        thread::spawn(move || for client in listener.incoming() {
+            let mut client = client.unwrap();
+            let mut upstream = TcpStream::connect(server).unwrap();
+            let mut client_reader = client.try_clone().unwrap();
+            let mut upstream_reader = upstream.try_clone().unwrap();
+            {
+                let mut connections = open_connections.lock().unwrap();
+                connections.push(client.try_clone().unwrap());
+                connections.push(upstream.try_clone().unwrap());
+            }
+            thread::spawn(move || io::copy(&mut client_reader, &mut upstream));
+            thread::spawn(move || io::copy(&mut upstream_reader, &mut client));
+        });
+        (addr, connections)
+    }
+
+    fn kill_connections(connections: &Mutex<Vec<TcpStream>>) {
+        for connection in connections.lock().unwrap().drain(..) {
+            let _ = connection.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn chunk_identifiers(response: Message) -> Vec<String> {
+        match response.body {
+            MessageKind::GetChunks(body) => body.chunk_identifiers,
+            _ => panic!("Unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn session_reconnects_when_connection_drops() {
+        let (addr, connections) = spawn_proxy(spawn_echo_server());
+
+        let mut session = Session::new(addr).unwrap();
+        let response = session.call(example_message()).unwrap();
+        assert_eq!(response, example_message());
+
+        // The first request on the dropped connection fails, and is sent again.
+        kill_connections(&connections);
+        let response = session.call(example_message()).unwrap();
+        assert_eq!(response, example_message());
+
+        // The requests, whose responses were not handled yet, are sent again.
+        let mut received = Vec::new();
+        session
+            .call_window(
+                6,
+                |index| Ok(message::GetChunks::new(vec![index.to_string()])),
+                2,
+                |response| -> Result<(), io::Error> {
+                    received.push(chunk_identifiers(response));
+                    if received.len() == 3 {
+                        kill_connections(&connections);
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+        let expected: Vec<Vec<String>> = (0..6).map(|index| vec![index.to_string()]).collect();
+        assert_eq!(received, expected);
+    }
+
+    #[test]
+    fn session_pipelines_requests_on_one_connection() {
+        let addr = spawn_echo_server();
+
+        let mut session = Session::new(addr).unwrap();
+        let messages = vec![
+            message::GetChunks::new(vec!["a".into()]),
+            message::GetChunks::new(vec!["b".into()]),
+            message::GetChunks::new(vec!["c".into()]),
+        ];
+        let responses = session.call_all(messages.clone()).unwrap();
+        assert_eq!(responses, messages);
+
+        let response = session.call(example_message()).unwrap();
+        assert_eq!(response, example_message());
+    }
+
+    #[test]
+    fn session_keeps_window_of_requests_in_flight() {
+        let addr = spawn_echo_server();
+
+        let mut session = Session::new(addr).unwrap();
+        let identifiers: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let mut responses = Vec::new();
+        session
+            .call_window(
+                identifiers.len(),
+                |index| Ok(message::GetChunks::new(vec![identifiers[index].clone()])),
+                3,
+                |response| -> Result<(), io::Error> {
+                    responses.push(response);
+                    Ok(())
+                },
+            )
+            .unwrap();
+
+        let received: Vec<Vec<String>> = responses
+            .into_iter()
+            .map(|response| match response.body {
+                MessageKind::GetChunks(body) => body.chunk_identifiers,
+                _ => panic!("Unexpected response {:?}", response),
+            })
+            .collect();
+        let expected: Vec<Vec<String>> = identifiers
+            .into_iter()
+            .map(|identifier| vec![identifier])
+            .collect();
+        assert_eq!(received, expected);
+    }
 }
@@ -5,14 +5,14 @@ use chrono::{DateTime, Utc};
 use serde_bytes;
 
 /// A message, that is sent over the network
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Message {
     pub timestamp: DateTime<Utc>,
     pub body: MessageKind,
 }
 
 /// The kind of a message
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum MessageKind {
     GetDesignation(GetDesignation),
     ReturnDesignation(ReturnDesignation),
@@ -28,7 +28,7 @@ pub enum MessageKind {
     ReturnChunks(ReturnChunks),
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GetDesignation {
     pub estimate_size: u64,
     pub expiration_date: DateTime<Utc>,
@@ -46,7 +46,7 @@ impl GetDesignation {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ReturnDesignation {
     pub designation: bool,
 }
@@ -60,7 +60,7 @@ impl ReturnDesignation {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct InvalidRequest {
     pub reason: String,
 }
@@ -74,7 +74,7 @@ impl InvalidRequest {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct InternalError {
     pub reason: String,
 }
@@ -96,7 +96,7 @@ pub struct ChunkElement {
 }
 
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GetChunkStates {
     pub chunks: Vec<ChunkElement>,
 }
@@ -110,7 +110,7 @@ impl GetChunkStates {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ReturnChunkStates {
     pub chunks: Vec<ChunkElement>,
 }
@@ -125,7 +125,7 @@ impl ReturnChunkStates {
 }
 
 /// A chunk content element according to specification.
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ChunkContentElement {
     #[serde(with = "serde_bytes")]
     pub chunk_content: Vec<u8>,
@@ -145,7 +145,7 @@ impl Into<ChunkElement> for ChunkContentElement {
 }
 
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PostChunks {
     pub chunks: Vec<ChunkContentElement>,
 }
@@ -159,7 +159,7 @@ impl PostChunks {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GetRootHandles {}
 
 impl GetRootHandles {
@@ -171,7 +171,7 @@ impl GetRootHandles {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ReturnRootHandles {
     pub root_handle_chunks: Vec<ChunkContentElement>,
 }
@@ -186,7 +186,7 @@ impl ReturnRootHandles {
 }
 
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GetChunks {
     pub chunk_identifiers: Vec<String>,
 }
@@ -200,7 +200,7 @@ impl GetChunks {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ReturnChunks {
     pub chunks: Vec<ChunkContentElement>,
 }
@@ -214,7 +214,7 @@ impl ReturnChunks {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct AcknowledgeChunks {
     pub chunks: Vec<ChunkElement>,
 }
@@ -0,0 +1,172 @@
+//! A session keeps a connection to a node open for many requests.
+
+use std::cmp;
+use std::io;
+use std::net::SocketAddr;
+
+use futures::{stream, Future, Stream};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Core;
+use tokio_proto::TcpClient;
+use tokio_proto::pipeline::ClientService;
+use tokio_service::Service;
+
+use super::{Message, RedClientProto};
+
+/// Number of attempts to connect to a node, before the error is returned to the caller.
+const MAX_ATTEMPTS: usize = 2;
+
+/// A connection to a node, that is reused for all requests of a session.
+///
+/// Requests are pipelined on the connection. If the connection drops, the session reconnects
+/// and sends the requests again, whose responses were not received yet. A request is only sent
+/// again once, if it fails on the new connection as well, the error is returned to the caller.
+pub struct Session {
+    addr: SocketAddr,
+    event_loop: Core,
+    client: Option<ClientService<TcpStream, RedClientProto>>,
+}
+
+/// Error of a window of requests: either the connection broke, or the caller failed.
+enum WindowError<E> {
+    Connection(io::Error),
+    Caller(E),
+}
+
+impl Session {
+    /// Create a new session. The connection is established with the first request.
+    pub fn new(addr: SocketAddr) -> Result<Self, io::Error> {
+        Ok(Session {
+            addr,
+            event_loop: Core::new()?,
+            client: None,
+        })
+    }
+
+    pub fn addr(&self) -> &SocketAddr {
+        &self.addr
+    }
+
+    /// Send a message to the node and wait for the response.
+    pub fn call(&mut self, message: Message) -> Result<Message, io::Error> {
+        let mut responses = self.call_all(vec![message])?;
+        responses.pop().ok_or(io::Error::new(
+            io::ErrorKind::Other,
+            "No response received from node",
+        ))
+    }
+
+    /// Send all messages pipelined on the connection and wait for all responses.
+    /// The responses are returned in the same order as the messages.
+    ///
+    /// Every message is copied when it is sent, so it can be sent again on a new connection.
+    pub fn call_all(&mut self, messages: Vec<Message>) -> Result<Vec<Message>, io::Error> {
+        let mut responses = Vec::with_capacity(messages.len());
+        self.call_window(
+            messages.len(),
+            |index| Ok(messages[index].clone()),
+            messages.len(),
+            |response| -> Result<(), io::Error> {
+                responses.push(response);
+                Ok(())
+            },
+        )?;
+        Ok(responses)
+    }
+
+    /// Send `count` messages pipelined on the connection, with at most `window` requests in
+    /// flight, and pass the responses to `handle` in the same order as the messages.
+    ///
+    /// The message with an index (from 0 to `count`) is built by `message`, when there is room
+    /// in the window, so the messages are not all held in memory at the same time. If the
+    /// connection drops, the messages are built again, starting from the first message whose
+    /// response was not handled yet.
+    pub fn call_window<M, F, E>(
+        &mut self,
+        count: usize,
+        mut message: M,
+        window: usize,
+        mut handle: F,
+    ) -> Result<(), E>
+    where
+        M: FnMut(usize) -> Result<Message, E>,
+        F: FnMut(Message) -> Result<(), E>,
+        E: From<io::Error>,
+    {
+        let mut next = 0;
+        let mut reconnected = false;
+        while next < count {
+            let client = self.connect()?;
+            let mut handled = 0;
+            let result = {
+                let requests = (next..count).map(|index| {
+                    message(index).map_err(WindowError::Caller)
+                });
+                let responses = stream::iter_result(requests)
+                    .map(move |message| client.call(message).map_err(WindowError::Connection))
+                    .buffered(cmp::max(window, 1));
+                self.event_loop.run(responses.for_each(|response| {
+                    handled += 1;
+                    handle(response).map_err(WindowError::Caller)
+                }))
+            };
+            next += handled;
+            match result {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    // The connection is not reused after an error.
+                    self.client = None;
+                    match err {
+                        WindowError::Caller(err) => return Err(err),
+                        // The requests are only sent again once, unless any response was
+                        // received on the new connection.
+                        WindowError::Connection(err) => {
+                            if reconnected && handled == 0 {
+                                return Err(err.into());
+                            }
+                            warn!(
+                                "Connection to node {} failed ({}), will reconnect",
+                                self.addr,
+                                err
+                            );
+                            reconnected = true;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the connected client, connect to the node if there is no open connection.
+    fn connect(&mut self) -> Result<ClientService<TcpStream, RedClientProto>, io::Error> {
+        if let Some(ref client) = self.client {
+            return Ok(client.clone());
+        }
+        let mut attempt = 1;
+        loop {
+            debug!("Connect to node {}", self.addr);
+            let handle = self.event_loop.handle();
+            let result = self.event_loop.run(
+                TcpClient::new(RedClientProto).connect(&self.addr, &handle),
+            );
+            match result {
+                Ok(client) => {
+                    self.client = Some(client.clone());
+                    return Ok(client);
+                }
+                Err(err) => {
+                    if attempt >= MAX_ATTEMPTS {
+                        return Err(err);
+                    }
+                    warn!(
+                        "Connection to node {} failed ({}), will try again",
+                        self.addr,
+                        err
+                    );
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ extern crate log;
 #[macro_use]
 extern crate quick_error;
 extern crate sha2;
+extern crate fs2;
 
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -11,6 +12,9 @@ use std::io::Read;
 use std::io::Write;
 use sha2::{Sha256, Digest};
 
+/// Name of the folder in the storage, where corrupted chunks are kept.
+const QUARANTINE_FOLDER: &'static str = "quarantine";
+
 quick_error! {
     #[derive(Debug)]
     pub enum StorageError {
@@ -107,6 +111,15 @@ impl Storage {
         std::fs::remove_file(path).map_err(|e| StorageError::from(e))
     }
 
+    /// Get the size of the chunk content of specified chunk identifier in bytes.
+    pub fn size(&self, identifier: &str) -> Result<u64, StorageError> {
+        let path = self.filename_for_identifier(identifier);
+        if !path.exists() {
+            return Err(StorageError::GetNonExistingChunk(identifier.into()));
+        }
+        Ok(fs::metadata(path)?.len())
+    }
+
     /// Verify, that hashed chunk content and identifier are identical.
     pub fn verify(&self, identifier: &str) -> Result<(), StorageError> {
         let path = self.filename_for_identifier(identifier);
@@ -140,6 +153,53 @@ impl Storage {
         Ok(())
     }
 
+    /// Move a (corrupted) chunk out of the storage into the quarantine folder, where it is kept for
+    /// inspection. A file of the same chunk, that was quarantined before, is replaced.
+    pub fn quarantine(&self, identifier: &str) -> Result<PathBuf, StorageError> {
+        let path = self.filename_for_identifier(identifier);
+        if !path.exists() {
+            return Err(StorageError::GetNonExistingChunk(identifier.into()));
+        }
+        let quarantine_location = self.location().join(QUARANTINE_FOLDER);
+        fs::create_dir_all(&quarantine_location)?;
+        let quarantine_path = quarantine_location.join(identifier);
+        debug!(
+            "Quarantine chunk with identifier {} at {:?}",
+            identifier,
+            quarantine_path
+        );
+        fs::rename(path, &quarantine_path)?;
+        Ok(quarantine_path)
+    }
+
+    /// Get the space in bytes, that is available to the storage on its file system.
+    pub fn available_space(&self) -> Result<u64, StorageError> {
+        fs2::available_space(&self.location).map_err(|e| StorageError::from(e))
+    }
+
+    /// Get the space in bytes, that is used by all chunks in the storage (including the
+    /// quarantined chunks).
+    pub fn used_space(&self) -> Result<u64, StorageError> {
+        let mut used_space = Self::used_space_of(&self.location)?;
+        let quarantine_location = self.location().join(QUARANTINE_FOLDER);
+        if quarantine_location.is_dir() {
+            used_space += Self::used_space_of(&quarantine_location)?;
+        }
+        Ok(used_space)
+    }
+
+    /// Get the space in bytes, that is used by the files in a folder.
+    fn used_space_of(location: &Path) -> Result<u64, StorageError> {
+        let mut used_space = 0;
+        for entry in fs::read_dir(location)? {
+            let metadata = entry?.metadata()?;
+            if metadata.is_file() {
+                used_space += metadata.len();
+            }
+        }
+        Ok(used_space)
+    }
+
     pub fn location(&self) -> &Path {
         self.location.as_path()
     }
@@ -121,3 +121,45 @@ fn ensure_corrupted_chunk_is_detected() {
         )
     );
 }
+
+#[test]
+fn quarantine_chunk() {
+    let storage = _setup_empty_storage("quarantine_chunk");
+    let identifier = "5561330f1959d3e0491b1c4b2133b453f8ff545436346c0698a9cf9898d90be3";
+    let data = _read_data("tests/data/lorem.txt");
+    storage.persist(identifier, &data).unwrap();
+
+    let quarantine_path = storage.quarantine(identifier).unwrap();
+    assert_eq!(storage.location().join("quarantine").join(identifier), quarantine_path);
+    assert_eq!(data, _read_data(quarantine_path.to_str().unwrap()));
+    assert!(storage.get(identifier).is_err());
+    // Quarantined chunks still use space.
+    assert_eq!(data.len() as u64, storage.used_space().unwrap());
+    assert!(storage.quarantine(identifier).is_err());
+
+    // The chunk can be persisted again (e.g. from a replica).
+    storage.persist(identifier, &data).unwrap();
+    assert!(storage.quarantine(identifier).is_ok());
+}
+
+#[test]
+fn get_size_of_chunk() {
+    let storage = _setup_empty_storage("get_size_of_chunk");
+    let data = _read_data("tests/data/lorem.txt");
+    let identifier = "c1fcd4dd4dc0ee9208d7b9c6608b91bde8eee91b09bc5b4928b9371d5bdab16d";
+    storage.persist(identifier, &data).unwrap();
+    assert_eq!(data.len() as u64, storage.size(identifier).unwrap());
+    assert!(storage.size("non-existing").is_err());
+}
+
+#[test]
+fn get_used_and_available_space() {
+    let storage = _setup_empty_storage("get_used_and_available_space");
+    assert_eq!(0, storage.used_space().unwrap());
+    assert!(storage.available_space().unwrap() > 0);
+
+    let data = _read_data("tests/data/lorem.txt");
+    let identifier = "c1fcd4dd4dc0ee9208d7b9c6608b91bde8eee91b09bc5b4928b9371d5bdab16d";
+    storage.persist(identifier, &data).unwrap();
+    assert_eq!(data.len() as u64, storage.used_space().unwrap());
+}